@@ -0,0 +1,60 @@
+use anchor_lang::{prelude::Pubkey, AccountDeserialize};
+use solana_client::rpc_client::RpcClient;
+
+use crate::{
+    pda, CheckerPanel, DealReceipt, DealState, FeeSchedule, HolderStake, ProgramConfig, Result,
+};
+
+/// Decodes an anchor account, discriminator included
+pub fn decode<T: AccountDeserialize>(mut data: &[u8]) -> Result<T> {
+    Ok(T::try_deserialize(&mut data)?)
+}
+
+pub fn decode_deal_state(data: &[u8]) -> Result<DealState> {
+    decode(data)
+}
+
+pub fn fetch<T: AccountDeserialize>(rpc: &RpcClient, address: &Pubkey) -> Result<T> {
+    decode(&rpc.get_account_data(address)?)
+}
+
+pub fn fetch_deal_state(rpc: &RpcClient, address: &Pubkey) -> Result<DealState> {
+    fetch(rpc, address)
+}
+
+/// Fetches the deal by its id and parties, `None` once the deal is settled and closed
+pub fn fetch_deal(
+    rpc: &RpcClient,
+    deal_id: &[u8; 16],
+    client: &Pubkey,
+    executor: &Pubkey,
+) -> Result<Option<DealState>> {
+    let (address, _) = pda::deal_state(deal_id, client, executor);
+    rpc.get_account_with_commitment(&address, rpc.commitment())?
+        .value
+        .map(|account| decode_deal_state(&account.data))
+        .transpose()
+}
+
+pub fn fetch_config(rpc: &RpcClient) -> Result<ProgramConfig> {
+    fetch(rpc, &pda::config().0)
+}
+
+/// The panel of a deal with `with_panel`, needed to pay its members on settlement
+pub fn fetch_checker_panel(rpc: &RpcClient, deal_state: &Pubkey) -> Result<CheckerPanel> {
+    fetch(rpc, &pda::checker_panel(deal_state).0)
+}
+
+/// The service fee schedule of a deal mint, to preview the fee `initialize` charges
+pub fn fetch_fee_schedule(rpc: &RpcClient, mint: &Pubkey) -> Result<FeeSchedule> {
+    fetch(rpc, &pda::fee_schedule(mint).0)
+}
+
+/// The stake a client's holder mode deals reference, with the count of the active ones
+pub fn fetch_holder_stake(rpc: &RpcClient, owner: &Pubkey) -> Result<HolderStake> {
+    fetch(rpc, &pda::holder_stake(owner).0)
+}
+
+pub fn fetch_deal_receipt(rpc: &RpcClient, deal_state: &Pubkey) -> Result<DealReceipt> {
+    fetch(rpc, &pda::deal_receipt(deal_state).0)
+}
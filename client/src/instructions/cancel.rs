@@ -0,0 +1,134 @@
+use anchor_lang::{
+    prelude::Pubkey, solana_program::instruction::Instruction, system_program, InstructionData,
+    ToAccountMetas,
+};
+use anchor_spl::associated_token;
+
+use super::{panel_fee_accounts, DealAccounts, Mints};
+use crate::{pda, CheckerPanel, DealState, PanelVote, ProgramConfig, TokenMint};
+
+/// Builds `cancel` for a fetched deal. The initializer is any party or the service account.
+pub struct CancelBuilder<'a> {
+    deal: &'a DealState,
+    config: &'a ProgramConfig,
+    initializer: Pubkey,
+    payer: Pubkey,
+    mints: Mints,
+    checker_panel: Option<&'a CheckerPanel>,
+    with_receipt: bool,
+    mutual: bool,
+}
+
+impl<'a> CancelBuilder<'a> {
+    pub fn new(deal: &'a DealState, config: &'a ProgramConfig, initializer: Pubkey) -> Self {
+        Self {
+            deal,
+            config,
+            initializer,
+            payer: initializer,
+            mints: Mints::default(),
+            checker_panel: None,
+            with_receipt: false,
+            mutual: false,
+        }
+    }
+
+    pub fn payer(mut self, payer: Pubkey) -> Self {
+        self.payer = payer;
+        self
+    }
+
+    /// Registers the token program of a Token-2022 mint used by the deal
+    pub fn mint(mut self, mint: TokenMint) -> Self {
+        self.mints.add(mint);
+        self
+    }
+
+    /// The fetched panel of a deal with `with_panel`, the members who voted are paid the checker fee
+    pub fn checker_panel(mut self, checker_panel: &'a CheckerPanel) -> Self {
+        self.checker_panel = Some(checker_panel);
+        self
+    }
+
+    /// Keeps a `DealReceipt` after the deal is closed
+    pub fn with_receipt(mut self) -> Self {
+        self.with_receipt = true;
+        self
+    }
+
+    /// Builds `mutual_cancel` instead, the client and the executor must both sign
+    pub fn mutual(mut self) -> Self {
+        self.mutual = true;
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let deal = DealAccounts::new(self.deal, &self.mints);
+
+        let checker = deal.checker.unwrap_or(self.initializer);
+
+        let accounts = deal_contract::accounts::Cancel {
+            initializer: self.initializer,
+            checker,
+            client: self.deal.client_key,
+            executor: self.deal.executor_key,
+            payer: self.payer,
+            deal_state_deal_ta: deal.deal_state_deal_ta,
+            client_deal_ta: deal.client_deal_ta,
+            checker_deal_ta: deal.deal_mint.token_account(&checker),
+            client_bond_ta: deal.client_bond_ta,
+            executor_bond_ta: deal.executor_bond_ta,
+            client_executor_bond_ta: deal.client_executor_bond_ta,
+            deal_state_client_bond_ta: deal.deal_state_client_bond_ta,
+            deal_state_executor_bond_ta: deal.deal_state_executor_bond_ta,
+            deal_mint: deal.deal_mint.address,
+            client_bond_mint: deal.client_bond_mint,
+            executor_bond_mint: deal.executor_bond_mint,
+            service_fee: self.config.service_fee_owner,
+            deal_state: deal.deal_state,
+            deal_milestones: self
+                .deal
+                .with_milestones
+                .then(|| pda::deal_milestones(&deal.deal_state).0),
+            checker_panel: self
+                .deal
+                .with_panel
+                .then(|| pda::checker_panel(&deal.deal_state).0),
+            deal_receipt: self.with_receipt.then(|| pda::deal_receipt(&deal.deal_state).0),
+            holder_stake: deal.holder_stake,
+            config: pda::config().0,
+            associated_token_program: associated_token::ID,
+            token_program: anchor_spl::token::ID,
+            token_2022_program: anchor_spl::token_2022::ID,
+            system_program: system_program::ID,
+        };
+
+        let mut accounts = accounts.to_account_metas(None);
+        if let Some(checker_panel) = self.checker_panel {
+            accounts.extend(panel_fee_accounts(
+                self.deal,
+                &deal.deal_mint,
+                checker_panel,
+                &self.initializer,
+                PanelVote::Cancel,
+            ));
+        }
+        if self.mutual {
+            for meta in accounts.iter_mut() {
+                if meta.pubkey == self.deal.client_key || meta.pubkey == self.deal.executor_key {
+                    meta.is_signer = true;
+                }
+            }
+        }
+
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts,
+            data: if self.mutual {
+                deal_contract::instruction::MutualCancel {}.data()
+            } else {
+                deal_contract::instruction::Cancel {}.data()
+            },
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use anchor_lang::{
+    prelude::Pubkey, solana_program::instruction::Instruction, system_program, InstructionData,
+    ToAccountMetas,
+};
+use anchor_spl::associated_token;
+use deal_contract::InitializeArgs;
+
+use crate::{pda, BondForfeiture, CheckerPanelArgs, MilestoneArgs, ProgramConfig, TokenMint};
+
+/// Builds `initialize`. The client, the executor and the checker (if any) must sign.
+pub struct InitializeBuilder {
+    deal_id: [u8; 16],
+    client: Pubkey,
+    executor: Pubkey,
+    payer: Pubkey,
+    deal_mint: TokenMint,
+    deal_amount: u64,
+
+    service_fee: Option<u64>,
+    service_fee_owner: Pubkey,
+    holder_mode: bool,
+
+    deadline_ts: Option<i64>,
+    checker: Option<(Pubkey, u64)>,
+    checker_panel: Option<(CheckerPanelArgs, u64)>,
+    client_bond: Option<(TokenMint, u64)>,
+    executor_bond: Option<(TokenMint, u64)>,
+    forfeiture: BondForfeiture,
+    advance_payment_amount: u64,
+    milestones: Vec<MilestoneArgs>,
+}
+
+impl InitializeBuilder {
+    pub fn new(
+        config: &ProgramConfig,
+        deal_id: [u8; 16],
+        client: Pubkey,
+        executor: Pubkey,
+        deal_mint: TokenMint,
+        deal_amount: u64,
+    ) -> Self {
+        Self {
+            deal_id,
+            client,
+            executor,
+            payer: client,
+            deal_mint,
+            deal_amount,
+            service_fee: None,
+            service_fee_owner: config.service_fee_owner,
+            holder_mode: false,
+            deadline_ts: None,
+            checker: None,
+            checker_panel: None,
+            client_bond: None,
+            executor_bond: None,
+            forfeiture: BondForfeiture::default(),
+            advance_payment_amount: 0,
+            milestones: vec![],
+        }
+    }
+
+    pub fn payer(mut self, payer: Pubkey) -> Self {
+        self.payer = payer;
+        self
+    }
+
+    /// The program computes the fee from the fee schedule of the deal mint,
+    /// the deal is rejected if it exceeds `max_service_fee`
+    pub fn max_service_fee(mut self, max_service_fee: u64) -> Self {
+        self.service_fee = Some(max_service_fee);
+        self
+    }
+
+    /// References the holder stake of the client, the largest config tier it covers discounts the service fee
+    pub fn holder_mode(mut self) -> Self {
+        self.holder_mode = true;
+        self
+    }
+
+    pub fn deadline(mut self, deadline_ts: i64) -> Self {
+        self.deadline_ts = Some(deadline_ts);
+        self
+    }
+
+    pub fn checker(mut self, checker: Pubkey, checker_fee: u64) -> Self {
+        self.checker_panel = None;
+        self.checker = Some((checker, checker_fee));
+        self
+    }
+
+    /// Replaces the single checker with a panel, whose members do not sign the deal
+    pub fn checker_panel(mut self, checkers: Vec<Pubkey>, threshold: u8, checker_fee: u64) -> Self {
+        self.checker = None;
+        self.checker_panel = Some((CheckerPanelArgs { checkers, threshold }, checker_fee));
+        self
+    }
+
+    pub fn client_bond(mut self, mint: TokenMint, amount: u64) -> Self {
+        self.client_bond = Some((mint, amount));
+        self
+    }
+
+    pub fn executor_bond(mut self, mint: TokenMint, amount: u64) -> Self {
+        self.executor_bond = Some((mint, amount));
+        self
+    }
+
+    /// Bonds are returned to their owners unless a forfeiture rule applies
+    pub fn forfeiture(mut self, forfeiture: BondForfeiture) -> Self {
+        self.forfeiture = forfeiture;
+        self
+    }
+
+    pub fn advance_payment(mut self, amount: u64) -> Self {
+        self.advance_payment_amount = amount;
+        self
+    }
+
+    pub fn milestones(mut self, milestones: Vec<MilestoneArgs>) -> Self {
+        self.milestones = milestones;
+        self
+    }
+
+    pub fn deal_state(&self) -> Pubkey {
+        pda::deal_state(&self.deal_id, &self.client, &self.executor).0
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let deal_state = self.deal_state();
+
+        let client_deal_ta = self.deal_mint.token_account(&self.client);
+        let executor_deal_ta = self.deal_mint.token_account(&self.executor);
+        let deal_state_deal_ta = self.deal_mint.token_account(&deal_state);
+
+        // unused accounts are filled with the deal ones, the program skips them
+        let (client_bond_mint, client_bond_ta, deal_state_client_bond_ta) = match self.client_bond {
+            Some((mint, _)) => (
+                mint.address,
+                mint.token_account(&self.client),
+                pda::client_bond_vault(&deal_state, &mint.address),
+            ),
+            None => (self.deal_mint.address, client_deal_ta, deal_state_deal_ta),
+        };
+        let (executor_bond_mint, executor_bond_ta, deal_state_executor_bond_ta) =
+            match self.executor_bond {
+                Some((mint, _)) => (
+                    mint.address,
+                    mint.token_account(&self.executor),
+                    pda::executor_bond_vault(&deal_state, &mint.address),
+                ),
+                None => (self.deal_mint.address, executor_deal_ta, deal_state_deal_ta),
+            };
+
+        let accounts = deal_contract::accounts::Initialize {
+            client: self.client,
+            executor: self.executor,
+            payer: self.payer,
+            checker: self.checker.map_or(self.payer, |(checker, _)| checker),
+            deal_mint: self.deal_mint.address,
+            client_bond_mint,
+            executor_bond_mint,
+            service_fee_owner: self.service_fee_owner,
+            service_fee_ta: self.deal_mint.token_account(&self.service_fee_owner),
+            client_deal_ta,
+            executor_deal_ta,
+            deal_state_deal_ta,
+            client_bond_ta,
+            executor_bond_ta,
+            deal_state_client_bond_ta,
+            deal_state_executor_bond_ta,
+            deal_state,
+            holder_stake: self.holder_mode.then(|| pda::holder_stake(&self.client).0),
+            deal_milestones: (!self.milestones.is_empty())
+                .then(|| pda::deal_milestones(&deal_state).0),
+            checker_panel: self
+                .checker_panel
+                .is_some()
+                .then(|| pda::checker_panel(&deal_state).0),
+            config: pda::config().0,
+            fee_schedule: pda::fee_schedule(&self.deal_mint.address).0,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            token_2022_program: anchor_spl::token_2022::ID,
+            associated_token_program: associated_token::ID,
+        };
+
+        let args = InitializeArgs {
+            id: self.deal_id,
+            deal_amount: self.deal_amount,
+            service_fee: self.service_fee,
+            deadline_ts: self.deadline_ts,
+            holder_mode: self.holder_mode,
+            client_bond: self.client_bond.map(|(_, amount)| amount),
+            executor_bond: self.executor_bond.map(|(_, amount)| amount),
+            forfeiture: self.forfeiture,
+            checker_fee: self
+                .checker
+                .map(|(_, checker_fee)| checker_fee)
+                .or(self.checker_panel.as_ref().map(|(_, checker_fee)| *checker_fee)),
+            checker_panel: self.checker_panel.map(|(checker_panel, _)| checker_panel),
+            advance_payment_amount: self.advance_payment_amount,
+            milestones: self.milestones,
+        };
+
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: accounts.to_account_metas(None),
+            data: deal_contract::instruction::Initialize { args }.data(),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::AccountMeta};
+
+use crate::{pda, Bond, Checker, CheckerPanel, DealState, PanelVote, TokenMint};
+
+pub mod cancel;
+pub use cancel::*;
+
+pub mod finish;
+pub use finish::*;
+
+pub mod initialize;
+pub use initialize::*;
+
+pub mod partially_pay;
+pub use partially_pay::*;
+
+pub mod update_checker;
+pub use update_checker::*;
+
+/// Token programs of the mints a deal uses. Mints not registered here are SPL Token ones.
+#[derive(Clone, Debug, Default)]
+pub struct Mints(Vec<TokenMint>);
+
+impl Mints {
+    pub fn add(&mut self, mint: TokenMint) {
+        self.0.retain(|known| known.address != mint.address);
+        self.0.push(mint);
+    }
+
+    pub fn get(&self, address: &Pubkey) -> TokenMint {
+        self.0
+            .iter()
+            .find(|mint| mint.address == *address)
+            .copied()
+            .unwrap_or_else(|| TokenMint::spl_token(*address))
+    }
+}
+
+/// `[checker, checker_deal_ta]` of every panel member paid on settlement, passed as remaining accounts.
+/// A member settling the deal casts `vote` first, and nobody voting refunds the fee to the client
+pub(crate) fn panel_fee_accounts(
+    deal: &DealState,
+    deal_mint: &TokenMint,
+    checker_panel: &CheckerPanel,
+    initializer: &Pubkey,
+    vote: PanelVote,
+) -> Vec<AccountMeta> {
+    let mut checker_panel = checker_panel.clone();
+    if let Some(member) = checker_panel
+        .members
+        .iter_mut()
+        .find(|member| member.checker_key == *initializer && member.vote.is_none())
+    {
+        member.vote = Some(vote);
+    }
+    let mut recipients = checker_panel.fee_recipients();
+    if recipients.is_empty() {
+        recipients.push(deal.client_key);
+    }
+
+    recipients
+        .iter()
+        .flat_map(|checker| {
+            [
+                AccountMeta::new(*checker, false),
+                AccountMeta::new(deal_mint.token_account(checker), false),
+            ]
+        })
+        .collect()
+}
+
+/// Every token account of an existing deal. Accounts of absent bonds or checker
+/// are filled with the deal ones, the program skips them.
+pub(crate) struct DealAccounts {
+    pub deal_state: Pubkey,
+    pub deal_mint: TokenMint,
+    pub client_deal_ta: Pubkey,
+    pub executor_deal_ta: Pubkey,
+    pub deal_state_deal_ta: Pubkey,
+
+    pub client_bond_mint: Pubkey,
+    pub client_bond_ta: Pubkey,
+    pub deal_state_client_bond_ta: Pubkey,
+    pub executor_bond_mint: Pubkey,
+    pub executor_bond_ta: Pubkey,
+    pub deal_state_executor_bond_ta: Pubkey,
+    /// Receives the executor bond when it is forfeited
+    pub client_executor_bond_ta: Pubkey,
+
+    pub holder_stake: Option<Pubkey>,
+
+    pub checker: Option<Pubkey>,
+    pub checker_deal_ta: Option<Pubkey>,
+}
+
+impl DealAccounts {
+    pub fn new(deal: &DealState, mints: &Mints) -> Self {
+        let deal_state = pda::deal_state(&deal.id, &deal.client_key, &deal.executor_key).0;
+        let deal_mint = mints.get(&deal.deal_token_mint);
+
+        let client_deal_ta = deal_mint.token_account(&deal.client_key);
+        let executor_deal_ta = deal_mint.token_account(&deal.executor_key);
+        let deal_state_deal_ta = deal_mint.token_account(&deal_state);
+
+        let bond_accounts =
+            |bond: &Option<Bond>,
+             owner: &Pubkey,
+             owner_deal_ta: Pubkey,
+             vault: fn(&Pubkey, &Pubkey) -> Pubkey| match bond {
+                Some(Bond { mint, .. }) => {
+                    let mint = mints.get(mint);
+                    (mint.address, mint.token_account(owner), vault(&deal_state, &mint.address))
+                }
+                None => (deal_mint.address, owner_deal_ta, deal_state_deal_ta),
+            };
+        let (client_bond_mint, client_bond_ta, deal_state_client_bond_ta) = bond_accounts(
+            &deal.client_bond,
+            &deal.client_key,
+            client_deal_ta,
+            pda::client_bond_vault,
+        );
+        let (executor_bond_mint, executor_bond_ta, deal_state_executor_bond_ta) = bond_accounts(
+            &deal.executor_bond,
+            &deal.executor_key,
+            executor_deal_ta,
+            pda::executor_bond_vault,
+        );
+
+        let client_executor_bond_ta = match deal.executor_bond {
+            Some(Bond { mint, .. }) => mints.get(&mint).token_account(&deal.client_key),
+            None => client_deal_ta,
+        };
+
+        let holder_stake = deal.holder_mode.map(|_| pda::holder_stake(&deal.client_key).0);
+
+        let checker = deal.checker.as_ref().map(|Checker { checker_key, .. }| *checker_key);
+
+        Self {
+            deal_state,
+            deal_mint,
+            client_deal_ta,
+            executor_deal_ta,
+            deal_state_deal_ta,
+            client_bond_mint,
+            client_bond_ta,
+            deal_state_client_bond_ta,
+            executor_bond_mint,
+            executor_bond_ta,
+            deal_state_executor_bond_ta,
+            client_executor_bond_ta,
+            holder_stake,
+            checker,
+            checker_deal_ta: checker.map(|checker| deal_mint.token_account(&checker)),
+        }
+    }
+}
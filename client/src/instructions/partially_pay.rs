@@ -0,0 +1,85 @@
+use anchor_lang::{
+    prelude::Pubkey, solana_program::instruction::Instruction, system_program, InstructionData,
+    ToAccountMetas,
+};
+use anchor_spl::associated_token;
+use deal_contract::{PartiallyPayArgs, PaymentMode};
+
+use crate::{pda, DealState, TokenMint};
+
+/// Builds `partially_pay`, a release of `amount` from the escrow to the executor.
+/// The client must sign.
+pub struct PartiallyPayBuilder<'a> {
+    deal: &'a DealState,
+    amount: u64,
+    mode: PaymentMode,
+    payer: Pubkey,
+    deal_token_program: Pubkey,
+}
+
+impl<'a> PartiallyPayBuilder<'a> {
+    pub fn new(deal: &'a DealState, amount: u64) -> Self {
+        Self {
+            deal,
+            amount,
+            mode: PaymentMode::Release,
+            payer: deal.client_key,
+            deal_token_program: anchor_spl::token::ID,
+        }
+    }
+
+    /// Pays `amount` straight from the client wallet on top of the escrowed deal amount
+    pub fn top_up(mut self) -> Self {
+        self.mode = PaymentMode::TopUp;
+        self
+    }
+
+    pub fn payer(mut self, payer: Pubkey) -> Self {
+        self.payer = payer;
+        self
+    }
+
+    /// Must be set for Token-2022 deal mints
+    pub fn deal_token_program(mut self, token_program: Pubkey) -> Self {
+        self.deal_token_program = token_program;
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let deal_mint = TokenMint {
+            address: self.deal.deal_token_mint,
+            token_program: self.deal_token_program,
+        };
+
+        let deal_state =
+            pda::deal_state(&self.deal.id, &self.deal.client_key, &self.deal.executor_key).0;
+
+        let accounts = deal_contract::accounts::PartiallyPay {
+            client: self.deal.client_key,
+            executor: self.deal.executor_key,
+            payer: self.payer,
+            deal_mint: deal_mint.address,
+            client_deal_ta: deal_mint.token_account(&self.deal.client_key),
+            deal_state_deal_ta: deal_mint.token_account(&deal_state),
+            executor_deal_ta: deal_mint.token_account(&self.deal.executor_key),
+            deal_state,
+            config: pda::config().0,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            token_2022_program: anchor_spl::token_2022::ID,
+            associated_token_program: associated_token::ID,
+        };
+
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: accounts.to_account_metas(None),
+            data: deal_contract::instruction::PartiallyPay {
+                args: PartiallyPayArgs {
+                    amount: self.amount,
+                    mode: self.mode,
+                },
+            }
+            .data(),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use anchor_lang::{
+    prelude::Pubkey, solana_program::instruction::Instruction, system_program, InstructionData,
+    ToAccountMetas,
+};
+use deal_contract::CheckerUpdate;
+
+use crate::{pda, Checker, DealState, Mints, ProgramConfig, TokenMint};
+
+/// Builds `update_checker`. On resignation the initializer is the current checker. Otherwise the
+/// client and the executor must sign unless the initializer is the service account, the new
+/// checker always signs, and the client signs when the fee is raised.
+pub struct UpdateCheckerBuilder<'a> {
+    deal: &'a DealState,
+    config: &'a ProgramConfig,
+    initializer: Pubkey,
+    update: CheckerUpdate,
+    new_checker: Option<Pubkey>,
+    mints: Mints,
+}
+
+impl<'a> UpdateCheckerBuilder<'a> {
+    pub fn new(
+        deal: &'a DealState,
+        config: &'a ProgramConfig,
+        initializer: Pubkey,
+        update: CheckerUpdate,
+    ) -> Self {
+        Self {
+            deal,
+            config,
+            initializer,
+            update,
+            new_checker: None,
+            mints: Mints::default(),
+        }
+    }
+
+    /// Required by `Replace` and `Add`
+    pub fn new_checker(mut self, new_checker: Pubkey) -> Self {
+        self.new_checker = Some(new_checker);
+        self
+    }
+
+    /// Registers the token program of a Token-2022 deal mint
+    pub fn mint(mut self, mint: TokenMint) -> Self {
+        self.mints.add(mint);
+        self
+    }
+
+    pub fn instruction(self) -> Instruction {
+        let deal_mint = self.mints.get(&self.deal.deal_token_mint);
+        let deal_state =
+            pda::deal_state(&self.deal.id, &self.deal.client_key, &self.deal.executor_key).0;
+        let current_checker = self
+            .deal
+            .checker
+            .as_ref()
+            .map_or(self.initializer, |Checker { checker_key, .. }| *checker_key);
+        let new_checker = self.new_checker.unwrap_or(current_checker);
+
+        let accounts = deal_contract::accounts::UpdateChecker {
+            initializer: self.initializer,
+            client: self.deal.client_key,
+            executor: self.deal.executor_key,
+            current_checker,
+            new_checker,
+            deal_mint: deal_mint.address,
+            client_deal_ta: deal_mint.token_account(&self.deal.client_key),
+            deal_state_deal_ta: deal_mint.token_account(&deal_state),
+            deal_state,
+            config: pda::config().0,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            token_2022_program: anchor_spl::token_2022::ID,
+        };
+
+        let mut accounts = accounts.to_account_metas(None);
+        if self.update != CheckerUpdate::Resign {
+            let parties_sign = self.initializer != self.config.service_account;
+            for meta in accounts.iter_mut() {
+                if meta.pubkey == new_checker
+                    || parties_sign
+                        && (meta.pubkey == self.deal.client_key
+                            || meta.pubkey == self.deal.executor_key)
+                {
+                    meta.is_signer = true;
+                }
+            }
+        }
+
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts,
+            data: deal_contract::instruction::UpdateChecker {
+                update: self.update,
+            }
+            .data(),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Instruction builders, PDA derivations and account decoding for the deal contract.
+//!
+//! Every builder derives the accounts the program expects for the given deal
+//! (bond, holder and checker token accounts included), so a backend only has to
+//! provide the parties, the mints and the amounts.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::spl_token::native_mint;
+
+pub mod accounts;
+pub mod instructions;
+pub mod pda;
+
+pub use accounts::*;
+pub use instructions::*;
+
+pub use deal_contract::{
+    state::{
+        Bond, BondForfeiture, Checker, CheckerPanel, CheckerPanelArgs, DealReceipt, DealState,
+        DealStatus, FeeSchedule, FeeScheduleArgs, HolderStake, HolderTier, MilestoneArgs,
+        PanelVote, ProgramConfig,
+    },
+    CheckerUpdate, ID as PROGRAM_ID,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("invalid deal id: {0}")]
+    InvalidDealId(#[from] uuid::Error),
+    #[error(transparent)]
+    Rpc(Box<solana_client::client_error::ClientError>),
+    #[error(transparent)]
+    Anchor(Box<anchor_lang::error::Error>),
+}
+
+impl From<solana_client::client_error::ClientError> for ClientError {
+    fn from(error: solana_client::client_error::ClientError) -> Self {
+        Self::Rpc(Box::new(error))
+    }
+}
+
+impl From<anchor_lang::error::Error> for ClientError {
+    fn from(error: anchor_lang::error::Error) -> Self {
+        Self::Anchor(Box::new(error))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+/// Deal ids are UUIDs, stored on chain as their 16 raw bytes
+pub fn deal_id_from_uuid(uuid: &str) -> Result<[u8; 16]> {
+    Ok(uuid::Uuid::parse_str(uuid)?.into_bytes())
+}
+
+pub fn deal_id_to_uuid(deal_id: &[u8; 16]) -> String {
+    uuid::Uuid::from_bytes(*deal_id).hyphenated().to_string()
+}
+
+/// A mint together with the token program that owns it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenMint {
+    pub address: Pubkey,
+    pub token_program: Pubkey,
+}
+
+impl TokenMint {
+    pub fn spl_token(address: Pubkey) -> Self {
+        Self {
+            address,
+            token_program: anchor_spl::token::ID,
+        }
+    }
+
+    pub fn token_2022(address: Pubkey) -> Self {
+        Self {
+            address,
+            token_program: anchor_spl::token_2022::ID,
+        }
+    }
+
+    /// Native SOL, settled in lamports
+    pub fn native() -> Self {
+        Self::spl_token(native_mint::ID)
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.address == native_mint::ID
+    }
+
+    pub fn token_account(&self, owner: &Pubkey) -> Pubkey {
+        pda::token_account(&self.address, owner, &self.token_program)
+    }
+}
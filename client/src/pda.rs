@@ -0,0 +1,69 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::spl_token::native_mint;
+use deal_contract::constants::*;
+
+pub fn deal_state(deal_id: &[u8; 16], client: &Pubkey, executor: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[&deal_id[..], DEAL_STATE_SEED, client.as_ref(), executor.as_ref()],
+        &deal_contract::ID,
+    )
+}
+
+pub fn config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], &deal_contract::ID)
+}
+
+pub fn dispute(deal_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DISPUTE_SEED, deal_state.as_ref()], &deal_contract::ID)
+}
+
+pub fn deal_milestones(deal_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MILESTONES_SEED, deal_state.as_ref()], &deal_contract::ID)
+}
+
+pub fn deal_receipt(deal_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RECEIPT_SEED, deal_state.as_ref()], &deal_contract::ID)
+}
+
+pub fn checker_panel(deal_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CHECKER_PANEL_SEED, deal_state.as_ref()], &deal_contract::ID)
+}
+
+pub fn fee_schedule(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FEE_SCHEDULE_SEED, mint.as_ref()], &deal_contract::ID)
+}
+
+/// Holder tokens of the stake are kept in its holder mint ATA
+pub fn holder_stake(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[HOLDER_STAKE_SEED, owner.as_ref()], &deal_contract::ID)
+}
+
+/// The vault of the client bond in `mint`. Native SOL bonds are kept in the deal state itself.
+pub fn client_bond_vault(deal_state: &Pubkey, mint: &Pubkey) -> Pubkey {
+    bond_vault(CLIENT_BOND_SEED, deal_state, mint)
+}
+
+/// The vault of the executor bond in `mint`. Native SOL bonds are kept in the deal state itself.
+pub fn executor_bond_vault(deal_state: &Pubkey, mint: &Pubkey) -> Pubkey {
+    bond_vault(EXECUTOR_BOND_SEED, deal_state, mint)
+}
+
+fn bond_vault(seed: &[u8], deal_state: &Pubkey, mint: &Pubkey) -> Pubkey {
+    if *mint == native_mint::ID {
+        return *deal_state;
+    }
+    Pubkey::find_program_address(&[seed, deal_state.as_ref()], &deal_contract::ID).0
+}
+
+/// The account holding `owner`'s tokens of `mint`.
+/// Native SOL deals are settled in lamports, so the owner itself stands for the token account.
+pub fn token_account(mint: &Pubkey, owner: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    if *mint == native_mint::ID {
+        return *owner;
+    }
+    spl_associated_token_account::get_associated_token_address_with_program_id(
+        owner,
+        mint,
+        token_program,
+    )
+}
@@ -0,0 +1,513 @@
+use anchor_lang::{
+    prelude::Pubkey, solana_program::instruction::AccountMeta, system_program, ToAccountMetas,
+};
+use anchor_spl::{associated_token, token::spl_token::native_mint};
+use deal_contract::{constants::*, state::PanelMember};
+use deal_contract_client::{
+    pda, Bond, CancelBuilder, Checker, CheckerPanel, CheckerUpdate, DealState, DealStatus,
+    FinishBuilder, HolderTier, PanelVote, PartiallyPayBuilder, ProgramConfig, TokenMint,
+    UpdateCheckerBuilder,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id as ata;
+
+const TOKEN: Pubkey = anchor_spl::token::ID;
+const TOKEN_2022: Pubkey = anchor_spl::token_2022::ID;
+
+fn config() -> ProgramConfig {
+    ProgramConfig {
+        authority: Pubkey::new_unique(),
+        service_account: Pubkey::new_unique(),
+        service_fee_owner: Pubkey::new_unique(),
+        holder_mint: Pubkey::new_unique(),
+        bump: [255],
+        version: 1,
+        holder_tiers: [HolderTier::default(); 4],
+        holder_stake_cooldown: 0,
+        max_advance_bps: 0,
+        review_window: 0,
+        paused: 0,
+    }
+}
+
+fn deal(deal_token_mint: Pubkey) -> DealState {
+    DealState {
+        id: [1; 16],
+        client_key: Pubkey::new_unique(),
+        executor_key: Pubkey::new_unique(),
+        deal_token_mint,
+        client_bond: None,
+        executor_bond: None,
+        checker: None,
+        holder_mode: None,
+        amount: 100_000,
+        paid_amount: 0,
+        deadline_ts: None,
+        bump: [255],
+        version: 1,
+        status: DealStatus::Active,
+        forfeiture: Default::default(),
+        with_milestones: false,
+        with_panel: false,
+        acceptance_deadline_ts: None,
+        executor_accepted: true,
+        checker_accepted: true,
+        amendments: 0,
+        review_deadline_ts: None,
+        held_service_fee: 0,
+    }
+}
+
+fn deal_state(deal: &DealState) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            &deal.id,
+            DEAL_STATE_SEED,
+            deal.client_key.as_ref(),
+            deal.executor_key.as_ref(),
+        ],
+        &deal_contract::ID,
+    )
+    .0
+}
+
+fn child(seed: &[u8], deal_state: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seed, deal_state.as_ref()], &deal_contract::ID).0
+}
+
+#[test]
+fn finish_derives_the_bond_holder_and_checker_accounts() {
+    let config = config();
+    let (deal_mint, client_bond_mint, executor_bond_mint) =
+        (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let checker = Pubkey::new_unique();
+    let deal = DealState {
+        client_bond: Some(Bond {
+            mint: client_bond_mint,
+            amount: 3_000,
+        }),
+        executor_bond: Some(Bond {
+            mint: executor_bond_mint,
+            amount: 4_000,
+        }),
+        checker: Some(Checker {
+            checker_fee: 2_000,
+            checker_key: checker,
+        }),
+        holder_mode: Some(1_000),
+        ..deal(deal_mint)
+    };
+    let deal_state = deal_state(&deal);
+
+    let ix = FinishBuilder::new(&deal, &config, checker)
+        .mint(TokenMint::token_2022(executor_bond_mint))
+        .with_receipt()
+        .instruction();
+
+    let expected = deal_contract::accounts::Finish {
+        initializer: checker,
+        client: deal.client_key,
+        executor: deal.executor_key,
+        checker,
+        payer: checker,
+        deal_state_deal_ta: ata(&deal_state, &deal_mint, &TOKEN),
+        executor_deal_ta: ata(&deal.executor_key, &deal_mint, &TOKEN),
+        checker_deal_ta: ata(&checker, &deal_mint, &TOKEN),
+        deal_state_client_bond_ta: child(CLIENT_BOND_SEED, &deal_state),
+        deal_state_executor_bond_ta: child(EXECUTOR_BOND_SEED, &deal_state),
+        client_bond_ta: ata(&deal.client_key, &client_bond_mint, &TOKEN),
+        executor_bond_ta: ata(&deal.executor_key, &executor_bond_mint, &TOKEN_2022),
+        deal_mint,
+        client_bond_mint,
+        executor_bond_mint,
+        service_fee: config.service_fee_owner,
+        deal_state,
+        holder_stake: Some(
+            Pubkey::find_program_address(
+                &[HOLDER_STAKE_SEED, deal.client_key.as_ref()],
+                &deal_contract::ID,
+            )
+            .0,
+        ),
+        deal_milestones: None,
+        checker_panel: None,
+        deal_receipt: Some(child(RECEIPT_SEED, &deal_state)),
+        config: Pubkey::find_program_address(&[CONFIG_SEED], &deal_contract::ID).0,
+        associated_token_program: associated_token::ID,
+        token_program: TOKEN,
+        token_2022_program: TOKEN_2022,
+        system_program: system_program::ID,
+    };
+    assert_eq!(ix.accounts, expected.to_account_metas(None));
+}
+
+#[test]
+fn finish_without_bonds_uses_the_deal_accounts() {
+    let config = config();
+    let deal_mint = Pubkey::new_unique();
+    let deal = deal(deal_mint);
+    let deal_state = deal_state(&deal);
+
+    let ix = FinishBuilder::new(&deal, &config, deal.client_key).instruction();
+
+    let deal_state_deal_ta = ata(&deal_state, &deal_mint, &TOKEN);
+    let executor_deal_ta = ata(&deal.executor_key, &deal_mint, &TOKEN);
+    let expected = deal_contract::accounts::Finish {
+        initializer: deal.client_key,
+        client: deal.client_key,
+        executor: deal.executor_key,
+        checker: deal.client_key,
+        payer: deal.client_key,
+        deal_state_deal_ta,
+        executor_deal_ta,
+        checker_deal_ta: executor_deal_ta,
+        deal_state_client_bond_ta: deal_state_deal_ta,
+        deal_state_executor_bond_ta: deal_state_deal_ta,
+        client_bond_ta: ata(&deal.client_key, &deal_mint, &TOKEN),
+        executor_bond_ta: executor_deal_ta,
+        deal_mint,
+        client_bond_mint: deal_mint,
+        executor_bond_mint: deal_mint,
+        service_fee: config.service_fee_owner,
+        deal_state,
+        holder_stake: None,
+        deal_milestones: None,
+        checker_panel: None,
+        deal_receipt: None,
+        config: pda::config().0,
+        associated_token_program: associated_token::ID,
+        token_program: TOKEN,
+        token_2022_program: TOKEN_2022,
+        system_program: system_program::ID,
+    };
+    assert_eq!(ix.accounts, expected.to_account_metas(None));
+}
+
+#[test]
+fn cancel_derives_the_bond_holder_and_checker_accounts() {
+    let config = config();
+    let (deal_mint, bond_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let checker = Pubkey::new_unique();
+    let deal = DealState {
+        client_bond: Some(Bond {
+            mint: bond_mint,
+            amount: 3_000,
+        }),
+        executor_bond: Some(Bond {
+            mint: bond_mint,
+            amount: 4_000,
+        }),
+        checker: Some(Checker {
+            checker_fee: 2_000,
+            checker_key: checker,
+        }),
+        holder_mode: Some(1_000),
+        ..deal(deal_mint)
+    };
+    let deal_state = deal_state(&deal);
+    let payer = Pubkey::new_unique();
+
+    let ix = CancelBuilder::new(&deal, &config, config.service_account)
+        .payer(payer)
+        .mint(TokenMint::token_2022(deal_mint))
+        .instruction();
+
+    let expected = deal_contract::accounts::Cancel {
+        initializer: config.service_account,
+        checker,
+        client: deal.client_key,
+        executor: deal.executor_key,
+        payer,
+        deal_state_deal_ta: ata(&deal_state, &deal_mint, &TOKEN_2022),
+        client_deal_ta: ata(&deal.client_key, &deal_mint, &TOKEN_2022),
+        checker_deal_ta: ata(&checker, &deal_mint, &TOKEN_2022),
+        client_bond_ta: ata(&deal.client_key, &bond_mint, &TOKEN),
+        executor_bond_ta: ata(&deal.executor_key, &bond_mint, &TOKEN),
+        client_executor_bond_ta: ata(&deal.client_key, &bond_mint, &TOKEN),
+        deal_state_client_bond_ta: child(CLIENT_BOND_SEED, &deal_state),
+        deal_state_executor_bond_ta: child(EXECUTOR_BOND_SEED, &deal_state),
+        deal_mint,
+        client_bond_mint: bond_mint,
+        executor_bond_mint: bond_mint,
+        service_fee: config.service_fee_owner,
+        deal_state,
+        deal_milestones: None,
+        checker_panel: None,
+        deal_receipt: None,
+        holder_stake: Some(pda::holder_stake(&deal.client_key).0),
+        config: pda::config().0,
+        associated_token_program: associated_token::ID,
+        token_program: TOKEN,
+        token_2022_program: TOKEN_2022,
+        system_program: system_program::ID,
+    };
+    assert_eq!(ix.accounts, expected.to_account_metas(None));
+}
+
+#[test]
+fn native_bonds_are_kept_in_the_deal_state() {
+    let config = config();
+    let deal = DealState {
+        client_bond: Some(Bond {
+            mint: native_mint::ID,
+            amount: 3_000,
+        }),
+        ..deal(native_mint::ID)
+    };
+    let deal_state = deal_state(&deal);
+
+    let ix = CancelBuilder::new(&deal, &config, deal.client_key).mutual().instruction();
+
+    let mut expected = deal_contract::accounts::Cancel {
+        initializer: deal.client_key,
+        checker: deal.client_key,
+        client: deal.client_key,
+        executor: deal.executor_key,
+        payer: deal.client_key,
+        deal_state_deal_ta: deal_state,
+        client_deal_ta: deal.client_key,
+        checker_deal_ta: deal.client_key,
+        client_bond_ta: deal.client_key,
+        executor_bond_ta: deal.executor_key,
+        client_executor_bond_ta: deal.client_key,
+        deal_state_client_bond_ta: deal_state,
+        deal_state_executor_bond_ta: deal_state,
+        deal_mint: native_mint::ID,
+        client_bond_mint: native_mint::ID,
+        executor_bond_mint: native_mint::ID,
+        service_fee: config.service_fee_owner,
+        deal_state,
+        deal_milestones: None,
+        checker_panel: None,
+        deal_receipt: None,
+        holder_stake: None,
+        config: pda::config().0,
+        associated_token_program: associated_token::ID,
+        token_program: TOKEN,
+        token_2022_program: TOKEN_2022,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    // `mutual_cancel` is signed by both parties, whose wallets stand for their token accounts
+    for meta in expected.iter_mut() {
+        meta.is_signer |= meta.pubkey == deal.client_key || meta.pubkey == deal.executor_key;
+    }
+    assert_eq!(ix.accounts, expected);
+}
+
+fn panel_deal(deal_mint: Pubkey, votes: [Option<PanelVote>; 3]) -> (DealState, CheckerPanel) {
+    let deal = DealState {
+        with_panel: true,
+        ..deal(deal_mint)
+    };
+    let deal_state = deal_state(&deal);
+    let deal = DealState {
+        checker: Some(Checker {
+            checker_fee: 3_000,
+            checker_key: child(CHECKER_PANEL_SEED, &deal_state),
+        }),
+        ..deal
+    };
+    let checker_panel = CheckerPanel {
+        deal_state,
+        members: votes
+            .into_iter()
+            .map(|vote| PanelMember {
+                checker_key: Pubkey::new_unique(),
+                vote,
+            })
+            .collect(),
+        threshold: 2,
+        bump: [255],
+    };
+    (deal, checker_panel)
+}
+
+fn fee_accounts(recipients: &[Pubkey], deal_mint: &Pubkey) -> Vec<AccountMeta> {
+    recipients
+        .iter()
+        .flat_map(|recipient| {
+            [
+                AccountMeta::new(*recipient, false),
+                AccountMeta::new(ata(recipient, deal_mint, &TOKEN), false),
+            ]
+        })
+        .collect()
+}
+
+#[test]
+fn settling_panel_member_is_paid_with_the_voters() {
+    let config = config();
+    let deal_mint = Pubkey::new_unique();
+    let (deal, checker_panel) = panel_deal(deal_mint, [Some(PanelVote::Approve), None, None]);
+    let members: Vec<_> = checker_panel.members.iter().map(|member| member.checker_key).collect();
+
+    let ix = FinishBuilder::new(&deal, &config, members[1])
+        .checker_panel(&checker_panel)
+        .instruction();
+    let fixed = ix.accounts.len() - 4;
+    // the panel takes the place of the checker
+    assert!(ix.accounts[..fixed]
+        .iter()
+        .any(|meta| meta.pubkey == child(CHECKER_PANEL_SEED, &checker_panel.deal_state)));
+    assert_eq!(ix.accounts[fixed..], fee_accounts(&members[..2], &deal_mint));
+
+    // every voter is paid, whatever the vote
+    let ix = CancelBuilder::new(&deal, &config, members[2])
+        .checker_panel(&checker_panel)
+        .instruction();
+    let fixed = ix.accounts.len() - 4;
+    assert_eq!(ix.accounts[fixed..], fee_accounts(&[members[0], members[2]], &deal_mint));
+}
+
+#[test]
+fn panel_fee_is_refunded_to_the_client_without_voters() {
+    let config = config();
+    let deal_mint = Pubkey::new_unique();
+    let (deal, checker_panel) = panel_deal(deal_mint, [None, None, None]);
+
+    let ix = CancelBuilder::new(&deal, &config, deal.client_key)
+        .checker_panel(&checker_panel)
+        .instruction();
+    let fixed = ix.accounts.len() - 2;
+    assert_eq!(ix.accounts[fixed..], fee_accounts(&[deal.client_key], &deal_mint));
+}
+
+#[test]
+fn update_checker_derives_the_deal_accounts_and_signers() {
+    let config = config();
+    let deal_mint = Pubkey::new_unique();
+    let (checker, new_checker) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let deal = DealState {
+        checker: Some(Checker {
+            checker_fee: 2_000,
+            checker_key: checker,
+        }),
+        ..deal(deal_mint)
+    };
+    let deal_state = deal_state(&deal);
+
+    let accounts = |initializer: Pubkey| deal_contract::accounts::UpdateChecker {
+        initializer,
+        client: deal.client_key,
+        executor: deal.executor_key,
+        current_checker: checker,
+        new_checker,
+        deal_mint,
+        client_deal_ta: ata(&deal.client_key, &deal_mint, &TOKEN_2022),
+        deal_state_deal_ta: ata(&deal_state, &deal_mint, &TOKEN_2022),
+        deal_state,
+        config: pda::config().0,
+        system_program: system_program::ID,
+        token_program: TOKEN,
+        token_2022_program: TOKEN_2022,
+    };
+    let sign = |mut metas: Vec<AccountMeta>, signers: &[Pubkey]| {
+        for meta in metas.iter_mut() {
+            meta.is_signer |= signers.contains(&meta.pubkey);
+        }
+        metas
+    };
+    let update = CheckerUpdate::Replace { checker_fee: 2_500 };
+
+    let ix = UpdateCheckerBuilder::new(&deal, &config, deal.client_key, update)
+        .new_checker(new_checker)
+        .mint(TokenMint::token_2022(deal_mint))
+        .instruction();
+    let expected = accounts(deal.client_key).to_account_metas(None);
+    assert_eq!(
+        ix.accounts,
+        sign(expected, &[deal.client_key, deal.executor_key, new_checker])
+    );
+
+    // the service account replaces the checker without the parties
+    let ix = UpdateCheckerBuilder::new(&deal, &config, config.service_account, update)
+        .new_checker(new_checker)
+        .mint(TokenMint::token_2022(deal_mint))
+        .instruction();
+    let expected = accounts(config.service_account).to_account_metas(None);
+    assert_eq!(ix.accounts, sign(expected, &[new_checker]));
+}
+
+#[test]
+fn update_checker_resignation_is_signed_by_the_checker_only() {
+    let config = config();
+    let deal_mint = Pubkey::new_unique();
+    let checker = Pubkey::new_unique();
+    let deal = DealState {
+        checker: Some(Checker {
+            checker_fee: 2_000,
+            checker_key: checker,
+        }),
+        ..deal(deal_mint)
+    };
+
+    let ix =
+        UpdateCheckerBuilder::new(&deal, &config, checker, CheckerUpdate::Resign).instruction();
+    let signers: Vec<_> = ix.accounts.iter().filter(|meta| meta.is_signer).collect();
+    assert_eq!(signers.len(), 1);
+    assert_eq!(signers[0].pubkey, checker);
+    // the current checker stands in for the missing new one
+    assert_eq!(ix.accounts.iter().filter(|meta| meta.pubkey == checker).count(), 3);
+    assert!(ix
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey == ata(&deal.client_key, &deal_mint, &TOKEN)));
+}
+
+#[test]
+fn partially_pay_derives_the_deal_token_accounts() {
+    let deal_mint = Pubkey::new_unique();
+    let deal = deal(deal_mint);
+    let deal_state = deal_state(&deal);
+
+    let accounts = |token_program: &Pubkey, payer: Pubkey| deal_contract::accounts::PartiallyPay {
+        client: deal.client_key,
+        executor: deal.executor_key,
+        payer,
+        deal_mint,
+        client_deal_ta: ata(&deal.client_key, &deal_mint, token_program),
+        deal_state_deal_ta: ata(&deal_state, &deal_mint, token_program),
+        executor_deal_ta: ata(&deal.executor_key, &deal_mint, token_program),
+        deal_state,
+        config: pda::config().0,
+        system_program: system_program::ID,
+        token_program: TOKEN,
+        token_2022_program: TOKEN_2022,
+        associated_token_program: associated_token::ID,
+    };
+
+    let ix = PartiallyPayBuilder::new(&deal, 1_000).instruction();
+    assert_eq!(ix.accounts, accounts(&TOKEN, deal.client_key).to_account_metas(None));
+
+    let payer = Pubkey::new_unique();
+    let ix = PartiallyPayBuilder::new(&deal, 1_000)
+        .top_up()
+        .payer(payer)
+        .deal_token_program(TOKEN_2022)
+        .instruction();
+    assert_eq!(ix.accounts, accounts(&TOKEN_2022, payer).to_account_metas(None));
+}
+
+#[test]
+fn partially_pay_native_deal_pays_the_wallets() {
+    let deal = deal(native_mint::ID);
+    let deal_state = deal_state(&deal);
+
+    let ix = PartiallyPayBuilder::new(&deal, 1_000).instruction();
+    let expected = deal_contract::accounts::PartiallyPay {
+        client: deal.client_key,
+        executor: deal.executor_key,
+        payer: deal.client_key,
+        deal_mint: native_mint::ID,
+        client_deal_ta: deal.client_key,
+        deal_state_deal_ta: deal_state,
+        executor_deal_ta: deal.executor_key,
+        deal_state,
+        config: pda::config().0,
+        system_program: system_program::ID,
+        token_program: TOKEN,
+        token_2022_program: TOKEN_2022,
+        associated_token_program: associated_token::ID,
+    };
+    assert_eq!(ix.accounts, expected.to_account_metas(None));
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::Pubkey;
+use deal_contract::constants::DEAL_STATE_SEED;
+use deal_contract_client::{deal_id_from_uuid, deal_id_to_uuid, pda, ClientError};
+
+#[test]
+fn uuid_round_trips_through_the_deal_id() {
+    let uuid = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+    let deal_id = deal_id_from_uuid(uuid).unwrap();
+    assert_eq!(
+        deal_id,
+        [
+            0x67, 0xe5, 0x50, 0x44, 0x10, 0xb1, 0x42, 0x6f, 0x92, 0x47, 0xbb, 0x68, 0x0e, 0x5f,
+            0xe0, 0xc8
+        ]
+    );
+    assert_eq!(deal_id_to_uuid(&deal_id), uuid);
+
+    let deal_id = [7; 16];
+    assert_eq!(deal_id_from_uuid(&deal_id_to_uuid(&deal_id)).unwrap(), deal_id);
+}
+
+#[test]
+fn uuid_is_parsed_in_any_form() {
+    let deal_id = deal_id_from_uuid("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+    for uuid in [
+        "67E55044-10B1-426F-9247-BB680E5FE0C8",
+        "67e5504410b1426f9247bb680e5fe0c8",
+        "urn:uuid:67e55044-10b1-426f-9247-bb680e5fe0c8",
+    ] {
+        assert_eq!(deal_id_from_uuid(uuid).unwrap(), deal_id);
+    }
+    // printed back hyphenated and lowercase
+    assert_eq!(deal_id_to_uuid(&deal_id), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+}
+
+#[test]
+fn invalid_uuid_is_rejected() {
+    for uuid in ["", "67e55044-10b1-426f-9247", "not-a-uuid-at-all-not-a-uuid-at-all"] {
+        assert!(matches!(deal_id_from_uuid(uuid), Err(ClientError::InvalidDealId(_))));
+    }
+}
+
+#[test]
+fn deal_state_is_derived_from_the_raw_id() {
+    let (client, executor) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let deal_id = deal_id_from_uuid("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+    let expected = Pubkey::find_program_address(
+        &[&deal_id, DEAL_STATE_SEED, client.as_ref(), executor.as_ref()],
+        &deal_contract::ID,
+    );
+    assert_eq!(pda::deal_state(&deal_id, &client, &executor), expected);
+}
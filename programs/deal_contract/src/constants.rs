@@ -1,24 +1,57 @@
-use anchor_lang::prelude::*;
-use solana_program::pubkey;
-
 pub const DEAL_STATE_SEED: &[u8] = b"deal_state";
 
-// NEED CHECK: CTUS address
-// Devnet: CyhjLfsfDz7rtszqBGaHiFrBbck2LNKEXQkywqNrGVyw
-// Mainnet: ---
-// Localnet: 67sJHNFLxkREsdu35n8tmfudv1ZU59XhBYjc6rivMt2V
-pub const SERVICE_FEE_MINT: Pubkey = pubkey!("62PtWFh2dQ69LKbHumBpMa7wG71r7i7Damwo2wMYfcR1");
-// pub const SERVICE_FEE_TA: Pubkey = pubkey!("11111111111111111111111111111111");
-pub const SERVICE_FEE_OWNER: Pubkey = pubkey!("C8pHACh7SAZWVZvgppM6VkWEDC6voLGGctch5Vr5hkEz");
-
-// NEED CHECK: Admin level
-// Devnet: 3aDaxu2XwsGmj7amUnrxaHoKTtKJUqebkYP9HJTkP434
-// Mainnet: ---
-// Localnet: TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA
-pub const SERVICE_ACCOUNT_ADDRESS: Pubkey = pubkey!("C8pHACh7SAZWVZvgppM6VkWEDC6voLGGctch5Vr5hkEz");
-
-// NEED CHECK: 10_000 CTUS
-#[constant]
-pub const HOLDER_MODE_AMOUNT: u64 = 10000000000000;
-
-pub const HOLDER_MINT: Pubkey = pubkey!("64esx9p99rgwzmBCFCaUDCKJL2b2WrgdFe7chyaDyrKD");
+pub const CONFIG_SEED: &[u8] = b"config";
+
+pub const DISPUTE_SEED: &[u8] = b"dispute";
+
+pub const BASIS_POINTS: u16 = 10_000;
+
+pub const MILESTONES_SEED: &[u8] = b"milestones";
+
+pub const RECEIPT_SEED: &[u8] = b"receipt";
+
+pub const CLIENT_BOND_SEED: &[u8] = b"client_bond";
+
+pub const EXECUTOR_BOND_SEED: &[u8] = b"executor_bond";
+
+pub const CHECKER_PANEL_SEED: &[u8] = b"checker_panel";
+
+pub const FEE_SCHEDULE_SEED: &[u8] = b"fee_schedule";
+
+pub const HOLDER_STAKE_SEED: &[u8] = b"holder_stake";
+
+// Bits of `ProgramConfig::paused`, one per instruction the config authority may pause.
+// Pausing any of `PAUSE_EXITS` locks the funds of the parties until it is unpaused.
+pub const PAUSE_INITIALIZE: u32 = 1 << 0;
+pub const PAUSE_FINISH: u32 = 1 << 1;
+pub const PAUSE_CANCEL: u32 = 1 << 2;
+pub const PAUSE_UPDATE_CHECKER: u32 = 1 << 3;
+pub const PAUSE_PARTIALLY_PAY: u32 = 1 << 4;
+pub const PAUSE_OPEN_DISPUTE: u32 = 1 << 5;
+pub const PAUSE_SUBMIT_EVIDENCE: u32 = 1 << 6;
+pub const PAUSE_RESOLVE_DISPUTE: u32 = 1 << 7;
+pub const PAUSE_APPROVE_MILESTONE: u32 = 1 << 8;
+pub const PAUSE_REJECT_MILESTONE: u32 = 1 << 9;
+pub const PAUSE_CREATE_OFFER: u32 = 1 << 10;
+pub const PAUSE_ACCEPT_OFFER: u32 = 1 << 11;
+pub const PAUSE_ACCEPT_ROLE: u32 = 1 << 12;
+pub const PAUSE_WITHDRAW_OFFER: u32 = 1 << 13;
+pub const PAUSE_AMEND_DEAL: u32 = 1 << 14;
+pub const PAUSE_MARK_DELIVERED: u32 = 1 << 15;
+pub const PAUSE_SLASH_BOND: u32 = 1 << 16;
+pub const PAUSE_CHECKER_VOTE: u32 = 1 << 17;
+pub const PAUSE_STAKE: u32 = 1 << 18;
+pub const PAUSE_UNSTAKE: u32 = 1 << 19;
+pub const PAUSE_RECOVER_HOLDER_TOKENS: u32 = 1 << 20;
+pub const PAUSE_MIGRATE_DEAL: u32 = 1 << 21;
+// The instructions the parties leave a deal or a stake with. A legacy deal is settled
+// only once it is migrated
+pub const PAUSE_EXITS: u32 = PAUSE_FINISH
+    | PAUSE_CANCEL
+    | PAUSE_RESOLVE_DISPUTE
+    | PAUSE_WITHDRAW_OFFER
+    | PAUSE_UNSTAKE
+    | PAUSE_RECOVER_HOLDER_TOKENS
+    | PAUSE_MIGRATE_DEAL;
+// Every bit, exits included. `PAUSE_ALL & !PAUSE_EXITS` stops everything but the exits
+pub const PAUSE_ALL: u32 = (1 << 22) - 1;
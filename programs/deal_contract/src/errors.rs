@@ -35,12 +35,116 @@ pub enum ErrorCodes {
 
     #[msg("DealWithExecutorBond")]
     DealWithExecutorBond,
+
+    #[msg("DealDisputed")]
+    DealDisputed,
+    #[msg("DealNotDisputed")]
+    DealNotDisputed,
+    #[msg("Evidence limit reached")]
+    EvidenceLimitReached,
+    #[msg("Basis points must not exceed 10000")]
+    InvalidBasisPoints,
+
+    #[msg("Too many milestones")]
+    TooManyMilestones,
+    #[msg("Milestones and advance payment do not add up to the deal amount")]
+    MilestonesAmountMismatch,
+    #[msg("MilestoneNotFound")]
+    MilestoneNotFound,
+    #[msg("MilestoneAlreadySettled")]
+    MilestoneAlreadySettled,
+    #[msg("DealWithMilestones")]
+    DealWithMilestones,
+
+    #[msg("The offer has not been accepted yet")]
+    OfferNotAccepted,
+    #[msg("OfferAlreadyAccepted")]
+    OfferAlreadyAccepted,
+    #[msg("Acceptance deadline expired")]
+    AcceptanceDeadlineExpired,
+    #[msg("The acceptance deadline has not yet come.")]
+    AcceptanceDeadlineNotExpired,
+
+    #[msg("The release exceeds the remaining escrow")]
+    ReleaseExceedsEscrow,
+
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+
+    #[msg("The advance payment exceeds the deal amount")]
+    AdvanceExceedsDealAmount,
+    #[msg("The advance payment exceeds the configured maximum")]
+    AdvanceExceedsMaxAdvance,
+
+    #[msg("The deal amount can not drop below the paid amount")]
+    AmountBelowPaid,
+
+    #[msg("The review window must be positive")]
+    InvalidReviewWindow,
+    #[msg("The delivery has already been marked")]
+    AlreadyDelivered,
+    #[msg("The review window has not yet expired")]
+    ReviewWindowNotExpired,
+    #[msg("The review window expired")]
+    ReviewWindowExpired,
+
+    #[msg("A forfeiture rule is set without its bond, deadline or checker")]
+    InvalidForfeitureRules,
+    #[msg("The deal does not allow the checker to slash bonds")]
+    SlashNotAllowed,
+    #[msg("The bond is already forfeited")]
+    BondAlreadyForfeited,
+
+    #[msg("A checker panel needs 2 or more distinct checkers and a threshold over half of them")]
+    InvalidCheckerPanel,
+    #[msg("The checker already voted")]
+    AlreadyVoted,
+    #[msg("The checker panel already reached its threshold")]
+    PanelAlreadyDecided,
+    #[msg("The checker panel has not reached its threshold for this settlement")]
+    PanelNotDecided,
+    #[msg("The vote reaches the panel threshold, cast it with finish or cancel")]
+    PanelVoteSettles,
+    #[msg("The deal is checked by a checker panel")]
+    DealWithCheckerPanel,
+
+    #[msg("The minimum service fee exceeds the maximum one")]
+    InvalidFeeSchedule,
+    #[msg("The service fee exceeds the limit set by the client")]
+    ServiceFeeAboveLimit,
+    #[msg("Holder tiers must raise both the amount and the discount")]
+    InvalidHolderTiers,
+
+    #[msg("The stake cooldown must not be negative")]
+    InvalidStakeCooldown,
+    #[msg("The stake is referenced by active deals")]
+    StakeInUse,
+    #[msg("The stake is still in its cooldown")]
+    StakeInCooldown,
+    #[msg("The amount exceeds the stake")]
+    InsufficientStake,
+    #[msg("The deal state is not closed")]
+    DealNotClosed,
+    #[msg("The instruction is paused")]
+    InstructionPaused,
+    #[msg("Unknown or no instructions to pause")]
+    InvalidPauseFlags,
+    #[msg("The deal was delivered, finish or dispute it instead")]
+    DealDelivered,
+    #[msg("The account already has the current layout")]
+    AlreadyMigrated,
+    #[msg("The acceptance deadline is after the deal deadline")]
+    AcceptanceAfterDeadline,
+    #[msg("The deal is not active, a legacy deal has to go through migrate_deal first")]
+    DealNotActive,
 }
 
 #[error_code]
 pub enum InvalidAccount {
     #[msg("Initializer")]
     Initializer,
+    #[msg("Authority")]
+    Authority,
     #[msg("Checker")]
     Checker,
     #[msg("CheckerDealTokenAccount")]
@@ -74,4 +178,17 @@ pub enum InvalidAccount {
 
     #[msg("ClientHolderTokenAccountOwner")]
     ClientHolderTokenAccountOwner,
+
+    #[msg("ExecutorClientBondTokenAccount")]
+    ExecutorClientBondTokenAccount,
+    #[msg("ClientExecutorBondTokenAccount")]
+    ClientExecutorBondTokenAccount,
+    #[msg("DisputeParty")]
+    DisputeParty,
+    #[msg("DealMilestones")]
+    DealMilestones,
+    #[msg("CheckerPanel")]
+    CheckerPanel,
+    #[msg("HolderStake")]
+    HolderStake,
 }
@@ -0,0 +1,278 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    instructions::PaymentMode,
+    state::{Bond, Checker, DealState, PanelVote},
+};
+
+#[event]
+pub struct DealCreated {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub executor: Pubkey,
+    pub checker: Option<Pubkey>,
+    pub deal_mint: Pubkey,
+    pub amount: u64,
+    pub service_fee: u64,
+    pub checker_fee: u64,
+    pub client_bond: u64,
+    pub executor_bond: u64,
+    pub holder_mode: Option<u64>,
+    pub deadline_ts: Option<i64>,
+    pub timestamp: i64,
+}
+
+impl DealCreated {
+    pub fn new(deal_state: &DealState, service_fee: u64, timestamp: i64) -> Self {
+        let bond_amount = |bond: &Option<Bond>| bond.as_ref().map_or(0, |Bond { amount, .. }| *amount);
+        Self {
+            deal_id: deal_state.id,
+            client: deal_state.client_key,
+            executor: deal_state.executor_key,
+            checker: deal_state.checker.as_ref().map(|Checker { checker_key, .. }| *checker_key),
+            deal_mint: deal_state.deal_token_mint,
+            amount: deal_state.amount,
+            service_fee,
+            checker_fee: deal_state.checker_fee(),
+            client_bond: bond_amount(&deal_state.client_bond),
+            executor_bond: bond_amount(&deal_state.executor_bond),
+            holder_mode: deal_state.holder_mode,
+            deadline_ts: deal_state.deadline_ts,
+            timestamp,
+        }
+    }
+}
+
+#[event]
+pub struct PartiallyPaid {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub executor: Pubkey,
+    pub deal_mint: Pubkey,
+    pub amount: u64,
+    pub mode: PaymentMode,
+    /// total released from the escrow to the executor so far, top-ups are not counted
+    pub paid_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CheckerUpdated {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub executor: Pubkey,
+    pub old_checker: Option<Pubkey>,
+    /// `None` once the checker resigned
+    pub new_checker: Option<Pubkey>,
+    pub checker_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DealFinished {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub executor: Pubkey,
+    pub checker: Option<Pubkey>,
+    pub deal_mint: Pubkey,
+    /// the rest of the deal amount transfered to the executor
+    pub payment: u64,
+    /// returned to the client, only a dispute ruling splits the escrow
+    pub refund: u64,
+    pub checker_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DealCancelled {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub executor: Pubkey,
+    pub initializer: Pubkey,
+    pub deal_mint: Pubkey,
+    /// returned to the client from the deal escrow
+    pub refund: u64,
+    /// paid to the checker
+    pub checker_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OfferAccepted {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub executor: Pubkey,
+    pub amount: u64,
+    pub executor_bond: u64,
+    /// Set once every invited party accepted, the deal is active then
+    pub activated: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoleAccepted {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub executor: Pubkey,
+    pub checker: Pubkey,
+    pub checker_fee: u64,
+    pub activated: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MilestoneApproved {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub executor: Pubkey,
+    pub initializer: Pubkey,
+    pub index: u8,
+    /// transfered to the executor
+    pub amount: u64,
+    pub paid_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MilestoneRejected {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub executor: Pubkey,
+    pub initializer: Pubkey,
+    pub index: u8,
+    /// returned to the client
+    pub amount: u64,
+    /// the deal amount without the rejected milestone
+    pub deal_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DealDisputed {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub executor: Pubkey,
+    pub initiator: Pubkey,
+    pub reason_hash: [u8; 32],
+    /// held in the escrow while the dispute is open
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DeliveryMarked {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub executor: Pubkey,
+    pub review_deadline_ts: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DealAmended {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub executor: Pubkey,
+    pub amount: u64,
+    pub deadline_ts: Option<i64>,
+    /// `DealState::amendments` after this amendment
+    pub amendment: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BondReturned {
+    pub deal_id: [u8; 16],
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BondForfeited {
+    pub deal_id: [u8; 16],
+    pub owner: Pubkey,
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HolderTokensReturned {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub holder_mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HolderStaked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    /// The whole stake after the deposit
+    pub staked: u64,
+    pub unlock_ts: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HolderUnstaked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub staked: u64,
+    pub timestamp: i64,
+}
+
+/// A settled holder mode deal no longer counts against the stake of its client
+#[event]
+pub struct HolderStakeReleased {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub active_deals: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CheckerVoted {
+    pub deal_id: [u8; 16],
+    pub checker: Pubkey,
+    pub vote: PanelVote,
+    pub approvals: u8,
+    pub cancellations: u8,
+    /// Set by the vote reaching the threshold, which `finish` or `cancel` casts
+    pub decision: Option<PanelVote>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DealMigrated {
+    pub deal_id: [u8; 16],
+    pub client: Pubkey,
+    pub executor: Pubkey,
+    /// `DealState::version` after the migration
+    pub version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InstructionsPaused {
+    pub authority: Pubkey,
+    /// The `PAUSE_*` bits paused by this call
+    pub flags: u32,
+    /// Every paused instruction afterwards
+    pub paused: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InstructionsUnpaused {
+    pub authority: Pubkey,
+    /// The `PAUSE_*` bits unpaused by this call
+    pub flags: u32,
+    /// Every paused instruction afterwards
+    pub paused: u32,
+    pub timestamp: i64,
+}
@@ -0,0 +1,188 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::Token,
+    token_2022::Token2022,
+    token_interface::{spl_token_2022::cmp_pubkeys, Mint},
+};
+
+use crate::{
+    constants::{CONFIG_SEED, EXECUTOR_BOND_SEED, PAUSE_ACCEPT_OFFER},
+    errors::{ErrorCodes, InvalidAccount},
+    events::OfferAccepted,
+    state::{Bond, DealState, ProgramConfig},
+    utils::{
+        check_or_init_ata, check_token_account, init_bond_vault, token_program_for,
+        transfer_from_wallet, BondsTransfered, DealEscrow, DealStateUpdated, ServiceFeeTransfered,
+    },
+};
+
+#[derive(Accounts)]
+pub struct AcceptOffer<'info> {
+    /// CHECK:
+    #[account(mut, signer, address = deal_state.executor_key)]
+    pub executor: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: in access_control if executor_bond.is_some()
+    pub executor_bond_mint: AccountInfo<'info>,
+    /// CHECK: in access_control if executor_bond.is_some()
+    #[account(mut)]
+    pub executor_bond_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. the executor bond vault, initialized if executor_bond.is_some()
+    #[account(mut)]
+    pub deal_state_executor_bond_ta: AccountInfo<'info>,
+
+    #[account(address = deal_state.deal_token_mint)]
+    pub deal_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: in access_control. the deal state itself for native SOL deals
+    #[account(mut)]
+    pub deal_state_deal_ta: AccountInfo<'info>,
+    /// CHECK: by address
+    #[account(mut, address = config.service_fee_owner)]
+    pub service_fee_owner: AccountInfo<'info>,
+    /// CHECK: in access_control. the deal mint one, may be uninitialized.
+    #[account(mut)]
+    pub service_fee_ta: AccountInfo<'info>,
+
+    #[account(mut, constraint = deal_state.is_offer() @ ErrorCodes::OfferAlreadyAccepted)]
+    pub deal_state: Box<Account<'info, DealState>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    bonds_transfered: BondsTransfered,
+    deal_state_updated: DealStateUpdated,
+    service_fee_transfered: ServiceFeeTransfered,
+}
+
+impl<'info> AcceptOffer<'info> {
+    fn check_accounts(ctx: &Context<AcceptOffer>) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_ACCEPT_OFFER)?;
+        if let Some(Bond { mint, .. }) = ctx.accounts.deal_state.executor_bond.as_ref() {
+            if !cmp_pubkeys(mint, ctx.accounts.executor_bond_mint.key) {
+                return Err(InvalidAccount::ExecutorBondMint.into());
+            }
+            check_token_account(&ctx.accounts.executor_bond_ta, mint, ctx.accounts.executor.key)
+                .map_err(|_| InvalidAccount::ExecutorBondTokenAccount)?;
+            init_bond_vault(
+                &ctx.accounts.payer,
+                &ctx.accounts.executor_bond_mint,
+                &ctx.accounts.deal_state.to_account_info(),
+                &ctx.accounts.deal_state_executor_bond_ta,
+                EXECUTOR_BOND_SEED,
+                &ctx.accounts.token_program_for(&ctx.accounts.executor_bond_mint),
+                &ctx.accounts.system_program.to_account_info(),
+            )
+            .map_err(|_| InvalidAccount::DealStateExecutorBondTokenAccount)?;
+        }
+
+        let deal_mint = ctx.accounts.deal_mint.to_account_info();
+        check_token_account(
+            &ctx.accounts.deal_state_deal_ta,
+            deal_mint.key,
+            ctx.accounts.deal_state.to_account_info().key,
+        )?;
+        check_or_init_ata(
+            &ctx.accounts.payer,
+            &deal_mint,
+            &ctx.accounts.service_fee_owner,
+            &ctx.accounts.service_fee_ta,
+            &ctx.accounts.token_program_for(&deal_mint),
+        )?;
+
+        Ok(())
+    }
+
+    fn token_program_for(&self, mint: &AccountInfo<'info>) -> AccountInfo<'info> {
+        token_program_for(
+            mint,
+            &self.token_program.to_account_info(),
+            &self.token_2022_program.to_account_info(),
+        )
+    }
+
+    fn escrow(&self) -> DealEscrow<'_, 'info> {
+        DealEscrow::new(&self.deal_state, &self.token_program, &self.token_2022_program)
+    }
+
+    fn check_acceptance(&self) -> Result<()> {
+        if self.deal_state.executor_accepted {
+            return Err(ErrorCodes::OfferAlreadyAccepted.into());
+        }
+        if self.deal_state.acceptance_expired() {
+            return Err(ErrorCodes::AcceptanceDeadlineExpired.into());
+        }
+        if self.deal_state.deadline_expired() {
+            return Err(ErrorCodes::DeadlineExpired.into());
+        }
+        Ok(())
+    }
+
+    /// Transfer-fee mints are grossed up, so that the escrow holds exactly the bond amount
+    fn transfer_executor_bond(&self) -> Result<BondsTransfered> {
+        if let Some(Bond { amount, .. }) = self.deal_state.executor_bond {
+            transfer_from_wallet(
+                &self.executor_bond_ta,
+                &self.deal_state_executor_bond_ta,
+                &self.executor_bond_mint,
+                &self.executor,
+                &self.token_program_for(&self.executor_bond_mint),
+                &self.system_program.to_account_info(),
+                amount,
+            )?;
+        }
+        Ok(BondsTransfered)
+    }
+
+    fn update_deal_state(&mut self) -> DealStateUpdated {
+        self.deal_state.executor_accepted = true;
+        self.deal_state.try_activate();
+
+        DealStateUpdated
+    }
+
+    /// The held service fee goes to the service once the offer is active
+    fn transfer_service_fee(&mut self) -> Result<ServiceFeeTransfered> {
+        if self.deal_state.is_active() {
+            let service_fee = std::mem::take(&mut self.deal_state.held_service_fee);
+            self.escrow().transfer(&self.deal_state_deal_ta, &self.service_fee_ta, &*self.deal_mint, service_fee)?;
+        }
+        Ok(ServiceFeeTransfered)
+    }
+}
+
+#[access_control(AcceptOffer::check_accounts(&ctx))]
+pub fn handle(ctx: Context<AcceptOffer>) -> Result<()> {
+    ctx.accounts.check_acceptance()?;
+    let bonds_transfered = ctx.accounts.transfer_executor_bond()?;
+    let deal_state_updated = ctx.accounts.update_deal_state();
+    let service_fee_transfered = ctx.accounts.transfer_service_fee()?;
+
+    let deal_state = &ctx.accounts.deal_state;
+    emit!(OfferAccepted {
+        deal_id: deal_state.id,
+        client: deal_state.client_key,
+        executor: deal_state.executor_key,
+        amount: deal_state.amount,
+        executor_bond: deal_state.executor_bond.as_ref().map_or(0, |Bond { amount, .. }| *amount),
+        activated: deal_state.is_active(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    let _ = Checklist {
+        bonds_transfered,
+        deal_state_updated,
+        service_fee_transfered,
+    };
+
+    Ok(())
+}
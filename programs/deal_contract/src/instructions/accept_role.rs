@@ -0,0 +1,116 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::Token,
+    token_2022::Token2022,
+    token_interface::{spl_token_2022::cmp_pubkeys, Mint},
+};
+
+use crate::{
+    constants::{CONFIG_SEED, PAUSE_ACCEPT_ROLE},
+    errors::{ErrorCodes, InvalidAccount},
+    events::RoleAccepted,
+    state::{Checker, DealState, ProgramConfig},
+    utils::{check_token_account, DealEscrow, DealStateUpdated, ServiceFeeTransfered},
+};
+
+#[derive(Accounts)]
+pub struct AcceptRole<'info> {
+    /// CHECK:
+    #[account(signer, constraint =
+        matches!(deal_state.checker.as_ref(), Some(Checker { checker_key, .. }) if cmp_pubkeys(checker.key, checker_key))
+        @ InvalidAccount::Checker
+    )]
+    pub checker: AccountInfo<'info>,
+
+    #[account(address = deal_state.deal_token_mint)]
+    pub deal_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: in access_control. the deal state itself for native SOL deals
+    #[account(mut)]
+    pub deal_state_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. initialized by `create_offer`
+    #[account(mut)]
+    pub service_fee_ta: AccountInfo<'info>,
+
+    #[account(mut, constraint = deal_state.is_offer() @ ErrorCodes::OfferAlreadyAccepted)]
+    pub deal_state: Box<Account<'info, DealState>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    deal_state_updated: DealStateUpdated,
+    service_fee_transfered: ServiceFeeTransfered,
+}
+
+impl<'info> AcceptRole<'info> {
+    fn check_accounts(ctx: &Context<AcceptRole>) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_ACCEPT_ROLE)?;
+        let deal_mint = ctx.accounts.deal_mint.key();
+        check_token_account(
+            &ctx.accounts.deal_state_deal_ta,
+            &deal_mint,
+            ctx.accounts.deal_state.to_account_info().key,
+        )?;
+        check_token_account(&ctx.accounts.service_fee_ta, &deal_mint, &ctx.accounts.config.service_fee_owner)?;
+
+        Ok(())
+    }
+
+    fn escrow(&self) -> DealEscrow<'_, 'info> {
+        DealEscrow::new(&self.deal_state, &self.token_program, &self.token_2022_program)
+    }
+
+    fn check_acceptance(&self) -> Result<()> {
+        if self.deal_state.checker_accepted {
+            return Err(ErrorCodes::OfferAlreadyAccepted.into());
+        }
+        if self.deal_state.acceptance_expired() {
+            return Err(ErrorCodes::AcceptanceDeadlineExpired.into());
+        }
+        Ok(())
+    }
+
+    fn update_deal_state(&mut self) -> DealStateUpdated {
+        self.deal_state.checker_accepted = true;
+        self.deal_state.try_activate();
+
+        DealStateUpdated
+    }
+
+    /// The held service fee goes to the service once the offer is active
+    fn transfer_service_fee(&mut self) -> Result<ServiceFeeTransfered> {
+        if self.deal_state.is_active() {
+            let service_fee = std::mem::take(&mut self.deal_state.held_service_fee);
+            self.escrow().transfer(&self.deal_state_deal_ta, &self.service_fee_ta, &*self.deal_mint, service_fee)?;
+        }
+        Ok(ServiceFeeTransfered)
+    }
+}
+
+#[access_control(AcceptRole::check_accounts(&ctx))]
+pub fn handle(ctx: Context<AcceptRole>) -> Result<()> {
+    ctx.accounts.check_acceptance()?;
+    let deal_state_updated = ctx.accounts.update_deal_state();
+    let service_fee_transfered = ctx.accounts.transfer_service_fee()?;
+
+    let deal_state = &ctx.accounts.deal_state;
+    emit!(RoleAccepted {
+        deal_id: deal_state.id,
+        client: deal_state.client_key,
+        executor: deal_state.executor_key,
+        checker: ctx.accounts.checker.key(),
+        checker_fee: deal_state.checker_fee(),
+        activated: deal_state.is_active(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    let _ = Checklist {
+        deal_state_updated,
+        service_fee_transfered,
+    };
+
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::Token,
+    token_2022::Token2022,
+    token_interface::{spl_token_2022::cmp_pubkeys, Mint},
+};
+
+use crate::{
+    constants::{CHECKER_PANEL_SEED, CONFIG_SEED, PAUSE_AMEND_DEAL},
+    errors::{ErrorCodes, InvalidAccount},
+    events::DealAmended,
+    state::{Checker, CheckerPanel, DealState, ProgramConfig},
+    utils::{
+        check_token_account, transfer_from_wallet, DeadlineChecked, DealAmountChecked, DealEscrow,
+        DealStateUpdated, DepositTransfered,
+    },
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DeadlineAmendment {
+    Keep,
+    Set(i64),
+    Remove,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AmendDealArgs {
+    pub deadline: DeadlineAmendment,
+    /// New deal amount. A raise is deposited by the client, a cut is refunded to the client
+    pub amount: Option<u64>,
+}
+
+#[derive(Accounts)]
+pub struct AmendDeal<'info> {
+    /// CHECK:
+    #[account(mut, signer, address = deal_state.client_key)]
+    pub client: AccountInfo<'info>,
+    /// CHECK:
+    #[account(signer, address = deal_state.executor_key)]
+    pub executor: AccountInfo<'info>,
+    /// CHECK: in access_control, must sign if the deal has a checker. The panel address for
+    /// panel deals, whose members sign as remaining accounts instead
+    pub checker: AccountInfo<'info>,
+
+    #[account(address = deal_state.deal_token_mint)]
+    pub deal_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: in access_control. the client itself for native SOL deals
+    #[account(mut)]
+    pub client_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. the deal state itself for native SOL deals
+    #[account(mut)]
+    pub deal_state_deal_ta: AccountInfo<'info>,
+
+    #[account(mut,
+        constraint = !deal_state.is_offer() @ ErrorCodes::OfferNotAccepted,
+        constraint = !deal_state.is_disputed() @ ErrorCodes::DealDisputed,
+        constraint = deal_state.is_active() @ ErrorCodes::DealNotActive,
+    )]
+    pub deal_state: Box<Account<'info, DealState>>,
+    #[account(
+        seeds = [CHECKER_PANEL_SEED, deal_state.key().as_ref()],
+        bump = checker_panel.bump(),
+    )]
+    pub checker_panel: Option<Box<Account<'info, CheckerPanel>>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    deadline_checked: DeadlineChecked,
+    amount_checked: DealAmountChecked,
+    deposit_transfered: DepositTransfered,
+    deal_state_updated: DealStateUpdated,
+}
+
+impl<'info> AmendDeal<'info> {
+    fn check_accounts(ctx: &Context<AmendDeal>) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_AMEND_DEAL)?;
+        if let Some(Checker { checker_key, .. }) = ctx.accounts.deal_state.checker.as_ref() {
+            if !cmp_pubkeys(ctx.accounts.checker.key, checker_key) {
+                return Err(InvalidAccount::Checker.into());
+            }
+            if ctx.accounts.deal_state.with_panel {
+                let checker_panel =
+                    ctx.accounts.checker_panel.as_ref().ok_or(InvalidAccount::CheckerPanel)?;
+                require!(
+                    checker_panel.signers(ctx.remaining_accounts) >= checker_panel.threshold as usize,
+                    ErrorCode::AccountNotSigner
+                );
+            } else {
+                require!(ctx.accounts.checker.is_signer, ErrorCode::AccountNotSigner);
+            }
+        }
+
+        check_token_account(
+            &ctx.accounts.client_deal_ta,
+            &ctx.accounts.deal_mint.key(),
+            ctx.accounts.client.key,
+        )?;
+        check_token_account(
+            &ctx.accounts.deal_state_deal_ta,
+            &ctx.accounts.deal_mint.key(),
+            ctx.accounts.deal_state.to_account_info().key,
+        )?;
+
+        Ok(())
+    }
+
+    fn escrow(&self) -> DealEscrow<'_, 'info> {
+        DealEscrow::new(&self.deal_state, &self.token_program, &self.token_2022_program)
+    }
+
+    fn check_deadline(&self, deadline: DeadlineAmendment) -> Result<DeadlineChecked> {
+        if let DeadlineAmendment::Set(deadline_ts) = deadline {
+            if deadline_ts < Clock::get()?.unix_timestamp {
+                return Err(ErrorCodes::DeadlineExpired.into());
+            }
+        }
+        Ok(DeadlineChecked)
+    }
+
+    /// Milestones add up to the deal amount, so such deals keep their amount
+    fn check_amount(&self, amount: Option<u64>) -> Result<DealAmountChecked> {
+        match amount {
+            Some(0) => Err(ErrorCodes::AmountTooLow.into()),
+            Some(amount) if amount != self.deal_state.amount && self.deal_state.with_milestones => {
+                Err(ErrorCodes::DealWithMilestones.into())
+            }
+            _ => Ok(DealAmountChecked),
+        }
+    }
+
+    /// Transfer-fee mints are grossed up, so that the escrow holds exactly the deal amount
+    fn transfer_deposit(&self, deposit: u64) -> Result<()> {
+        let deal_mint = self.deal_mint.to_account_info();
+        transfer_from_wallet(
+            &self.client_deal_ta,
+            &self.deal_state_deal_ta,
+            &deal_mint,
+            &self.client,
+            &self.escrow().token_program_for(&deal_mint),
+            &self.system_program.to_account_info(),
+            deposit,
+        )
+    }
+
+    fn transfer_refund(&self, refund: u64) -> Result<()> {
+        self.escrow().transfer(&self.deal_state_deal_ta, &self.client_deal_ta, &*self.deal_mint, refund)
+    }
+
+    fn update_deal_state(&mut self, args: &AmendDealArgs) -> Result<(u64, u64, DealStateUpdated)> {
+        match args.deadline {
+            DeadlineAmendment::Keep => {}
+            DeadlineAmendment::Set(deadline_ts) => self.deal_state.deadline_ts = Some(deadline_ts),
+            DeadlineAmendment::Remove => self.deal_state.deadline_ts = None,
+        }
+        let (deposit, refund) = match args.amount {
+            Some(amount) => self.deal_state.amend_amount(amount)?,
+            None => (0, 0),
+        };
+        self.deal_state.amendments =
+            self.deal_state.amendments.checked_add(1).ok_or(ErrorCodes::MathOverflow)?;
+
+        Ok((deposit, refund, DealStateUpdated))
+    }
+}
+
+#[access_control(AmendDeal::check_accounts(&ctx))]
+pub fn handle(ctx: Context<AmendDeal>, args: AmendDealArgs) -> Result<()> {
+    let deadline_checked = ctx.accounts.check_deadline(args.deadline)?;
+    let amount_checked = ctx.accounts.check_amount(args.amount)?;
+    let (deposit, refund, deal_state_updated) = ctx.accounts.update_deal_state(&args)?;
+
+    let deposit_transfered = {
+        if deposit > 0 {
+            ctx.accounts.transfer_deposit(deposit)?;
+        }
+        if refund > 0 {
+            ctx.accounts.transfer_refund(refund)?;
+        }
+        DepositTransfered
+    };
+
+    emit!(DealAmended {
+        deal_id: ctx.accounts.deal_state.id,
+        client: ctx.accounts.deal_state.client_key,
+        executor: ctx.accounts.deal_state.executor_key,
+        amount: ctx.accounts.deal_state.amount,
+        deadline_ts: ctx.accounts.deal_state.deadline_ts,
+        amendment: ctx.accounts.deal_state.amendments,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    let _ = Checklist {
+        deadline_checked,
+        amount_checked,
+        deposit_transfered,
+        deal_state_updated,
+    };
+
+    Ok(())
+}
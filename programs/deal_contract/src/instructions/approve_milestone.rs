@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::Token,
+    token_2022::Token2022,
+    token_interface::{spl_token_2022::cmp_pubkeys, Mint},
+};
+
+use crate::{
+    constants::*,
+    errors::{ErrorCodes, InvalidAccount},
+    events::MilestoneApproved,
+    state::{Checker, DealMilestones, DealState, MilestoneStatus, ProgramConfig},
+    utils::{
+        check_or_init_ata, check_token_account, DealEscrow, DealStateUpdated, MilestoneSettled,
+        PaymentTransfered,
+    },
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SettleMilestoneArgs {
+    pub index: u8,
+}
+
+#[derive(Accounts)]
+pub struct ApproveMilestone<'info> {
+    /// CHECK: the client or the checker of the deal
+    #[account(signer, constraint =
+        cmp_pubkeys(initializer.key, &deal_state.client_key)
+        || matches!(deal_state.checker.as_ref(), Some(Checker { checker_key, .. }) if cmp_pubkeys(initializer.key, checker_key))
+        @ InvalidAccount::Initializer
+    )]
+    pub initializer: AccountInfo<'info>,
+    /// CHECK:
+    #[account(address = deal_state.executor_key)]
+    pub executor: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+
+    #[account(address = deal_state.deal_token_mint)]
+    pub deal_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: in access_control. the deal state itself for native SOL deals
+    #[account(mut)]
+    pub deal_state_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub executor_deal_ta: AccountInfo<'info>,
+
+    #[account(mut,
+        constraint = !deal_state.is_offer() @ ErrorCodes::OfferNotAccepted,
+        constraint = !deal_state.is_disputed() @ ErrorCodes::DealDisputed,
+        constraint = deal_state.is_active() @ ErrorCodes::DealNotActive,
+    )]
+    pub deal_state: Box<Account<'info, DealState>>,
+    #[account(mut,
+        seeds = [MILESTONES_SEED, deal_state.key().as_ref()],
+        bump = deal_milestones.bump(),
+    )]
+    pub deal_milestones: Box<Account<'info, DealMilestones>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    milestone_settled: MilestoneSettled,
+    payment_transfered: PaymentTransfered,
+    deal_state_updated: DealStateUpdated,
+}
+
+impl<'info> ApproveMilestone<'info> {
+    fn check_accounts(ctx: &Context<ApproveMilestone>) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_APPROVE_MILESTONE)?;
+        check_token_account(
+            &ctx.accounts.deal_state_deal_ta,
+            &ctx.accounts.deal_mint.key(),
+            ctx.accounts.deal_state.to_account_info().key,
+        )?;
+        check_or_init_ata(
+            &ctx.accounts.payer,
+            &ctx.accounts.deal_mint.to_account_info(),
+            &ctx.accounts.executor,
+            &ctx.accounts.executor_deal_ta,
+            &ctx.accounts.escrow().token_program_for(&ctx.accounts.deal_mint.to_account_info()),
+        )?;
+
+        Ok(())
+    }
+
+    fn escrow(&self) -> DealEscrow<'_, 'info> {
+        DealEscrow::new(&self.deal_state, &self.token_program, &self.token_2022_program)
+    }
+
+    fn settle_milestone(&mut self, index: u8) -> Result<(u64, MilestoneSettled)> {
+        let milestone = self.deal_milestones.pending_milestone(index)?;
+        milestone.status = MilestoneStatus::Approved;
+
+        Ok((milestone.amount, MilestoneSettled))
+    }
+
+    fn transfer_payment(&self, amount: u64) -> Result<PaymentTransfered> {
+        self.escrow().transfer(&self.deal_state_deal_ta, &self.executor_deal_ta, &*self.deal_mint, amount)?;
+        Ok(PaymentTransfered)
+    }
+
+    fn update_deal_state(&mut self, amount: u64) -> Result<DealStateUpdated> {
+        self.deal_state.release(amount)?;
+
+        Ok(DealStateUpdated)
+    }
+}
+
+#[access_control(ApproveMilestone::check_accounts(&ctx))]
+pub fn handle(ctx: Context<ApproveMilestone>, args: SettleMilestoneArgs) -> Result<()> {
+    let (amount, milestone_settled) = ctx.accounts.settle_milestone(args.index)?;
+    let payment_transfered = ctx.accounts.transfer_payment(amount)?;
+    let deal_state_updated = ctx.accounts.update_deal_state(amount)?;
+
+    emit!(MilestoneApproved {
+        deal_id: ctx.accounts.deal_state.id,
+        client: ctx.accounts.deal_state.client_key,
+        executor: ctx.accounts.deal_state.executor_key,
+        initializer: ctx.accounts.initializer.key(),
+        index: args.index,
+        amount,
+        paid_amount: ctx.accounts.deal_state.paid_amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    let _ = Checklist {
+        milestone_settled,
+        payment_transfered,
+        deal_state_updated,
+    };
+
+    Ok(())
+}
@@ -1,17 +1,24 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer},
-    token_interface::spl_token_2022::cmp_pubkeys,
+    token::Token,
+    token_2022::Token2022,
+    token_interface::{spl_token_2022::cmp_pubkeys, Mint, TokenAccount},
 };
 
 use crate::{
     constants::*,
     errors::{ErrorCodes, InvalidAccount},
-    state::{Bond, Checker, DealState},
+    events::{BondForfeited, BondReturned, DealCancelled},
+    state::{
+        Bond, Checker, CheckerPanel, DealMilestones, DealReceipt, DealState, DealStatus, HolderStake,
+        PanelVote, ProgramConfig,
+    },
     utils::{
-        check_ta, init_ata, AccountClosed, BondsTransfered, CheckerFeeTransfered, DeadlineChecked,
-        DepositTransfered,
+        cast_settling_vote, check_bond_vault, check_or_init_ata, check_ta, check_token_account,
+        init_ata, panel_fee_shares, release_holder_stake, AccountClosed, BondsTransfered,
+        CheckerFeeTransfered, DealEscrow, DeadlineChecked, DepositTransfered,
+        HolderModeHandled, ReceiptRecorded,
     },
 };
 
@@ -32,16 +39,12 @@ pub struct Cancel<'info> {
     #[account(mut, signer)]
     pub payer: AccountInfo<'info>,
 
-    #[account(mut,
-        constraint = cmp_pubkeys(&deal_state.deal_token_mint, &deal_state_deal_ta.mint),
-        constraint = cmp_pubkeys(&deal_state_deal_ta.owner, &deal_state.key())
-    )]
-    pub deal_state_deal_ta: Box<Account<'info, TokenAccount>>,
-    #[account(init_if_needed, payer = payer,
-        associated_token::mint = deal_mint,
-        associated_token::authority = client,
-    )]
-    pub client_deal_ta: Box<Account<'info, TokenAccount>>,
+    /// CHECK: in access_control. the deal state itself for native SOL deals
+    #[account(mut)]
+    pub deal_state_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub client_deal_ta: AccountInfo<'info>,
 
     /// CHECK: in access_control. may be uninitialized.
     #[account(mut)]
@@ -53,6 +56,9 @@ pub struct Cancel<'info> {
     /// CHECK: in access_control. may be uninitialized.
     #[account(mut)]
     pub executor_bond_ta: AccountInfo<'info>,
+    /// CHECK: in transfer_bonds. may be uninitialized, used when the executor bond is forfeited
+    #[account(mut)]
+    pub client_executor_bond_ta: AccountInfo<'info>,
 
     /// CHECK: in access_control
     #[account(mut)]
@@ -61,20 +67,51 @@ pub struct Cancel<'info> {
     #[account(mut)]
     pub deal_state_executor_bond_ta: AccountInfo<'info>,
 
-    #[account(constraint = cmp_pubkeys(&deal_mint.key(), &deal_state.deal_token_mint))]
-    pub deal_mint: Box<Account<'info, Mint>>,
+    #[account(mut, constraint = cmp_pubkeys(&deal_mint.key(), &deal_state.deal_token_mint))]
+    pub deal_mint: Box<InterfaceAccount<'info, Mint>>,
     /// CHECK: in transfer_bonds
+    #[account(mut)]
     pub client_bond_mint: AccountInfo<'info>,
     /// CHECK: in transfer_bonds
+    #[account(mut)]
     pub executor_bond_mint: AccountInfo<'info>,
 
-    /// CHECK: constant address
-    #[account(mut, address = SERVICE_FEE_OWNER)]
+    /// CHECK: by address
+    #[account(mut, address = config.service_fee_owner)]
     pub service_fee: AccountInfo<'info>,
-    #[account(mut, close = initializer)]
+    #[account(mut,
+        constraint = !deal_state.is_offer() @ ErrorCodes::OfferNotAccepted,
+        constraint = !deal_state.is_disputed() @ ErrorCodes::DealDisputed,
+        constraint = deal_state.is_active() @ ErrorCodes::DealNotActive,
+        close = initializer
+    )]
     pub deal_state: Box<Account<'info, DealState>>,
+    #[account(mut,
+        seeds = [MILESTONES_SEED, deal_state.key().as_ref()],
+        bump = deal_milestones.bump(),
+        close = initializer
+    )]
+    pub deal_milestones: Option<Box<Account<'info, DealMilestones>>>,
+    #[account(mut,
+        seeds = [CHECKER_PANEL_SEED, deal_state.key().as_ref()],
+        bump = checker_panel.bump(),
+        close = initializer
+    )]
+    pub checker_panel: Option<Box<Account<'info, CheckerPanel>>>,
+    #[account(init,
+        seeds = [RECEIPT_SEED, deal_state.key().as_ref()],
+        bump,
+        payer = payer,
+        space = DealReceipt::SPACE
+    )]
+    pub deal_receipt: Option<Box<Account<'info, DealReceipt>>>,
+    #[account(mut, seeds = [HOLDER_STAKE_SEED, deal_state.client_key.as_ref()], bump = holder_stake.bump())]
+    pub holder_stake: Option<Box<Account<'info, HolderStake>>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
     pub system_program: Program<'info, System>,
 }
 
@@ -82,20 +119,34 @@ enum Initializer {
     Client,
     Executor,
     Checker,
+    /// A member of the checker panel, once the panel voted to cancel
+    Panel,
     Service,
+    /// `mutual_cancel`, signed by both the client and the executor
+    Parties,
 }
 
 impl<'info> TryFrom<&Cancel<'info>> for Initializer {
     type Error = anchor_lang::error::Error;
     fn try_from(value: &Cancel<'info>) -> Result<Self> {
         let initializer_key = &value.initializer.key;
-        if cmp_pubkeys(initializer_key, value.checker.key) {
+        let is_checker = matches!(
+            value.deal_state.checker.as_ref(),
+            Some(Checker { checker_key, .. }) if cmp_pubkeys(initializer_key, checker_key)
+        );
+        let is_panel_member = matches!(
+            value.checker_panel.as_ref(),
+            Some(checker_panel) if checker_panel.is_member(initializer_key)
+        );
+        if is_checker {
             Ok(Initializer::Checker)
-        } else if cmp_pubkeys(&initializer_key, value.client.key) {
+        } else if is_panel_member {
+            Ok(Initializer::Panel)
+        } else if cmp_pubkeys(initializer_key, value.client.key) {
             Ok(Initializer::Client)
-        } else if cmp_pubkeys(&initializer_key, value.executor.key) {
+        } else if cmp_pubkeys(initializer_key, value.executor.key) {
             Ok(Initializer::Executor)
-        } else if cmp_pubkeys(&initializer_key, &SERVICE_ACCOUNT_ADDRESS) {
+        } else if cmp_pubkeys(initializer_key, &value.config.service_account) {
             Ok(Initializer::Service)
         } else {
             Err(InvalidAccount::Initializer.into())
@@ -109,17 +160,43 @@ struct Checklist {
     checker_fee_transfered: CheckerFeeTransfered,
     deposit_transfered: DepositTransfered,
     bonds_transfered: BondsTransfered,
+    holder_mode_handled: HolderModeHandled,
     deal_state_deal_ta_closed: AccountClosed,
+    receipt_recorded: ReceiptRecorded,
 }
 
 impl<'info> Cancel<'info> {
     fn check_accounts(ctx: &Context<Cancel>) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_CANCEL)?;
+        if ctx.accounts.deal_state.with_milestones && ctx.accounts.deal_milestones.is_none() {
+            return Err(InvalidAccount::DealMilestones.into());
+        }
+        if ctx.accounts.deal_state.with_panel && ctx.accounts.checker_panel.is_none() {
+            return Err(InvalidAccount::CheckerPanel.into());
+        }
+
+        check_token_account(
+            &ctx.accounts.deal_state_deal_ta,
+            &ctx.accounts.deal_state.deal_token_mint,
+            ctx.accounts.deal_state.to_account_info().key,
+        )?;
+
+        check_or_init_ata(
+            &ctx.accounts.payer,
+            &ctx.accounts.deal_mint.to_account_info(),
+            &ctx.accounts.client,
+            &ctx.accounts.client_deal_ta,
+            &ctx.accounts.escrow().token_program_for(&ctx.accounts.deal_mint.to_account_info()),
+        )?;
+
         if let Some(Checker { checker_key, .. }) = ctx.accounts.deal_state.checker.as_ref() {
-            if !cmp_pubkeys(ctx.accounts.checker.as_ref().key, &checker_key) {
+            if !cmp_pubkeys(ctx.accounts.checker.as_ref().key, checker_key) {
                 return Err(InvalidAccount::Checker.into());
             };
-
-            match Account::<TokenAccount>::try_from(&ctx.accounts.checker_deal_ta) {
+        }
+        // the panel members are paid through the remaining accounts, see transfer_panel_fee
+        if ctx.accounts.deal_state.checker.is_some() && !ctx.accounts.deal_state.with_panel {
+            match InterfaceAccount::<TokenAccount>::try_from(&ctx.accounts.checker_deal_ta) {
                 Ok(checker_deal_ta) => {
                     check_ta(
                         &checker_deal_ta,
@@ -134,20 +211,25 @@ impl<'info> Cancel<'info> {
                         &ctx.accounts.deal_mint.to_account_info(),
                         &ctx.accounts.checker,
                         &ctx.accounts.checker_deal_ta,
-                        &ctx.accounts.token_program,
+                        &ctx.accounts.escrow().token_program_for(&ctx.accounts.deal_mint.to_account_info()),
                     )?;
                 }
             };
         }
 
         if let Some(Bond { mint, .. }) = ctx.accounts.deal_state.client_bond.as_ref() {
-            if !cmp_pubkeys(&mint, &ctx.accounts.client_bond_mint.key) {
+            if !cmp_pubkeys(mint, ctx.accounts.client_bond_mint.key) {
                 return Err(InvalidAccount::ClientBondMint)?;
             }
 
-            let deal_state_client_bond_ta =
-                Account::<TokenAccount>::try_from(&ctx.accounts.deal_state_client_bond_ta)?;
-            match Account::<TokenAccount>::try_from(&ctx.accounts.client_bond_ta) {
+            check_bond_vault(
+                &ctx.accounts.deal_state_client_bond_ta,
+                &ctx.accounts.client_bond_mint.key(),
+                CLIENT_BOND_SEED,
+                ctx.accounts.deal_state.to_account_info().key,
+            )
+            .map_err(|_| InvalidAccount::DealStateClientBondTokenAccount)?;
+            match InterfaceAccount::<TokenAccount>::try_from(&ctx.accounts.client_bond_ta) {
                 Ok(client_bond_ta) => {
                     check_ta(
                         &client_bond_ta,
@@ -155,12 +237,6 @@ impl<'info> Cancel<'info> {
                         ctx.accounts.client.key,
                     )
                     .map_err(|_| InvalidAccount::ClientBondTokenAccount)?;
-                    check_ta(
-                        &deal_state_client_bond_ta,
-                        &ctx.accounts.client_bond_mint.key(),
-                        ctx.accounts.deal_state.to_account_info().key,
-                    )
-                    .map_err(|_| InvalidAccount::DealStateClientBondTokenAccount)?;
                 }
                 Err(_) => {
                     init_ata(
@@ -168,7 +244,7 @@ impl<'info> Cancel<'info> {
                         &ctx.accounts.client_bond_mint.to_account_info(),
                         &ctx.accounts.client,
                         &ctx.accounts.client_bond_ta,
-                        &ctx.accounts.token_program,
+                        &ctx.accounts.escrow().token_program_for(&ctx.accounts.client_bond_mint),
                     )
                     .map_err(|_| InvalidAccount::ClientBondTokenAccount)?;
                 }
@@ -176,13 +252,18 @@ impl<'info> Cancel<'info> {
         };
 
         if let Some(Bond { mint, .. }) = ctx.accounts.deal_state.executor_bond.as_ref() {
-            if !cmp_pubkeys(&mint, &ctx.accounts.executor_bond_mint.key) {
+            if !cmp_pubkeys(mint, ctx.accounts.executor_bond_mint.key) {
                 return Err(InvalidAccount::ExecutorBondMint)?;
             }
 
-            let deal_state_executor_bond_ta =
-                Account::<TokenAccount>::try_from(&ctx.accounts.deal_state_executor_bond_ta)?;
-            match Account::<TokenAccount>::try_from(&ctx.accounts.executor_bond_ta) {
+            check_bond_vault(
+                &ctx.accounts.deal_state_executor_bond_ta,
+                &ctx.accounts.executor_bond_mint.key(),
+                EXECUTOR_BOND_SEED,
+                ctx.accounts.deal_state.to_account_info().key,
+            )
+            .map_err(|_| InvalidAccount::DealStateExecutorBondTokenAccount)?;
+            match InterfaceAccount::<TokenAccount>::try_from(&ctx.accounts.executor_bond_ta) {
                 Ok(executor_bond_ta) => {
                     check_ta(
                         &executor_bond_ta,
@@ -190,12 +271,6 @@ impl<'info> Cancel<'info> {
                         ctx.accounts.executor.key,
                     )
                     .map_err(|_| InvalidAccount::ExecutorBondTokenAccount)?;
-                    check_ta(
-                        &deal_state_executor_bond_ta,
-                        &ctx.accounts.executor_bond_mint.key(),
-                        ctx.accounts.deal_state.to_account_info().key,
-                    )
-                    .map_err(|_| InvalidAccount::DealStateExecutorBondTokenAccount)?;
                 }
                 Err(_) => {
                     init_ata(
@@ -203,152 +278,312 @@ impl<'info> Cancel<'info> {
                         &ctx.accounts.executor_bond_mint.to_account_info(),
                         &ctx.accounts.executor,
                         &ctx.accounts.executor_bond_ta,
-                        &ctx.accounts.token_program,
+                        &ctx.accounts.escrow().token_program_for(&ctx.accounts.executor_bond_mint),
                     )
                     .map_err(|_| InvalidAccount::ExecutorBondTokenAccount)?;
                 }
             };
         };
 
+        if ctx.accounts.deal_state.holder_mode.is_some() && ctx.accounts.holder_stake.is_none() {
+            return Err(InvalidAccount::HolderStake.into());
+        }
+
         Ok(())
     }
 
-    fn check_deadline(&self) -> Result<DeadlineChecked> {
+    fn check_parties_signed(&self) -> Result<Initializer> {
+        if !cmp_pubkeys(self.initializer.key, self.client.key)
+            && !cmp_pubkeys(self.initializer.key, self.executor.key)
+        {
+            return Err(InvalidAccount::Initializer.into());
+        }
+        require!(self.client.is_signer, ErrorCode::AccountNotSigner);
+        require!(self.executor.is_signer, ErrorCode::AccountNotSigner);
+        Ok(Initializer::Parties)
+    }
+
+    /// A panel member settles with the vote cancelling the deal, see `cast_settling_vote`
+    fn cast_panel_vote(&mut self, initializer: &Initializer) -> Result<()> {
+        let deal_id = self.deal_state.id;
+        if let (Initializer::Panel, Some(checker_panel)) = (initializer, self.checker_panel.as_mut()) {
+            cast_settling_vote(checker_panel, deal_id, self.initializer.key, PanelVote::Cancel)?;
+        }
+        Ok(())
+    }
+
+    /// Once delivered, the client finishes or disputes the deal, `claim_after_review` covers the executor
+    fn check_not_delivered(&self, initializer: &Initializer) -> Result<()> {
+        if matches!(initializer, Initializer::Client | Initializer::Executor)
+            && self.deal_state.review_deadline_ts.is_some()
+        {
+            return Err(ErrorCodes::DealDelivered.into());
+        }
+        Ok(())
+    }
+
+    /// The panel decision stands for the deadline, see cast_panel_vote
+    fn check_deadline(&self, initializer: &Initializer) -> Result<DeadlineChecked> {
+        if let Initializer::Panel = initializer {
+            return Ok(DeadlineChecked);
+        }
         if self.deal_state.deadline_ts.is_some() && !self.deal_state.deadline_expired() {
             return Err(ErrorCodes::DeadlineNotExpired.into());
         }
         Ok(DeadlineChecked)
     }
 
-    fn transfer_checker_fee(&self) -> Result<CheckerFeeTransfered> {
+    fn escrow(&self) -> DealEscrow<'_, 'info> {
+        DealEscrow::new(
+            &self.deal_state,
+            &self.token_program,
+            &self.token_2022_program,
+        )
+    }
+
+    fn transfer_panel_fee(&self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let checker_panel = self.checker_panel.as_ref().ok_or(InvalidAccount::CheckerPanel)?;
+        let deal_mint = self.deal_mint.to_account_info();
+        for (checker, checker_deal_ta, share) in
+            panel_fee_shares(
+                checker_panel,
+                self.deal_state.checker_fee(),
+                &self.deal_state.client_key,
+                remaining_accounts,
+            )?
+        {
+            check_or_init_ata(
+                &self.payer,
+                &deal_mint,
+                checker,
+                checker_deal_ta,
+                &self.escrow().token_program_for(&deal_mint),
+            )
+            .map_err(|_| InvalidAccount::CheckerDealTokenAccount)?;
+            if share > 0 {
+                self.escrow().transfer(&self.deal_state_deal_ta, checker_deal_ta, &deal_mint, share)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The parties may agree to cancel without the checker, whose fee is then refunded to the client
+    fn checker_paid(&self, initializer: &Initializer) -> bool {
+        !matches!(initializer, Initializer::Parties)
+    }
+
+    fn transfer_checker_fee(
+        &self,
+        checker_paid: bool,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<CheckerFeeTransfered> {
+        if !checker_paid {
+            return Ok(CheckerFeeTransfered);
+        }
+        if self.deal_state.with_panel {
+            self.transfer_panel_fee(remaining_accounts)?;
+            return Ok(CheckerFeeTransfered);
+        }
         if let Some(Checker { checker_fee, .. }) = self.deal_state.checker {
-            token::transfer(
-                CpiContext::new_with_signer(
-                    self.token_program.to_account_info(),
-                    Transfer {
-                        from: self.deal_state_deal_ta.to_account_info(),
-                        to: self.checker_deal_ta.to_account_info(),
-                        authority: self.deal_state.to_account_info(),
-                    },
-                    &[&self.deal_state.seeds()[..]],
-                ),
+            self.escrow().transfer(
+                &self.deal_state_deal_ta,
+                &self.checker_deal_ta,
+                &self.deal_mint.to_account_info(),
                 checker_fee,
             )?;
         };
         Ok(CheckerFeeTransfered)
     }
 
-    fn transfer_deposit(&self) -> Result<DepositTransfered> {
-        let amount_to_transfer = self.deal_state.amount.saturating_sub(self.deal_state.paid_amount);
+    fn transfer_deposit(&self, checker_paid: bool) -> Result<DepositTransfered> {
+        let amount_to_transfer = self.deal_state.refund(!checker_paid)?;
         if amount_to_transfer > 0 {
-            token::transfer(
-                CpiContext::new_with_signer(
-                    self.token_program.to_account_info(),
-                    Transfer {
-                        from: self.deal_state_deal_ta.to_account_info(),
-                        to: self.client_deal_ta.to_account_info(),
-                        authority: self.deal_state.to_account_info(),
-                    },
-                    &[&self.deal_state.seeds()],
-                ),
+            self.escrow().transfer(
+                &self.deal_state_deal_ta,
+                &self.client_deal_ta,
+                &self.deal_mint.to_account_info(),
                 amount_to_transfer,
             )?;
         }
         Ok(DepositTransfered)
     }
 
+    /// The parties may agree to cancel without any forfeiture
+    fn executor_bond_forfeited(&self, initializer: &Initializer) -> bool {
+        !matches!(initializer, Initializer::Parties)
+            && self.deal_state.forfeiture.executor_on_deadline
+            && self.deal_state.deadline_expired()
+            && self.deal_state.review_deadline_ts.is_none()
+    }
+
     fn transfer_bonds(&mut self, initializer: Initializer) -> Result<BondsTransfered> {
-        if let Some(Bond { amount, .. }) = self.deal_state.client_bond.as_ref() {
+        if let Some(Bond { amount, mint }) = self.deal_state.client_bond.as_ref() {
             if let Initializer::Executor = initializer {
                 return Err(ErrorCodes::DealWithClientBond.into());
             }
             if *amount > 0 {
-                anchor_spl::token::transfer(
-                    CpiContext::new(
-                        self.token_program.to_account_info(),
-                        token::Transfer {
-                            from: self.deal_state_client_bond_ta.to_account_info(),
-                            to: self.client_bond_ta.to_account_info(),
-                            authority: self.deal_state.to_account_info(),
-                        },
-                    )
-                    .with_signer(&[&self.deal_state.seeds()[..]]),
+                self.escrow().transfer(
+                    &self.deal_state_client_bond_ta,
+                    &self.client_bond_ta,
+                    &self.client_bond_mint,
                     *amount,
                 )?;
             }
+            emit!(BondReturned {
+                deal_id: self.deal_state.id,
+                owner: self.deal_state.client_key,
+                mint: *mint,
+                amount: *amount,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
         }
-        if let Some(Bond { amount, .. }) = self.deal_state.executor_bond.as_ref() {
-            if let Initializer::Client = initializer {
-                return Err(ErrorCodes::DealWithExecutorBond.into());
-            }
-            if *amount > 0 {
-                anchor_spl::token::transfer(
-                    CpiContext::new(
-                        self.token_program.to_account_info(),
-                        token::Transfer {
-                            from: self.deal_state_client_bond_ta.to_account_info(),
-                            to: self.executor_bond_ta.to_account_info(),
-                            authority: self.deal_state.to_account_info(),
-                        },
-                    )
-                    .with_signer(&[&self.deal_state.seeds()[..]]),
-                    *amount,
-                )?;
+        if let Some(Bond { amount, mint }) = self.deal_state.executor_bond.as_ref() {
+            if self.executor_bond_forfeited(&initializer) {
+                check_or_init_ata(
+                    &self.payer,
+                    &self.executor_bond_mint,
+                    &self.client,
+                    &self.client_executor_bond_ta,
+                    &self.escrow().token_program_for(&self.executor_bond_mint),
+                )
+                .map_err(|_| InvalidAccount::ClientExecutorBondTokenAccount)?;
+                if *amount > 0 {
+                    self.escrow().transfer(
+                        &self.deal_state_executor_bond_ta,
+                        &self.client_executor_bond_ta,
+                        &self.executor_bond_mint,
+                        *amount,
+                    )?;
+                }
+                emit!(BondForfeited {
+                    deal_id: self.deal_state.id,
+                    owner: self.deal_state.executor_key,
+                    recipient: self.deal_state.client_key,
+                    mint: *mint,
+                    amount: *amount,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+            } else {
+                if let Initializer::Client = initializer {
+                    return Err(ErrorCodes::DealWithExecutorBond.into());
+                }
+                if *amount > 0 {
+                    self.escrow().transfer(
+                        &self.deal_state_executor_bond_ta,
+                        &self.executor_bond_ta,
+                        &self.executor_bond_mint,
+                        *amount,
+                    )?;
+                }
+                emit!(BondReturned {
+                    deal_id: self.deal_state.id,
+                    owner: self.deal_state.executor_key,
+                    mint: *mint,
+                    amount: *amount,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
             }
         }
 
         if self.deal_state.client_bond.is_some() {
-            self.close_deal_state_ta(&self.deal_state_client_bond_ta.clone())?;
+            self.escrow().close(
+                &self.deal_state_client_bond_ta.clone(),
+                &self.client_bond_mint.clone(),
+                &self.service_fee,
+            )?;
         }
-        if self.deal_state.executor_bond.is_some()
-            && !cmp_pubkeys(
-                self.deal_state_client_bond_ta.key,
-                self.deal_state_executor_bond_ta.key,
-            )
-        {
-            self.close_deal_state_ta(&self.deal_state_executor_bond_ta.clone())?;
+        if self.deal_state.executor_bond.is_some() {
+            self.escrow().close(
+                &self.deal_state_executor_bond_ta.clone(),
+                &self.executor_bond_mint.clone(),
+                &self.service_fee,
+            )?;
         }
 
         Ok(BondsTransfered)
     }
 
-    fn close_deal_state_ta(&self, token_account: &AccountInfo<'info>) -> Result<AccountClosed> {
-        token::close_account(CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
-            CloseAccount {
-                account: token_account.clone(),
-                destination: self.service_fee.to_account_info(),
-                authority: self.deal_state.to_account_info(),
-            },
-            &[&self.deal_state.seeds()[..]],
-        ))?;
-        Ok(AccountClosed)
+    fn record_receipt(&mut self, checker_paid: bool, bump: Option<&u8>) -> Result<ReceiptRecorded> {
+        self.deal_state.status = DealStatus::Cancelled;
+        if let (Some(deal_receipt), Some(bump)) = (self.deal_receipt.as_mut(), bump) {
+            ***deal_receipt = DealReceipt {
+                deal_state: self.deal_state.key(),
+                id: self.deal_state.id,
+                client_key: self.deal_state.client_key,
+                executor_key: self.deal_state.executor_key,
+                deal_token_mint: self.deal_state.deal_token_mint,
+                status: DealStatus::Cancelled,
+                amount: self.deal_state.amount,
+                executor_received: self.deal_state.paid_amount,
+                client_refunded: self.deal_state.refund(!checker_paid)?,
+                checker_fee: if checker_paid { self.deal_state.checker_fee() } else { 0 },
+                settled_by: self.initializer.key(),
+                settled_ts: Clock::get()?.unix_timestamp,
+                bump: [*bump],
+            };
+        }
+        Ok(ReceiptRecorded)
     }
 }
 
 #[access_control(Cancel::check_accounts(&ctx))]
-pub fn handle(ctx: Context<Cancel>) -> Result<()> {
+pub fn handle<'info>(ctx: Context<'_, '_, '_, 'info, Cancel<'info>>) -> Result<()> {
     let initializer = Initializer::try_from(&*ctx.accounts)?;
-    let deadline_checked = ctx.accounts.check_deadline()?;
-    let checker_fee_transfered = ctx.accounts.transfer_checker_fee()?;
-    let deposit_transfered = ctx.accounts.transfer_deposit()?;
+    ctx.accounts.cast_panel_vote(&initializer)?;
+    ctx.accounts.check_not_delivered(&initializer)?;
+    let deadline_checked = ctx.accounts.check_deadline(&initializer)?;
+    settle(ctx, initializer, deadline_checked)
+}
+
+/// The client and the executor agree to cancel, so neither the deadline nor the bonds hold the deal.
+/// The checker fee goes back to the client
+#[access_control(Cancel::check_accounts(&ctx))]
+pub fn handle_mutual<'info>(ctx: Context<'_, '_, '_, 'info, Cancel<'info>>) -> Result<()> {
+    let initializer = ctx.accounts.check_parties_signed()?;
+    settle(ctx, initializer, DeadlineChecked)
+}
+
+fn settle<'info>(
+    ctx: Context<'_, '_, '_, 'info, Cancel<'info>>,
+    initializer: Initializer,
+    deadline_checked: DeadlineChecked,
+) -> Result<()> {
+    let checker_paid = ctx.accounts.checker_paid(&initializer);
+    let checker_fee_transfered =
+        ctx.accounts.transfer_checker_fee(checker_paid, ctx.remaining_accounts)?;
+    let deposit_transfered = ctx.accounts.transfer_deposit(checker_paid)?;
     let bonds_transfered = ctx.accounts.transfer_bonds(initializer)?;
+    let holder_mode_handled =
+        release_holder_stake(&ctx.accounts.deal_state, ctx.accounts.holder_stake.as_deref_mut())?;
 
-    let deal_state_deal_ta_closed = if ctx.accounts.deal_state_deal_ta.to_account_info().lamports()
-        == 0
-    {
-        AccountClosed
-    } else {
-        ctx.accounts
-            .close_deal_state_ta(AsRef::<AccountInfo>::as_ref(&*ctx.accounts.deal_state_deal_ta))?
-    };
+    let deal_state_deal_ta_closed = ctx.accounts.escrow().close(
+        &ctx.accounts.deal_state_deal_ta,
+        &ctx.accounts.deal_mint.to_account_info(),
+        &ctx.accounts.service_fee,
+    )?;
+
+    let receipt_recorded =
+        ctx.accounts.record_receipt(checker_paid, ctx.bumps.get("deal_receipt"))?;
+
+    emit!(DealCancelled {
+        deal_id: ctx.accounts.deal_state.id,
+        client: ctx.accounts.deal_state.client_key,
+        executor: ctx.accounts.deal_state.executor_key,
+        initializer: ctx.accounts.initializer.key(),
+        deal_mint: ctx.accounts.deal_state.deal_token_mint,
+        refund: ctx.accounts.deal_state.refund(!checker_paid)?,
+        checker_fee: if checker_paid { ctx.accounts.deal_state.checker_fee() } else { 0 },
+        timestamp: Clock::get()?.unix_timestamp,
+    });
 
-    Checklist {
+    let _ = Checklist {
         deadline_checked,
         checker_fee_transfered,
         deposit_transfered,
         bonds_transfered,
+        holder_mode_handled,
         deal_state_deal_ta_closed,
+        receipt_recorded,
     };
 
     Ok(())
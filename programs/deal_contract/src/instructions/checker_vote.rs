@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::*,
+    errors::ErrorCodes,
+    events::CheckerVoted,
+    state::{CheckerPanel, DealState, PanelVote, ProgramConfig},
+    utils::DealStateUpdated,
+};
+
+#[derive(Accounts)]
+pub struct CheckerVote<'info> {
+    /// CHECK: a member of the panel, checked in handle
+    #[account(signer)]
+    pub checker: AccountInfo<'info>,
+
+    #[account(
+        constraint = !deal_state.is_offer() @ ErrorCodes::OfferNotAccepted,
+        constraint = !deal_state.is_disputed() @ ErrorCodes::DealDisputed,
+        constraint = deal_state.is_active() @ ErrorCodes::DealNotActive,
+    )]
+    pub deal_state: Box<Account<'info, DealState>>,
+    #[account(mut,
+        seeds = [CHECKER_PANEL_SEED, deal_state.key().as_ref()],
+        bump = checker_panel.bump(),
+    )]
+    pub checker_panel: Box<Account<'info, CheckerPanel>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    panel_updated: DealStateUpdated,
+}
+
+/// Records the vote of a panel member short of the threshold. The vote reaching it is cast by
+/// the member settling the deal with `finish` (approve) or `cancel`, the latter even before
+/// the deal deadline, so a decided deal never waits for its settlement.
+#[access_control(ctx.accounts.config.check_not_paused(PAUSE_CHECKER_VOTE))]
+pub fn handle(ctx: Context<CheckerVote>, vote: PanelVote) -> Result<()> {
+    let panel_updated = {
+        ctx.accounts.checker_panel.vote(ctx.accounts.checker.key, vote)?;
+        if ctx.accounts.checker_panel.decision().is_some() {
+            return Err(ErrorCodes::PanelVoteSettles.into());
+        }
+        DealStateUpdated
+    };
+
+    let panel = &ctx.accounts.checker_panel;
+    emit!(CheckerVoted {
+        deal_id: ctx.accounts.deal_state.id,
+        checker: ctx.accounts.checker.key(),
+        vote,
+        approvals: panel.votes(PanelVote::Approve) as u8,
+        cancellations: panel.votes(PanelVote::Cancel) as u8,
+        decision: panel.decision(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    let _ = Checklist { panel_updated };
+
+    Ok(())
+}
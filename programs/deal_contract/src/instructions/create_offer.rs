@@ -0,0 +1,289 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::Token,
+    token_2022::Token2022,
+    token_interface::{spl_token_2022::cmp_pubkeys, Mint},
+};
+
+use crate::{
+    constants::*,
+    errors::{ErrorCodes, InvalidAccount},
+    events::DealCreated,
+    state::{
+        accounting, Bond, BondForfeiture, Checker, DealState, DealStatus, FeeSchedule, HolderStake,
+        HolderTier, ProgramConfig, DEAL_STATE_VERSION,
+    },
+    utils::{
+        check_or_init_ata, check_token_account, init_bond_vault, token_program_for,
+        transfer_from_wallet, BondsTransfered, CheckerFeeTransfered, DeadlineChecked,
+        DealAmountChecked, DealStateCreated, DepositTransfered, ForfeitureChecked,
+        HolderModeHandled,
+    },
+};
+
+/// Offers are checked by a single `checker`, milestones and checker panels need `initialize`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CreateOfferArgs {
+    pub id: [u8; 16],
+    pub deal_amount: u64,
+    /// Upper bound of the service fee the program computes from the fee schedule of the deal mint
+    pub service_fee: Option<u64>,
+    pub deadline_ts: Option<i64>,
+    /// Must be in the future, the client may withdraw the offer once it passed
+    pub acceptance_deadline_ts: i64,
+    /// References the client holder stake, its largest covered tier discounts the service fee
+    pub holder_mode: bool,
+    pub client_bond: Option<u64>,
+    pub executor_bond: Option<u64>,
+    pub forfeiture: BondForfeiture,
+    pub checker_fee: Option<u64>,
+}
+
+#[derive(Accounts)]
+#[instruction(args: CreateOfferArgs)]
+pub struct CreateOffer<'info> {
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(
+        signer,
+        constraint = !cmp_pubkeys(executor.to_account_info().key, client.to_account_info().key)
+    )]
+    pub client: AccountInfo<'info>,
+    /// CHECK: accepts the offer later with `accept_offer`
+    pub executor: AccountInfo<'info>,
+    /// CHECK: accepts the role later with `accept_role` if checker_fee.is_some()
+    pub checker: AccountInfo<'info>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+
+    pub deal_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: in access_control if client_bond.is_some()
+    pub client_bond_mint: AccountInfo<'info>,
+    /// CHECK: in access_control if executor_bond.is_some()
+    pub executor_bond_mint: AccountInfo<'info>,
+
+    /// CHECK: by address
+    #[account(address = config.service_fee_owner)]
+    pub service_fee_owner: AccountInfo<'info>,
+    /// CHECK: in access_control. the deal mint one, may be uninitialized. Paid once the offer is active
+    #[account(mut)]
+    pub service_fee_ta: AccountInfo<'info>,
+
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub client_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub deal_state_deal_ta: AccountInfo<'info>,
+
+    /// CHECK: in access_control
+    #[account(mut)]
+    pub client_bond_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. the client bond vault, initialized if client_bond.is_some()
+    #[account(mut)]
+    pub deal_state_client_bond_ta: AccountInfo<'info>,
+
+    #[account(init,
+        seeds = [&args.id, DEAL_STATE_SEED, client.key.as_ref(), executor.key.as_ref()],
+        bump,
+        payer = payer,
+        space = DealState::SPACE
+    )]
+    pub deal_state: Box<Account<'info, DealState>>,
+    #[account(mut, seeds = [HOLDER_STAKE_SEED, client.key.as_ref()], bump = holder_stake.bump())]
+    pub holder_stake: Option<Box<Account<'info, HolderStake>>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    #[account(seeds = [FEE_SCHEDULE_SEED, deal_mint.key().as_ref()], bump = fee_schedule.bump())]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+
+#[allow(dead_code)]
+struct Checklist {
+    pub deadline_checked: DeadlineChecked,
+    pub amount_checked: DealAmountChecked,
+    pub forfeiture_checked: ForfeitureChecked,
+
+    pub deal_state_created: DealStateCreated,
+    pub bonds_transfered: BondsTransfered,
+    pub holder_mode_handled: HolderModeHandled,
+    pub deposit_transfered: DepositTransfered,
+    pub checker_fee_transfered: CheckerFeeTransfered,
+}
+
+impl<'info> CreateOffer<'info> {
+    fn check_accounts(ctx: &Context<CreateOffer>, args: &CreateOfferArgs) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_CREATE_OFFER)?;
+        let deal_mint = ctx.accounts.deal_mint.to_account_info();
+        let deal_token_program = ctx.accounts.token_program_for(&deal_mint);
+        for (ta, authority) in [
+            (&ctx.accounts.client_deal_ta, &ctx.accounts.client),
+            (&ctx.accounts.deal_state_deal_ta, &ctx.accounts.deal_state.to_account_info()),
+        ] {
+            check_or_init_ata(&ctx.accounts.payer, &deal_mint, authority, ta, &deal_token_program)?;
+        }
+
+        check_or_init_ata(&ctx.accounts.payer, &deal_mint, &ctx.accounts.service_fee_owner, &ctx.accounts.service_fee_ta, &deal_token_program)?;
+
+        if args.client_bond.is_some() {
+            InterfaceAccount::<Mint>::try_from(&ctx.accounts.client_bond_mint).map_err(|_|InvalidAccount::ClientBondMint)?;
+
+            check_token_account(&ctx.accounts.client_bond_ta, &ctx.accounts.client_bond_mint.key(), ctx.accounts.client.key)
+                .map_err(|_|InvalidAccount::ClientBondTokenAccount)?;
+            init_bond_vault(
+                &ctx.accounts.payer,
+                &ctx.accounts.client_bond_mint,
+                &ctx.accounts.deal_state.to_account_info(),
+                &ctx.accounts.deal_state_client_bond_ta,
+                CLIENT_BOND_SEED,
+                &ctx.accounts.token_program_for(&ctx.accounts.client_bond_mint),
+                &ctx.accounts.system_program.to_account_info(),
+            ).map_err(|_|InvalidAccount::DealStateClientBondTokenAccount)?;
+        };
+
+        // the executor bond is posted with `accept_offer`
+        if args.executor_bond.is_some() {
+            InterfaceAccount::<Mint>::try_from(&ctx.accounts.executor_bond_mint).map_err(|_|InvalidAccount::ExecutorBondMint)?;
+        };
+
+        Ok(())
+    }
+
+    fn token_program_for(&self, mint: &AccountInfo<'info>) -> AccountInfo<'info> {
+        token_program_for(mint, &self.token_program.to_account_info(), &self.token_2022_program.to_account_info())
+    }
+
+    /// All transfers are made by the client. Transfer-fee mints are grossed up, so that `to` receives exactly `amount`
+    fn transfer_from_client(&self, from: &AccountInfo<'info>, to: &AccountInfo<'info>, mint: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        transfer_from_wallet(from, to, mint, &self.client, &self.token_program_for(mint), &self.system_program.to_account_info(), amount)
+    }
+
+    /// An offer must leave the executor time to accept it before the deal deadline
+    fn check_deadline(&self, acceptance_deadline_ts: i64) -> Result<DeadlineChecked> {
+        if self.deal_state.deadline_expired() {
+            return Err(ErrorCodes::DeadlineExpired.into())
+        };
+        if acceptance_deadline_ts <= Clock::get()?.unix_timestamp {
+            return Err(ErrorCodes::AcceptanceDeadlineExpired.into())
+        };
+        if matches!(self.deal_state.deadline_ts, Some(deadline_ts) if acceptance_deadline_ts > deadline_ts) {
+            return Err(ErrorCodes::AcceptanceAfterDeadline.into())
+        };
+        Ok(DeadlineChecked)
+    }
+
+    fn check_deal_amount(&self, deal_amount: u64) -> Result<DealAmountChecked> {
+        if deal_amount == 0 {
+            return Err(ErrorCodes::AmountTooLow.into());
+        }
+        Ok(DealAmountChecked)
+    }
+
+    fn check_forfeiture(&self, args: &CreateOfferArgs) -> Result<ForfeitureChecked> {
+        args.forfeiture.check(args.deadline_ts, args.client_bond, args.executor_bond, args.checker_fee, false)?;
+        Ok(ForfeitureChecked)
+    }
+
+    fn transfer_client_bond(&self, client_bond: Option<u64>) -> Result<BondsTransfered> {
+        if let Some(amount) = client_bond {
+            self.transfer_from_client(&self.client_bond_ta, &self.deal_state_client_bond_ta, &self.client_bond_mint, amount)?;
+        }
+        Ok(BondsTransfered)
+    }
+
+    fn transfer_deposit(&self, amount: u64) -> Result<DepositTransfered> {
+        self.transfer_from_client(&self.client_deal_ta, &self.deal_state_deal_ta, &self.deal_mint.to_account_info(), amount)?;
+        Ok(DepositTransfered)
+    }
+
+    /// The service fee is computed from the fee schedule of the deal mint, holder mode references the client stake for a discount.
+    /// The escrow holds it until the offer is active, see `DealState::held_service_fee`
+    fn handle_service_fee(&mut self, holder_tier: Option<HolderTier>, service_fee_limit: Option<u64>) -> Result<(u64, HolderModeHandled)> {
+        let service_fee = self.fee_schedule.discounted_fee(self.deal_state.amount, holder_tier, service_fee_limit)?;
+        if holder_tier.is_some() {
+            self.holder_stake.as_mut().ok_or(InvalidAccount::HolderStake)?.acquire()?;
+        }
+        self.deal_state.held_service_fee = service_fee;
+        self.transfer_from_client(&self.client_deal_ta, &self.deal_state_deal_ta, &self.deal_mint.to_account_info(), service_fee)?;
+
+        Ok((service_fee, HolderModeHandled))
+    }
+}
+
+#[access_control(CreateOffer::check_accounts(&ctx, &args))]
+pub fn handle(ctx: Context<CreateOffer>, args: CreateOfferArgs) -> Result<()> {
+    let holder_tier = if args.holder_mode {
+        Some(ctx.accounts.config.stake_tier(ctx.accounts.holder_stake.as_deref())?)
+    } else {
+        None
+    };
+
+    let deal_state_created = {
+        **ctx.accounts.deal_state = DealState {
+            id: args.id,
+            client_key: *ctx.accounts.client.key,
+            executor_key: *ctx.accounts.executor.key,
+
+            bump: [*ctx.bumps.get("deal_state").unwrap()],
+            version: DEAL_STATE_VERSION,
+            client_bond: args.client_bond.map(|amount| Bond {mint: ctx.accounts.client_bond_mint.key(), amount}),
+            executor_bond: args.executor_bond.map(|amount| Bond {mint: ctx.accounts.executor_bond_mint.key(), amount}),
+            forfeiture: args.forfeiture,
+            checker: args.checker_fee.map(|checker_fee| Checker {checker_fee, checker_key: ctx.accounts.checker.key()}),
+
+            amount: args.deal_amount,
+            paid_amount: 0,
+
+            deadline_ts: args.deadline_ts,
+            status: DealStatus::Created,
+            with_milestones: false,
+            with_panel: false,
+            acceptance_deadline_ts: Some(args.acceptance_deadline_ts),
+            executor_accepted: false,
+            checker_accepted: false,
+            amendments: 0,
+            review_deadline_ts: None,
+            held_service_fee: 0,
+            deal_token_mint: ctx.accounts.deal_mint.key(),
+            holder_mode: holder_tier.map(|holder_tier| holder_tier.amount),
+        };
+        DealStateCreated
+    };
+
+    let deadline_checked = ctx.accounts.check_deadline(args.acceptance_deadline_ts)?;
+
+    let amount_checked = ctx.accounts.check_deal_amount(args.deal_amount)?;
+
+    let forfeiture_checked = ctx.accounts.check_forfeiture(&args)?;
+
+    let (service_fee, holder_mode_handled) = ctx.accounts.handle_service_fee(holder_tier, args.service_fee)?;
+
+    let (deposit_transfered, checker_fee_transfered) = {
+        ctx.accounts.transfer_deposit(accounting::deposit(args.deal_amount, args.checker_fee)?)?;
+        (DepositTransfered, CheckerFeeTransfered)
+    };
+
+    let bonds_transfered = ctx.accounts.transfer_client_bond(args.client_bond)?;
+
+    emit!(DealCreated::new(&ctx.accounts.deal_state, service_fee, Clock::get()?.unix_timestamp));
+
+    let _ = Checklist {
+        deadline_checked,
+        amount_checked,
+        forfeiture_checked,
+        checker_fee_transfered,
+        deposit_transfered,
+        deal_state_created,
+        bonds_transfered,
+        holder_mode_handled,
+    };
+
+    Ok(())
+}
@@ -1,15 +1,35 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{token::{self, CloseAccount, Token, TokenAccount, Transfer, Mint}, token_interface::spl_token_2022::cmp_pubkeys, associated_token::AssociatedToken};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::Token,
+    token_2022::Token2022,
+    token_interface::{spl_token_2022::cmp_pubkeys, Mint, TokenAccount},
+};
 
-use crate::{constants::*, state::{DealState, Checker, Bond}, 
-    utils::{CheckerFeeTransfered, PaymentTransfered, BondsTransfered, AccountClosed, init_ata, check_ta, HolderModeHandled}, errors::InvalidAccount};
+use crate::{
+    constants::*,
+    errors::{ErrorCodes, InvalidAccount},
+    events::{BondReturned, DealFinished},
+    state::{
+        Bond, Checker, CheckerPanel, DealMilestones, DealReceipt, DealState, DealStatus, HolderStake,
+        PanelVote, ProgramConfig,
+    },
+    utils::{
+        cast_settling_vote, check_bond_vault, check_or_init_ata, check_ta, check_token_account,
+        init_ata, panel_fee_shares, release_holder_stake, AccountClosed, BondsTransfered,
+        CheckerFeeTransfered, DealEscrow, HolderModeHandled, PaymentTransfered,
+        ReceiptRecorded,
+    },
+};
 
 #[derive(Accounts)]
 pub struct Finish<'info> {
     /// CHECK:
     #[account(mut, signer, constraint = 
-        cmp_pubkeys(&initializer.key, checker.key)
-        || cmp_pubkeys(&initializer.key, client.key)
+        cmp_pubkeys(initializer.key, checker.key)
+        || cmp_pubkeys(initializer.key, client.key)
+        || cmp_pubkeys(initializer.key, executor.key)
+        || checker_panel.as_ref().is_some_and(|checker_panel| checker_panel.is_member(initializer.key))
     )]
     pub initializer: AccountInfo<'info>,
     /// CHECK:
@@ -24,22 +44,12 @@ pub struct Finish<'info> {
     #[account(mut, signer)]
     pub payer: AccountInfo<'info>,
     
-    #[account(mut,
-        constraint = cmp_pubkeys(&deal_state.deal_token_mint, &deal_state_deal_ta.mint),
-        constraint = cmp_pubkeys(&deal_state_deal_ta.owner, &deal_state.key())
-    )]
-    pub deal_state_deal_ta: Box<Account<'info, TokenAccount>>,
-    /// CHECK: may be uninitialized. check in access_control
+    /// CHECK: check in access_control. the deal state itself for native SOL deals
     #[account(mut)]
-    pub deal_state_holder_ta: AccountInfo<'info>,
+    pub deal_state_deal_ta: AccountInfo<'info>,
     /// CHECK: may be uninitialized. check in access_control
     #[account(mut)]
-    pub client_holder_ta: AccountInfo<'info>,
-    #[account(init_if_needed, payer = initializer,
-        associated_token::mint = deal_mint,
-        associated_token::authority = executor,
-    )]
-    pub executor_deal_ta: Box<Account<'info, TokenAccount>>,
+    pub executor_deal_ta: AccountInfo<'info>,
     /// CHECK: may be uninitialized. check in access_control
     #[account(mut)]
     pub checker_deal_ta: AccountInfo<'info>,
@@ -57,76 +67,104 @@ pub struct Finish<'info> {
     #[account(mut)]
     pub executor_bond_ta: AccountInfo<'info>,
 
-    #[account(address = deal_state.deal_token_mint)]
-    pub deal_mint: Box<Account<'info, Mint>>,
-    #[account(address = HOLDER_MINT)]
-    pub holder_mint: Box<Account<'info, Mint>>,
-    pub client_bond_mint: Box<Account<'info, Mint>>,
-    pub executor_bond_mint: Box<Account<'info, Mint>>,
+    #[account(mut, address = deal_state.deal_token_mint)]
+    pub deal_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub client_bond_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub executor_bond_mint: Box<InterfaceAccount<'info, Mint>>,
 
-    /// CHECK: constant address
-    #[account(mut, address = SERVICE_FEE_OWNER)]
+    /// CHECK: by address
+    #[account(mut, address = config.service_fee_owner)]
     pub service_fee: AccountInfo<'info>,
-    #[account(mut, constraint = 
+    #[account(mut, 
+        constraint = !deal_state.is_offer() @ ErrorCodes::OfferNotAccepted,
+        constraint = !deal_state.is_disputed() @ ErrorCodes::DealDisputed,
+        constraint = deal_state.is_active() @ ErrorCodes::DealNotActive,
+        constraint = 
         cmp_pubkeys(initializer.to_account_info().key, &deal_state.client_key) 
             || if let Some(Checker{checker_key, ..}) = deal_state.checker.as_ref() { 
-                cmp_pubkeys(initializer.to_account_info().key, &checker_key)} else { true },
+                cmp_pubkeys(initializer.to_account_info().key, checker_key)} else { false }
+            || cmp_pubkeys(initializer.to_account_info().key, &deal_state.executor_key) && deal_state.review_deadline_ts.is_some()
+            || deal_state.with_panel,
         close = service_fee
     )]
     pub deal_state: Box<Account<'info, DealState>>,
+    #[account(mut, seeds = [HOLDER_STAKE_SEED, deal_state.client_key.as_ref()], bump = holder_stake.bump())]
+    pub holder_stake: Option<Box<Account<'info, HolderStake>>>,
+    #[account(mut,
+        seeds = [MILESTONES_SEED, deal_state.key().as_ref()],
+        bump = deal_milestones.bump(),
+        close = service_fee
+    )]
+    pub deal_milestones: Option<Box<Account<'info, DealMilestones>>>,
+    #[account(mut,
+        seeds = [CHECKER_PANEL_SEED, deal_state.key().as_ref()],
+        bump = checker_panel.bump(),
+        close = service_fee
+    )]
+    pub checker_panel: Option<Box<Account<'info, CheckerPanel>>>,
+    #[account(init,
+        seeds = [RECEIPT_SEED, deal_state.key().as_ref()],
+        bump,
+        payer = payer,
+        space = DealReceipt::SPACE
+    )]
+    pub deal_receipt: Option<Box<Account<'info, DealReceipt>>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Finish<'info> {
     fn check_accounts(ctx: &Context<Finish>) -> Result<()> {
-        if ctx.accounts.deal_state.holder_mode.is_some() {
-            let deal_state_holder_ta = Account::<TokenAccount>::try_from(&ctx.accounts.deal_state_holder_ta)
-                .map_err(|_|InvalidAccount::DealStateHolderTokenAccount)?;
-            if !cmp_pubkeys(&deal_state_holder_ta.owner, ctx.accounts.deal_state.to_account_info().key)
-            || !cmp_pubkeys(&deal_state_holder_ta.mint, &HOLDER_MINT){
-                return Err(InvalidAccount::DealStateHolderTokenAccount.into())
-            }
+        ctx.accounts.config.check_not_paused(PAUSE_FINISH)?;
+        if ctx.accounts.deal_state.with_milestones && ctx.accounts.deal_milestones.is_none() {
+            return Err(InvalidAccount::DealMilestones.into());
+        }
+        if ctx.accounts.deal_state.with_panel && ctx.accounts.checker_panel.is_none() {
+            return Err(InvalidAccount::CheckerPanel.into());
+        }
 
-            match Account::<TokenAccount>::try_from(&ctx.accounts.client_holder_ta) {
-                Ok(client_holder_ta) => {
-                    if !cmp_pubkeys(&client_holder_ta.owner, ctx.accounts.client.to_account_info().key)
-                    || !cmp_pubkeys(&client_holder_ta.mint, &HOLDER_MINT){
-                        return Err(InvalidAccount::DealStateHolderTokenAccount.into())
-                    }
-                },
-                Err(_) => {
-                    init_ata(
-                        &ctx.accounts.payer, 
-                        &ctx.accounts.holder_mint.to_account_info(), 
-                        &ctx.accounts.client.to_account_info(), 
-                        &ctx.accounts.client_holder_ta.to_account_info(), 
-                        &ctx.accounts.token_program.to_account_info()
-                    )?;
-                }
-            }
+        check_token_account(&ctx.accounts.deal_state_deal_ta, &ctx.accounts.deal_state.deal_token_mint, &ctx.accounts.deal_state.key())?;
+
+        check_or_init_ata(
+            &ctx.accounts.payer,
+            &ctx.accounts.deal_mint.to_account_info(),
+            &ctx.accounts.executor,
+            &ctx.accounts.executor_deal_ta,
+            &ctx.accounts.escrow().token_program_for(&ctx.accounts.deal_mint.to_account_info()),
+        )?;
+
+        if ctx.accounts.deal_state.holder_mode.is_some() && ctx.accounts.holder_stake.is_none() {
+            return Err(InvalidAccount::HolderStake.into());
         }
 
         if let Some(Checker{checker_key, ..}) = ctx.accounts.deal_state.checker.as_ref() {
-            match Account::<TokenAccount>::try_from(&ctx.accounts.checker_deal_ta) {
-                Ok(checker_deal_ta) => {
-                    if !cmp_pubkeys(ctx.accounts.checker.as_ref().key, &checker_key) {
-                        return Err(InvalidAccount::Checker.into())
-                    };
-                    if !cmp_pubkeys(&checker_deal_ta.owner, &checker_key)
-                    || !cmp_pubkeys(&checker_deal_ta.mint, &ctx.accounts.deal_mint.key()) {
-                        return Err(InvalidAccount::CheckerDealTokenAccount.into())
-                    };
-                },
-                Err(_) => {
-                    init_ata(
-                        &ctx.accounts.payer, 
-                        &ctx.accounts.deal_mint.to_account_info(), 
-                        &ctx.accounts.checker.to_account_info(), 
-                        &ctx.accounts.checker_deal_ta.to_account_info(), 
-                        &ctx.accounts.token_program.to_account_info()
-                    )?;
+            if !cmp_pubkeys(ctx.accounts.checker.as_ref().key, checker_key) {
+                return Err(InvalidAccount::Checker.into())
+            };
+            // the panel members are paid through the remaining accounts, see transfer_panel_fee
+            if !ctx.accounts.deal_state.with_panel {
+                match InterfaceAccount::<TokenAccount>::try_from(&ctx.accounts.checker_deal_ta) {
+                    Ok(checker_deal_ta) => {
+                        if !cmp_pubkeys(&checker_deal_ta.owner, checker_key)
+                        || !cmp_pubkeys(&checker_deal_ta.mint, &ctx.accounts.deal_mint.key()) {
+                            return Err(InvalidAccount::CheckerDealTokenAccount.into())
+                        };
+                    },
+                    Err(_) => {
+                        init_ata(
+                            &ctx.accounts.payer, 
+                            &ctx.accounts.deal_mint.to_account_info(), 
+                            &ctx.accounts.checker.to_account_info(), 
+                            &ctx.accounts.checker_deal_ta.to_account_info(), 
+                            &ctx.accounts.escrow().token_program_for(&ctx.accounts.deal_mint.to_account_info())
+                        )?;
+                    }
                 }
             }
         }
@@ -135,9 +173,9 @@ impl<'info> Finish<'info> {
             if !cmp_pubkeys(&ctx.accounts.client_bond_mint.key(), mint) {
                 return Err(InvalidAccount::ClientBondMint.into())
             }
-            let deal_state_client_bond_ta = Account::<TokenAccount>::try_from(&ctx.accounts.deal_state_client_bond_ta)?;
-            check_ta(&deal_state_client_bond_ta, &ctx.accounts.client_bond_mint.key(), &ctx.accounts.deal_state.key())?;
-            match Account::<TokenAccount>::try_from(&ctx.accounts.client_bond_ta) {
+            check_bond_vault(&ctx.accounts.deal_state_client_bond_ta, &ctx.accounts.client_bond_mint.key(), CLIENT_BOND_SEED, &ctx.accounts.deal_state.key())
+                .map_err(|_| InvalidAccount::DealStateClientBondTokenAccount)?;
+            match InterfaceAccount::<TokenAccount>::try_from(&ctx.accounts.client_bond_ta) {
                 Ok(client_bond_ta) => {
                     check_ta(&client_bond_ta, &ctx.accounts.client_bond_mint.key(), ctx.accounts.client.key)?;
                 },
@@ -147,7 +185,7 @@ impl<'info> Finish<'info> {
                         &ctx.accounts.client_bond_mint.to_account_info(), 
                         &ctx.accounts.client,
                         &ctx.accounts.client_bond_ta, 
-                        &ctx.accounts.token_program.to_account_info() 
+                        &ctx.accounts.escrow().token_program_for(&ctx.accounts.client_bond_mint.to_account_info()) 
                     )?;
                 }
             }
@@ -157,9 +195,9 @@ impl<'info> Finish<'info> {
             if !cmp_pubkeys(&ctx.accounts.executor_bond_mint.key(), mint) {
                 return Err(InvalidAccount::ExecutorBondMint.into())
             }
-            let deal_state_executor_bond_ta = Account::<TokenAccount>::try_from(&ctx.accounts.deal_state_executor_bond_ta)?;
-            check_ta(&deal_state_executor_bond_ta, &ctx.accounts.executor_bond_mint.key(), &ctx.accounts.deal_state.key())?;
-            match Account::<TokenAccount>::try_from(&ctx.accounts.executor_bond_ta) {
+            check_bond_vault(&ctx.accounts.deal_state_executor_bond_ta, &ctx.accounts.executor_bond_mint.key(), EXECUTOR_BOND_SEED, &ctx.accounts.deal_state.key())
+                .map_err(|_| InvalidAccount::DealStateExecutorBondTokenAccount)?;
+            match InterfaceAccount::<TokenAccount>::try_from(&ctx.accounts.executor_bond_ta) {
                 Ok(executor_bond_ta) => {
                     check_ta(&executor_bond_ta, &ctx.accounts.executor_bond_mint.key(), ctx.accounts.executor.key)?;
                 },
@@ -169,7 +207,7 @@ impl<'info> Finish<'info> {
                         &ctx.accounts.executor_bond_mint.to_account_info(), 
                         &ctx.accounts.executor,
                         &ctx.accounts.executor_bond_ta, 
-                        &ctx.accounts.token_program.to_account_info() 
+                        &ctx.accounts.escrow().token_program_for(&ctx.accounts.executor_bond_mint.to_account_info()) 
                     )?;
                 }
             }
@@ -178,88 +216,104 @@ impl<'info> Finish<'info> {
         Ok(())
     }
 
+    fn escrow(&self) -> DealEscrow<'_, 'info> {
+        DealEscrow::new(
+            &self.deal_state,
+            &self.token_program,
+            &self.token_2022_program,
+        )
+    }
+
     fn transfer_payment(&self) -> Result<PaymentTransfered> {
-        let amount_to_transfer = self.deal_state.amount.saturating_sub( self.deal_state.paid_amount );
+        let amount_to_transfer = self.deal_state.remaining_escrow()?;
 
         if amount_to_transfer > 0 {
-            token::transfer(CpiContext::new_with_signer(self.token_program.to_account_info(), Transfer {
-                from: self.deal_state_deal_ta.to_account_info(),
-                to: self.executor_deal_ta.to_account_info(),
-                authority: self.deal_state.to_account_info(),
-            }, &[&self.deal_state.seeds()]), amount_to_transfer)?;
+            self.escrow().transfer(&self.deal_state_deal_ta, &self.executor_deal_ta, &*self.deal_mint, amount_to_transfer)?;
         }
         Ok(PaymentTransfered)
     }
 
-    fn transfer_checker_fee(&self) -> Result<CheckerFeeTransfered> {
+    /// A panel member settles with the vote approving the deal, see `cast_settling_vote`
+    fn cast_panel_vote(&mut self) -> Result<()> {
+        let deal_id = self.deal_state.id;
+        if let Some(checker_panel) = self.checker_panel.as_mut() {
+            if checker_panel.is_member(self.initializer.key) {
+                cast_settling_vote(checker_panel, deal_id, self.initializer.key, PanelVote::Approve)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn transfer_panel_fee(&self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let checker_panel = self.checker_panel.as_ref().ok_or(InvalidAccount::CheckerPanel)?;
+        let deal_mint = self.deal_mint.to_account_info();
+        for (checker, checker_deal_ta, share) in panel_fee_shares(checker_panel, self.deal_state.checker_fee(), &self.deal_state.client_key, remaining_accounts)? {
+            check_or_init_ata(&self.payer, &deal_mint, checker, checker_deal_ta, &self.escrow().token_program_for(&deal_mint))
+                .map_err(|_| InvalidAccount::CheckerDealTokenAccount)?;
+            if share > 0 {
+                self.escrow().transfer(&self.deal_state_deal_ta, checker_deal_ta, &*self.deal_mint, share)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn transfer_checker_fee(&self, remaining_accounts: &[AccountInfo<'info>]) -> Result<CheckerFeeTransfered> {
+        if self.deal_state.with_panel {
+            self.transfer_panel_fee(remaining_accounts)?;
+            return Ok(CheckerFeeTransfered);
+        }
         if let Some(Checker { checker_fee, .. }) = self.deal_state.checker {
             if checker_fee > 0 {
-                token::transfer(CpiContext::new_with_signer(self.token_program.to_account_info(), Transfer {
-                    from: self.deal_state_deal_ta.to_account_info(),
-                    to: self.checker_deal_ta.to_account_info(),
-                    authority: self.deal_state.to_account_info(),
-                }, &[&self.deal_state.seeds()[..]]), checker_fee)?;
+                self.escrow().transfer(&self.deal_state_deal_ta, &self.checker_deal_ta, &*self.deal_mint, checker_fee)?;
             }
         }
         Ok(CheckerFeeTransfered)
     }
 
     fn transfer_bonds(&self) -> Result<BondsTransfered> {
-        if let Some(Bond{ amount, .. }) = self.deal_state.client_bond {
+        if let Some(Bond{ amount, mint }) = self.deal_state.client_bond {
             if amount > 0 {
-                token::transfer(CpiContext::new_with_signer(self.token_program.to_account_info(), Transfer {
-                    from: self.deal_state_client_bond_ta.to_account_info(),
-                    to: self.client_bond_ta.to_account_info(),
-                    authority: self.deal_state.to_account_info(),
-                }, &[&self.deal_state.seeds()[..]]), amount)?;
+                self.escrow().transfer(&self.deal_state_client_bond_ta, &self.client_bond_ta, &*self.client_bond_mint, amount)?;
             }
+            emit!(BondReturned { deal_id: self.deal_state.id, owner: self.deal_state.client_key, mint, amount, timestamp: Clock::get()?.unix_timestamp });
         }
-        if let Some(Bond{ amount, .. }) = self.deal_state.executor_bond {
+        if let Some(Bond{ amount, mint }) = self.deal_state.executor_bond {
             if amount > 0 {
-                token::transfer(CpiContext::new_with_signer(self.token_program.to_account_info(), Transfer {
-                    from: self.deal_state_executor_bond_ta.to_account_info(),
-                    to: self.executor_bond_ta.to_account_info(),
-                    authority: self.deal_state.to_account_info(),
-                }, &[&self.deal_state.seeds()[..]]), amount)?;
+                self.escrow().transfer(&self.deal_state_executor_bond_ta, &self.executor_bond_ta, &*self.executor_bond_mint, amount)?;
             }
+            emit!(BondReturned { deal_id: self.deal_state.id, owner: self.deal_state.executor_key, mint, amount, timestamp: Clock::get()?.unix_timestamp });
         }
 
         if self.deal_state.client_bond.is_some() {
-            self.close_deal_state_ta(&self.deal_state_client_bond_ta.clone())?;
+            self.escrow().close(&self.deal_state_client_bond_ta.clone(), &*self.client_bond_mint, &self.service_fee)?;
         }
-        if self.deal_state.executor_bond.is_some() && !cmp_pubkeys(self.deal_state_client_bond_ta.key, self.deal_state_executor_bond_ta.key) {
-            self.close_deal_state_ta(&self.deal_state_executor_bond_ta.clone())?;
+        if self.deal_state.executor_bond.is_some() {
+            self.escrow().close(&self.deal_state_executor_bond_ta.clone(), &*self.executor_bond_mint, &self.service_fee)?;
         }
 
         Ok(BondsTransfered)
     }
 
-    fn handle_holder_mode(&self) -> Result<HolderModeHandled> {
-        if let Some(amount) = self.deal_state.holder_mode {
-            token::transfer(
-                CpiContext::new_with_signer(self.token_program.to_account_info(),
-                    Transfer { 
-                        from: self.deal_state_holder_ta.to_account_info(),
-                        to: self.client_holder_ta.to_account_info(),
-                        authority: self.deal_state.to_account_info() 
-                    }, 
-                    &[&self.deal_state.seeds()[..]]
-                ), 
-                amount
-            )?;
+    fn record_receipt(&mut self, bump: Option<&u8>) -> Result<ReceiptRecorded> {
+        self.deal_state.status = DealStatus::Finished;
+        if let (Some(deal_receipt), Some(bump)) = (self.deal_receipt.as_mut(), bump) {
+            ***deal_receipt = DealReceipt {
+                deal_state: self.deal_state.key(),
+                id: self.deal_state.id,
+                client_key: self.deal_state.client_key,
+                executor_key: self.deal_state.executor_key,
+                deal_token_mint: self.deal_state.deal_token_mint,
+                status: DealStatus::Finished,
+                amount: self.deal_state.amount,
+                executor_received: self.deal_state.amount,
+                client_refunded: 0,
+                checker_fee: if let Some(Checker { checker_fee, .. }) = self.deal_state.checker { checker_fee } else { 0 },
+                settled_by: self.initializer.key(),
+                settled_ts: Clock::get()?.unix_timestamp,
+                bump: [*bump],
+            };
         }
-
-        Ok(HolderModeHandled)
-    }
-
-    fn close_deal_state_ta(&self, token_account: &AccountInfo<'info>) -> Result<AccountClosed> {
-        token::close_account(
-            CpiContext::new_with_signer(self.token_program.to_account_info(), CloseAccount {
-                account: token_account.clone(),
-                destination: self.service_fee.to_account_info(),
-                authority: self.deal_state.to_account_info(),
-        }, &[&self.deal_state.seeds()[..]]))?;
-        Ok(AccountClosed)
+        Ok(ReceiptRecorded)
     }
 }
 
@@ -270,28 +324,65 @@ struct Checklist {
     bonds_transfered: BondsTransfered,
     holder_mode_handled: HolderModeHandled,
     deal_state_deal_ta_closed: AccountClosed,
+    receipt_recorded: ReceiptRecorded,
 }
 
 
 #[access_control(Finish::check_accounts(&ctx))]
-pub fn handle(ctx: Context<Finish>) -> Result<()> {
+pub fn handle<'info>(ctx: Context<'_, '_, '_, 'info, Finish<'info>>) -> Result<()> {
+    if cmp_pubkeys(ctx.accounts.initializer.key, &ctx.accounts.deal_state.executor_key) {
+        return Err(InvalidAccount::Initializer.into());
+    }
+    ctx.accounts.cast_panel_vote()?;
+    settle(ctx)
+}
+
+/// The executor settles a delivered deal the client neither finished nor disputed in time
+#[access_control(Finish::check_accounts(&ctx))]
+pub fn handle_claim<'info>(ctx: Context<'_, '_, '_, 'info, Finish<'info>>) -> Result<()> {
+    if !cmp_pubkeys(ctx.accounts.initializer.key, &ctx.accounts.deal_state.executor_key) {
+        return Err(InvalidAccount::Initializer.into());
+    }
+    if !ctx.accounts.deal_state.review_expired() {
+        return Err(ErrorCodes::ReviewWindowNotExpired.into());
+    }
+    settle(ctx)
+}
+
+fn settle<'info>(ctx: Context<'_, '_, '_, 'info, Finish<'info>>) -> Result<()> {
     let payment_transfered = ctx.accounts.transfer_payment()?;
-    let checker_fee_transfered = ctx.accounts.transfer_checker_fee()?;
+    let checker_fee_transfered = ctx.accounts.transfer_checker_fee(ctx.remaining_accounts)?;
     let bonds_transfered = ctx.accounts.transfer_bonds()?;
-    let holder_mode_handled = ctx.accounts.handle_holder_mode()?;
+    let holder_mode_handled =
+        release_holder_stake(&ctx.accounts.deal_state, ctx.accounts.holder_stake.as_deref_mut())?;
 
-    let deal_state_deal_ta_closed = if ctx.accounts.deal_state_deal_ta.to_account_info().lamports() == 0 { 
-        AccountClosed 
-    } else {
-        ctx.accounts.close_deal_state_ta(&ctx.accounts.deal_state_deal_ta.to_account_info())? 
-    };
+    let deal_state_deal_ta_closed = ctx.accounts.escrow().close(
+        &ctx.accounts.deal_state_deal_ta,
+        &*ctx.accounts.deal_mint,
+        &ctx.accounts.service_fee,
+    )?;
+
+    let receipt_recorded = ctx.accounts.record_receipt(ctx.bumps.get("deal_receipt"))?;
+
+    emit!(DealFinished {
+        deal_id: ctx.accounts.deal_state.id,
+        client: ctx.accounts.deal_state.client_key,
+        executor: ctx.accounts.deal_state.executor_key,
+        checker: ctx.accounts.deal_state.checker.as_ref().map(|Checker { checker_key, .. }| *checker_key),
+        deal_mint: ctx.accounts.deal_state.deal_token_mint,
+        payment: ctx.accounts.deal_state.remaining_escrow()?,
+        refund: 0,
+        checker_fee: ctx.accounts.deal_state.checker_fee(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
 
-    Checklist {
+    let _ = Checklist {
         checker_fee_transfered,
         payment_transfered,
         bonds_transfered,
         holder_mode_handled,
         deal_state_deal_ta_closed,
+        receipt_recorded,
     };
     
     Ok(())
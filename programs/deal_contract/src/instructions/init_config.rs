@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::CONFIG_SEED,
+    errors::InvalidAccount,
+    program::DealContract,
+    state::{ConfigArgs, ProgramConfig, CONFIG_VERSION},
+};
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    /// CHECK: must be the upgrade authority of the program
+    #[account(signer)]
+    pub authority: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, DealContract>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()) @ InvalidAccount::Authority)]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(init,
+        seeds = [CONFIG_SEED],
+        bump,
+        payer = payer,
+        space = ProgramConfig::SPACE
+    )]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handle(ctx: Context<InitConfig>, args: ConfigArgs) -> Result<()> {
+    ctx.accounts.config.bump = [*ctx.bumps.get("config").unwrap()];
+    ctx.accounts.config.version = CONFIG_VERSION;
+    ctx.accounts.config.apply(args)
+}
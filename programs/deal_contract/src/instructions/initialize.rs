@@ -1,25 +1,48 @@
 use anchor_lang::prelude::*;
 
-use anchor_spl::{token::{
-    self, Mint, TokenAccount, Transfer, Token,
-}, token_interface::spl_token_2022::cmp_pubkeys, associated_token::AssociatedToken};
-
-use crate::{constants::*, 
-    errors::{ErrorCodes, InvalidAccount}, 
-    state::{DealState, Bond, Checker }, 
-    utils::{DeadlineChecked, DealStateCreated, BondsTransfered, HolderModeHandled, DepositTransfered, CheckerFeeTransfered, DealAmountChecked, check_ta, init_ata, AdvancePaymentTransfered}};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::Token,
+    token_2022::Token2022,
+    token_interface::{spl_token_2022::cmp_pubkeys, Mint},
+};
+
+use crate::{
+    constants::*,
+    errors::{ErrorCodes, InvalidAccount},
+    events::{DealCreated, PartiallyPaid},
+    instructions::PaymentMode,
+    state::{
+        accounting, Bond, BondForfeiture, Checker, CheckerPanel, CheckerPanelArgs, DealMilestones,
+        DealState, DealStatus, FeeSchedule, HolderStake, HolderTier, Milestone, MilestoneArgs,
+        PanelMember, ProgramConfig, DEAL_STATE_VERSION, MAX_MILESTONES, MAX_PANEL_CHECKERS,
+    },
+    utils::{
+        check_or_init_ata, check_token_account, init_bond_vault, token_program_for,
+        transfer_from_wallet, AdvancePaymentChecked, AdvancePaymentTransfered, BondsTransfered,
+        CheckerFeeTransfered, CheckerPanelChecked, DeadlineChecked, DealAmountChecked, DealEscrow,
+        DealStateCreated, DepositTransfered, ForfeitureChecked, HolderModeHandled,
+        MilestonesChecked,
+    },
+};
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct InitializeArgs {
     pub id: [u8; 16],
     pub deal_amount: u64,
-    pub service_fee: u64,
+    /// Upper bound of the service fee the program computes from the fee schedule of the deal mint
+    pub service_fee: Option<u64>,
     pub deadline_ts: Option<i64>,
+    /// References the client holder stake, its largest covered tier discounts the service fee
     pub holder_mode: bool,
     pub client_bond: Option<u64>,
     pub executor_bond: Option<u64>,
+    pub forfeiture: BondForfeiture,
     pub checker_fee: Option<u64>,
+    /// The deal is checked by a panel instead of the `checker` account, `checker_fee` is split among its voters
+    pub checker_panel: Option<CheckerPanelArgs>,
     pub advance_payment_amount: u64,
+    pub milestones: Vec<MilestoneArgs>,
 }
 
 #[derive(Accounts)]
@@ -41,45 +64,29 @@ pub struct Initialize<'info> {
     #[account(mut, signer)]
     pub checker: AccountInfo<'info>,
 
-    pub deal_mint: Box<Account<'info, Mint>>,
+    pub deal_mint: Box<InterfaceAccount<'info, Mint>>,
     /// CHECK: in access_control if client_bond.is_some()
     pub client_bond_mint: AccountInfo<'info>,
     /// CHECK: in access_control if executor_bond.is_some()
     pub executor_bond_mint: AccountInfo<'info>,
-    pub service_mint: Box<Account<'info, Mint>>,
-    #[account(address = HOLDER_MINT )]
-    pub holder_mint: Box<Account<'info, Mint>>,
     
     /// CHECK: by address
-    #[account(address = SERVICE_FEE_OWNER)]
+    #[account(address = config.service_fee_owner)]
     pub service_fee_owner: AccountInfo<'info>,
-    #[account(init_if_needed, payer = payer,
-        associated_token::mint = service_mint,
-        associated_token::authority = service_fee_owner,
-    )]
-    pub service_fee_ta: Box<Account<'info, TokenAccount>>,
+    /// CHECK: in access_control. the deal mint one, may be uninitialized.
+    #[account(mut)]
+    pub service_fee_ta: AccountInfo<'info>,
 
-    #[account(init_if_needed, payer = payer,
-        associated_token::mint = service_mint,
-        associated_token::authority = client,
-    )]
-    pub client_service_ta: Box<Account<'info, TokenAccount>>,
-    
-    #[account(init_if_needed, payer = payer,
-        associated_token::mint = deal_mint,
-        associated_token::authority = client,
-    )]
-    pub client_deal_ta: Box<Account<'info, TokenAccount>>,
-    #[account(init_if_needed, payer = payer,
-        associated_token::mint = deal_mint,
-        associated_token::authority = executor,
-    )]
-    pub executor_deal_ta: Box<Account<'info, TokenAccount>>,
-    #[account(init_if_needed, payer = payer,
-        associated_token::mint = deal_mint,
-        associated_token::authority = deal_state,
-    )]
-    pub deal_state_deal_ta: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub client_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub executor_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub deal_state_deal_ta: AccountInfo<'info>,
 
     /// CHECK: in access_control
     #[account(mut)]
@@ -87,29 +94,43 @@ pub struct Initialize<'info> {
     /// CHECK: in access_control
     #[account(mut)]
     pub executor_bond_ta: AccountInfo<'info>,
-    /// CHECK: in access_control
+    /// CHECK: in access_control. the client bond vault, initialized if client_bond.is_some()
     #[account(mut)]
     pub deal_state_client_bond_ta: AccountInfo<'info>,
-    /// CHECK: in access_control
+    /// CHECK: in access_control. the executor bond vault, initialized if executor_bond.is_some()
     #[account(mut)]
     pub deal_state_executor_bond_ta: AccountInfo<'info>,
 
-    /// CHECK: must be checked if holder_mode
-    #[account(mut)]
-    pub client_holder_ta: AccountInfo<'info>,
-    /// CHECK: must be initialized if holder_mode
-    #[account(mut)]
-    pub deal_state_holder_ta: AccountInfo<'info>,
-
     #[account(init,
         seeds = [&args.id, DEAL_STATE_SEED, client.key.as_ref(), executor.key.as_ref()],
         bump,
         payer = payer, 
-        space = 8 + std::mem::size_of::<DealState>() // 8 is for anchor discriminator
+        space = DealState::SPACE
     )]
     pub deal_state: Box<Account<'info, DealState>>,
+    #[account(mut, seeds = [HOLDER_STAKE_SEED, client.key.as_ref()], bump = holder_stake.bump())]
+    pub holder_stake: Option<Box<Account<'info, HolderStake>>>,
+    #[account(init,
+        seeds = [MILESTONES_SEED, deal_state.key().as_ref()],
+        bump,
+        payer = payer,
+        space = DealMilestones::SPACE
+    )]
+    pub deal_milestones: Option<Box<Account<'info, DealMilestones>>>,
+    #[account(init,
+        seeds = [CHECKER_PANEL_SEED, deal_state.key().as_ref()],
+        bump,
+        payer = payer,
+        space = CheckerPanel::SPACE
+    )]
+    pub checker_panel: Option<Box<Account<'info, CheckerPanel>>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    #[account(seeds = [FEE_SCHEDULE_SEED, deal_mint.key().as_ref()], bump = fee_schedule.bump())]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
@@ -118,6 +139,10 @@ pub struct Initialize<'info> {
 struct Checklist {
     pub deadline_checked: DeadlineChecked,
     pub amount_checked: DealAmountChecked,
+    pub milestones_checked: MilestonesChecked,
+    pub advance_payment_checked: AdvancePaymentChecked,
+    pub forfeiture_checked: ForfeitureChecked,
+    pub checker_panel_checked: CheckerPanelChecked,
 
     pub deal_state_created: DealStateCreated,
     pub bonds_transfered: BondsTransfered,
@@ -130,82 +155,66 @@ struct Checklist {
 
 impl<'info> Initialize<'info> {
     fn check_accounts(ctx: &Context<Initialize>, args: &InitializeArgs) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_INITIALIZE)?;
+        let deal_mint = ctx.accounts.deal_mint.to_account_info();
+        let deal_token_program = ctx.accounts.token_program_for(&deal_mint);
+        for (ta, authority) in [
+            (&ctx.accounts.client_deal_ta, &ctx.accounts.client),
+            (&ctx.accounts.executor_deal_ta, &ctx.accounts.executor),
+            (&ctx.accounts.deal_state_deal_ta, &ctx.accounts.deal_state.to_account_info()),
+        ] {
+            check_or_init_ata(&ctx.accounts.payer, &deal_mint, authority, ta, &deal_token_program)?;
+        }
+
+        check_or_init_ata(&ctx.accounts.payer, &deal_mint, &ctx.accounts.service_fee_owner, &ctx.accounts.service_fee_ta, &deal_token_program)?;
+
         if args.client_bond.is_some() {
-            Account::<Mint>::try_from(&ctx.accounts.client_bond_mint).map_err(|_|InvalidAccount::ClientBondMint)?;
+            InterfaceAccount::<Mint>::try_from(&ctx.accounts.client_bond_mint).map_err(|_|InvalidAccount::ClientBondMint)?;
 
-            let client_bond_ta = Account::<TokenAccount>::try_from(&ctx.accounts.client_bond_ta)?;
-            check_ta(&client_bond_ta, &ctx.accounts.client_bond_mint.key(), ctx.accounts.client.key)
+            check_token_account(&ctx.accounts.client_bond_ta, &ctx.accounts.client_bond_mint.key(), ctx.accounts.client.key)
                 .map_err(|_|InvalidAccount::ClientBondTokenAccount)?;
 
-            match Account::<TokenAccount>::try_from(&ctx.accounts.deal_state_client_bond_ta) {
-                Ok(deal_state_client_bond_ta) => {
-                    check_ta(&deal_state_client_bond_ta, &ctx.accounts.client_bond_mint.key(), &ctx.accounts.deal_state.key())                
-                        .map_err(|_|InvalidAccount::DealStateClientBondTokenAccount)?;
-                }, 
-                Err(_) => {
-                    init_ata(
-                        &ctx.accounts.payer, 
-                        &ctx.accounts.client_bond_mint.to_account_info(), 
-                        &ctx.accounts.deal_state.to_account_info(),
-                        &ctx.accounts.deal_state_client_bond_ta, 
-                        &ctx.accounts.token_program.to_account_info() 
-                    )?;
-                }
-            };
+            init_bond_vault(
+                &ctx.accounts.payer,
+                &ctx.accounts.client_bond_mint,
+                &ctx.accounts.deal_state.to_account_info(),
+                &ctx.accounts.deal_state_client_bond_ta,
+                CLIENT_BOND_SEED,
+                &ctx.accounts.token_program_for(&ctx.accounts.client_bond_mint),
+                &ctx.accounts.system_program.to_account_info(),
+            ).map_err(|_|InvalidAccount::DealStateClientBondTokenAccount)?;
         };
 
         if args.executor_bond.is_some() {
-            Account::<Mint>::try_from(&ctx.accounts.executor_bond_mint).map_err(|_|InvalidAccount::ExecutorBondMint)?;
+            InterfaceAccount::<Mint>::try_from(&ctx.accounts.executor_bond_mint).map_err(|_|InvalidAccount::ExecutorBondMint)?;
 
-            let executor_bond_ta = Account::<TokenAccount>::try_from(&ctx.accounts.executor_bond_ta)?;
-            check_ta(&executor_bond_ta, &ctx.accounts.executor_bond_mint.key(), ctx.accounts.executor.key)
+            check_token_account(&ctx.accounts.executor_bond_ta, &ctx.accounts.executor_bond_mint.key(), ctx.accounts.executor.key)
                 .map_err(|_|InvalidAccount::ExecutorBondTokenAccount)?;
 
-            match Account::<TokenAccount>::try_from(&ctx.accounts.deal_state_executor_bond_ta) {
-                Ok(deal_state_executor_bond_ta) => {
-                    check_ta(&deal_state_executor_bond_ta, &ctx.accounts.executor_bond_mint.key(), &ctx.accounts.deal_state.key())
-                        .map_err(|_|InvalidAccount::DealStateExecutorBondTokenAccount)?;
-                }, 
-                Err(_) => {
-                    init_ata(
-                        &ctx.accounts.payer, 
-                        &ctx.accounts.executor_bond_mint.to_account_info(), 
-                        &ctx.accounts.deal_state.to_account_info(),
-                        &ctx.accounts.deal_state_executor_bond_ta, 
-                        &ctx.accounts.token_program.to_account_info() 
-                    )?;
-                }
-            };
+            init_bond_vault(
+                &ctx.accounts.payer,
+                &ctx.accounts.executor_bond_mint,
+                &ctx.accounts.deal_state.to_account_info(),
+                &ctx.accounts.deal_state_executor_bond_ta,
+                EXECUTOR_BOND_SEED,
+                &ctx.accounts.token_program_for(&ctx.accounts.executor_bond_mint),
+                &ctx.accounts.system_program.to_account_info(),
+            ).map_err(|_|InvalidAccount::DealStateExecutorBondTokenAccount)?;
         };
 
-        if args.holder_mode {
-            let client_holder_ta = Account::<TokenAccount>::try_from(&ctx.accounts.client_holder_ta)?;
-            check_ta(&client_holder_ta, &HOLDER_MINT, ctx.accounts.client.key).map_err(|_|InvalidAccount::ClientHolderTokenAccount)?;
+        Ok(())
+    }
 
-            if ctx.accounts.client_deal_ta.mint != SERVICE_FEE_MINT { 
-                return Err(ErrorCodes::HolderModeUnavailable.into()); 
-            }
-            
-            match Account::<TokenAccount>::try_from(&ctx.accounts.deal_state_holder_ta) {
-                Ok(deal_state_holder_ta ) => {
-                    check_ta(&deal_state_holder_ta, &HOLDER_MINT, ctx.accounts.deal_state.to_account_info().key)
-                        .map_err(|_|InvalidAccount::DealStateHolderTokenAccount)?;
-                },
-                Err(_) => {
-                    init_ata(
-                        &ctx.accounts.payer, 
-                        &ctx.accounts.holder_mint.to_account_info(), 
-                        &ctx.accounts.deal_state.to_account_info(), 
-                        &ctx.accounts.deal_state_holder_ta, 
-                        &ctx.accounts.token_program.to_account_info()
-                    )?;
-                }
-            };
-        }
+    fn token_program_for(&self, mint: &AccountInfo<'info>) -> AccountInfo<'info> {
+        token_program_for(mint, &self.token_program.to_account_info(), &self.token_2022_program.to_account_info())
+    }
 
-        Ok(())
+    /// Transfer-fee mints are grossed up, so that `to` receives exactly `amount`
+    fn transfer_from_wallet(&self, from: &AccountInfo<'info>, to: &AccountInfo<'info>, mint: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        transfer_from_wallet(from, to, mint, authority, &self.token_program_for(mint), &self.system_program.to_account_info(), amount)
     }
-    
+
     fn check_deadline(&self) -> Result<DeadlineChecked> {
         if self.deal_state.deadline_expired() {
             return Err(ErrorCodes::DeadlineExpired.into())
@@ -220,80 +229,108 @@ impl<'info> Initialize<'info> {
         Ok(DealAmountChecked)
     }
 
-    fn transfer_bonds(&self, client_bond: Option<u64>, executor_bond: Option<u64>) -> Result<BondsTransfered> {
-        if let Some(amount) = client_bond.as_ref() {
-            if *amount > 0 {
-                anchor_spl::token::transfer(CpiContext::new(
-                    self.token_program.to_account_info(),
-                    token::Transfer {
-                        from: self.client_bond_ta.to_account_info(),
-                        to: self.deal_state_client_bond_ta.to_account_info(),
-                        authority: self.client.to_account_info(),
-                    },
-                ), *amount)?;
+    fn check_advance_payment(&self, deal_amount: u64, advance_payment_amount: u64) -> Result<AdvancePaymentChecked> {
+        if advance_payment_amount > deal_amount {
+            return Err(ErrorCodes::AdvanceExceedsDealAmount.into());
+        }
+        if advance_payment_amount > accounting::max_advance(deal_amount, self.config.max_advance_bps) {
+            return Err(ErrorCodes::AdvanceExceedsMaxAdvance.into());
+        }
+        Ok(AdvancePaymentChecked)
+    }
+
+    fn check_forfeiture(&self, args: &InitializeArgs) -> Result<ForfeitureChecked> {
+        args.forfeiture.check(args.deadline_ts, args.client_bond, args.executor_bond, args.checker_fee, args.checker_panel.is_some())?;
+        Ok(ForfeitureChecked)
+    }
+
+    fn check_milestones(&mut self, args: &InitializeArgs) -> Result<MilestonesChecked> {
+        if args.milestones.is_empty() {
+            if self.deal_milestones.is_some() {
+                return Err(InvalidAccount::DealMilestones.into());
+            }
+            return Ok(MilestonesChecked);
+        }
+        if args.milestones.len() > MAX_MILESTONES {
+            return Err(ErrorCodes::TooManyMilestones.into());
+        }
+
+        let total = args.milestones.iter()
+            .try_fold(args.advance_payment_amount, |total, milestone| accounting::add(total, milestone.amount))?;
+        if total != args.deal_amount {
+            return Err(ErrorCodes::MilestonesAmountMismatch.into());
+        }
+
+        let deal_state = self.deal_state.key();
+        let deal_milestones = self.deal_milestones.as_mut().ok_or(InvalidAccount::DealMilestones)?;
+        deal_milestones.deal_state = deal_state;
+        deal_milestones.milestones = args.milestones.iter().map(Milestone::from).collect();
+
+        Ok(MilestonesChecked)
+    }
+
+    /// Panel members are distinct third parties, any two thresholds of them overlap
+    fn check_checker_panel(&mut self, args: &InitializeArgs) -> Result<CheckerPanelChecked> {
+        let Some(CheckerPanelArgs { checkers, threshold }) = args.checker_panel.as_ref() else {
+            if self.checker_panel.is_some() {
+                return Err(InvalidAccount::CheckerPanel.into());
             }
+            return Ok(CheckerPanelChecked);
+        };
+        if args.checker_fee.is_none() {
+            return Err(ErrorCodes::InvalidCheckerPanel.into());
+        }
+        if checkers.len() < 2 || checkers.len() > MAX_PANEL_CHECKERS {
+            return Err(ErrorCodes::InvalidCheckerPanel.into());
+        }
+        if (*threshold as usize) * 2 <= checkers.len() || *threshold as usize > checkers.len() {
+            return Err(ErrorCodes::InvalidCheckerPanel.into());
         }
-        if let Some(amount) = executor_bond.as_ref() {
-            if *amount > 0 {
-                anchor_spl::token::transfer(CpiContext::new(
-                    self.token_program.to_account_info(),
-                    token::Transfer {
-                        from: self.executor_bond_ta.to_account_info(),
-                        to: self.deal_state_client_bond_ta.to_account_info(),
-                        authority: self.executor.to_account_info(),
-                    },
-                ), *amount)?;
+        for (index, checker) in checkers.iter().enumerate() {
+            if checkers[..index].contains(checker)
+                || cmp_pubkeys(checker, self.client.key)
+                || cmp_pubkeys(checker, self.executor.key) {
+                return Err(ErrorCodes::InvalidCheckerPanel.into());
             }
         }
+
+        let deal_state = self.deal_state.key();
+        let checker_panel = self.checker_panel.as_mut().ok_or(InvalidAccount::CheckerPanel)?;
+        checker_panel.deal_state = deal_state;
+        checker_panel.members = checkers.iter().map(|checker_key| PanelMember { checker_key: *checker_key, vote: None }).collect();
+        checker_panel.threshold = *threshold;
+
+        Ok(CheckerPanelChecked)
+    }
+
+    fn transfer_bonds(&self, client_bond: Option<u64>, executor_bond: Option<u64>) -> Result<BondsTransfered> {
+        if let Some(amount) = client_bond {
+            self.transfer_from_wallet(&self.client_bond_ta, &self.deal_state_client_bond_ta, &self.client_bond_mint, &self.client, amount)?;
+        }
+        if let Some(amount) = executor_bond {
+            self.transfer_from_wallet(&self.executor_bond_ta, &self.deal_state_executor_bond_ta, &self.executor_bond_mint, &self.executor, amount)?;
+        }
         Ok(BondsTransfered)
     }
 
     fn transfer_deposit(&self, amount: u64) -> Result<DepositTransfered> {
-        anchor_spl::token::transfer(CpiContext::new(
-            self.token_program.to_account_info(),
-            token::Transfer {
-                from: self.client_deal_ta.to_account_info(),
-                to: self.deal_state_deal_ta.to_account_info(),
-                authority: self.client.to_account_info(),
-            },
-        ), amount)?;
+        self.transfer_from_wallet(&self.client_deal_ta, &self.deal_state_deal_ta, &self.deal_mint.to_account_info(), &self.client, amount)?;
         Ok(DepositTransfered)
     }
 
-    fn handle_service_fee(&self, holder_mode: bool, service_fee: u64) -> Result<HolderModeHandled> {
-        if !holder_mode && service_fee == 0 {
-            return Err(ErrorCodes::FeeIsTooLow.into());
-        };
-
-        if holder_mode {
-            let cpi_accounts = Transfer {
-                from: self.client_holder_ta.to_account_info(),
-                to: self.deal_state_holder_ta.to_account_info(),
-                authority: self.client.clone(),
-            };
-            token::transfer(CpiContext::new(self.token_program.to_account_info(), cpi_accounts), HOLDER_MODE_AMOUNT)?;
-        } else if service_fee > 0 {
-            let cpi_accounts = Transfer {
-                from: self.client_deal_ta.to_account_info(),
-                to: self.service_fee_ta.to_account_info(),
-                authority: self.client.clone(),
-            };
-            token::transfer(CpiContext::new(self.token_program.to_account_info(), cpi_accounts), service_fee)?;        
+    /// The service fee is computed from the fee schedule of the deal mint, holder mode references the client stake for a discount
+    fn handle_service_fee(&mut self, holder_tier: Option<HolderTier>, service_fee_limit: Option<u64>) -> Result<(u64, HolderModeHandled)> {
+        let service_fee = self.fee_schedule.discounted_fee(self.deal_state.amount, holder_tier, service_fee_limit)?;
+        if holder_tier.is_some() {
+            self.holder_stake.as_mut().ok_or(InvalidAccount::HolderStake)?.acquire()?;
         }
-        
-        Ok(HolderModeHandled)
+        self.transfer_from_wallet(&self.client_deal_ta, &self.service_fee_ta, &self.deal_mint.to_account_info(), &self.client, service_fee)?;
+
+        Ok((service_fee, HolderModeHandled))
     }
 
     fn transfer_advance_payment(&self, amount: u64) -> Result<AdvancePaymentTransfered> {
-        let cpi_accounts = Transfer {
-            from: self.deal_state_deal_ta.to_account_info(),
-            to: self.executor_deal_ta.to_account_info(),
-            authority: self.deal_state.to_account_info(),
-        };
-        token::transfer(
-            CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
-                .with_signer(&[&self.deal_state.seeds()]), 
-            amount)?;
+        DealEscrow::new(&self.deal_state, &self.token_program, &self.token_2022_program).transfer(&self.deal_state_deal_ta, &self.executor_deal_ta, &*self.deal_mint, amount)?;
 
         Ok(AdvancePaymentTransfered)
     }
@@ -301,6 +338,12 @@ impl<'info> Initialize<'info> {
 
 #[access_control(Initialize::check_accounts(&ctx, &args))]
 pub fn handle(ctx: Context<Initialize>, args: InitializeArgs) -> Result<()> {
+    let holder_tier = if args.holder_mode {
+        Some(ctx.accounts.config.stake_tier(ctx.accounts.holder_stake.as_deref())?)
+    } else {
+        None
+    };
+
     let deal_state_created = {
         **ctx.accounts.deal_state = DealState {
             id: args.id,
@@ -308,18 +351,33 @@ pub fn handle(ctx: Context<Initialize>, args: InitializeArgs) -> Result<()> {
             executor_key: *ctx.accounts.executor.to_account_info().key,
 
             bump: [*ctx.bumps.get("deal_state").unwrap()],
+            version: DEAL_STATE_VERSION,
             client_bond: if let Some(amount) = args.client_bond { Some(Bond {mint: ctx.accounts.client_bond_mint.key(), amount}) } else { None },
             executor_bond: if let Some(amount) = args.executor_bond { Some(Bond {mint: ctx.accounts.executor_bond_mint.key(), amount}) } else { None },
+            forfeiture: args.forfeiture,
             checker: if let Some(checker_fee) = args.checker_fee.as_ref() { 
-                Some(Checker {checker_fee: *checker_fee, checker_key: ctx.accounts.checker.key()})
+                let checker_key = match ctx.accounts.checker_panel.as_ref() {
+                    Some(checker_panel) => checker_panel.key(),
+                    None => ctx.accounts.checker.key(),
+                };
+                Some(Checker {checker_fee: *checker_fee, checker_key})
             } else { None },
 
             amount: args.deal_amount,
             paid_amount: args.advance_payment_amount,
 
             deadline_ts: args.deadline_ts,
+            status: DealStatus::Active,
+            with_milestones: !args.milestones.is_empty(),
+            with_panel: args.checker_panel.is_some(),
+            acceptance_deadline_ts: None,
+            executor_accepted: true,
+            checker_accepted: true,
+            amendments: 0,
+            review_deadline_ts: None,
+            held_service_fee: 0,
             deal_token_mint: ctx.accounts.deal_mint.to_account_info().key(),
-            holder_mode: if args.holder_mode { Some(HOLDER_MODE_AMOUNT) } else { None },
+            holder_mode: holder_tier.map(|holder_tier| holder_tier.amount),
         };
         DealStateCreated
     };
@@ -328,20 +386,65 @@ pub fn handle(ctx: Context<Initialize>, args: InitializeArgs) -> Result<()> {
     
     let amount_checked = ctx.accounts.check_deal_amount(args.deal_amount)?;
 
-    let holder_mode_handled = ctx.accounts.handle_service_fee(args.holder_mode, args.service_fee)?;
+    let advance_payment_checked = ctx.accounts.check_advance_payment(args.deal_amount, args.advance_payment_amount)?;
+
+    let forfeiture_checked = ctx.accounts.check_forfeiture(&args)?;
+
+    let checker_panel_checked = {
+        if let Some(bump) = ctx.bumps.get("checker_panel") {
+            if let Some(checker_panel) = ctx.accounts.checker_panel.as_mut() {
+                checker_panel.bump = [*bump];
+            }
+        }
+        ctx.accounts.check_checker_panel(&args)?
+    };
+
+    let milestones_checked = {
+        if let Some(bump) = ctx.bumps.get("deal_milestones") {
+            if let Some(deal_milestones) = ctx.accounts.deal_milestones.as_mut() {
+                deal_milestones.bump = [*bump];
+            }
+        }
+        ctx.accounts.check_milestones(&args)?
+    };
+
+    let (service_fee, holder_mode_handled) = ctx.accounts.handle_service_fee(holder_tier, args.service_fee)?;
     
     let (deposit_transfered, checker_fee_transfered) = {
-        ctx.accounts.transfer_deposit(args.deal_amount + if let Some(checker_fee) = args.checker_fee { checker_fee } else { 0 })?;
+        ctx.accounts.transfer_deposit(accounting::deposit(args.deal_amount, args.checker_fee)?)?;
         (DepositTransfered, CheckerFeeTransfered)
     };
 
     let bonds_transfered = ctx.accounts.transfer_bonds(args.client_bond, args.executor_bond)?;
 
-    let advance_payment_transfered = ctx.accounts.transfer_advance_payment(args.advance_payment_amount)?;
+    let advance_payment_transfered = if args.advance_payment_amount > 0 {
+        ctx.accounts.transfer_advance_payment(args.advance_payment_amount)?
+    } else {
+        AdvancePaymentTransfered
+    };
+
+    let now = Clock::get()?.unix_timestamp;
+    emit!(DealCreated::new(&ctx.accounts.deal_state, service_fee, now));
+    if args.advance_payment_amount > 0 {
+        emit!(PartiallyPaid {
+            deal_id: args.id,
+            client: ctx.accounts.deal_state.client_key,
+            executor: ctx.accounts.deal_state.executor_key,
+            deal_mint: ctx.accounts.deal_state.deal_token_mint,
+            amount: args.advance_payment_amount,
+            mode: PaymentMode::Release,
+            paid_amount: args.advance_payment_amount,
+            timestamp: now,
+        });
+    }
     
-    Checklist {
+    let _ = Checklist {
         deadline_checked,
         amount_checked,
+        milestones_checked,
+        advance_payment_checked,
+        forfeiture_checked,
+        checker_panel_checked,
         checker_fee_transfered,
         deposit_transfered,
         deal_state_created,
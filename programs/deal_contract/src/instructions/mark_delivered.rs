@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::*,
+    errors::ErrorCodes,
+    events::DeliveryMarked,
+    state::{DealState, ProgramConfig},
+    utils::DealStateUpdated,
+};
+
+#[derive(Accounts)]
+pub struct MarkDelivered<'info> {
+    /// CHECK:
+    #[account(signer, address = deal_state.executor_key)]
+    pub executor: AccountInfo<'info>,
+
+    #[account(mut,
+        constraint = !deal_state.is_offer() @ ErrorCodes::OfferNotAccepted,
+        constraint = !deal_state.is_disputed() @ ErrorCodes::DealDisputed,
+        constraint = deal_state.is_active() @ ErrorCodes::DealNotActive,
+        constraint = deal_state.review_deadline_ts.is_none() @ ErrorCodes::AlreadyDelivered,
+    )]
+    pub deal_state: Box<Account<'info, DealState>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    deal_state_updated: DealStateUpdated,
+}
+
+#[access_control(ctx.accounts.config.check_not_paused(PAUSE_MARK_DELIVERED))]
+pub fn handle(ctx: Context<MarkDelivered>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let review_deadline_ts = now
+        .checked_add(ctx.accounts.config.review_window)
+        .ok_or(ErrorCodes::MathOverflow)?;
+
+    let deal_state_updated = {
+        ctx.accounts.deal_state.review_deadline_ts = Some(review_deadline_ts);
+        DealStateUpdated
+    };
+
+    emit!(DeliveryMarked {
+        deal_id: ctx.accounts.deal_state.id,
+        client: ctx.accounts.deal_state.client_key,
+        executor: ctx.accounts.deal_state.executor_key,
+        review_deadline_ts,
+        timestamp: now,
+    });
+
+    let _ = Checklist { deal_state_updated };
+
+    Ok(())
+}
@@ -0,0 +1,225 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::Token,
+    token_interface::{self, spl_token_2022::cmp_pubkeys, CloseAccount, TokenAccount},
+};
+
+use crate::{
+    constants::{CLIENT_BOND_SEED, CONFIG_SEED, EXECUTOR_BOND_SEED, PAUSE_MIGRATE_DEAL},
+    errors::{ErrorCodes, InvalidAccount},
+    events::DealMigrated,
+    state::{Bond, BondForfeiture, DealState, DealStatus, ProgramConfig, DEAL_STATE_VERSION},
+    utils::{
+        check_token_account, init_bond_vault, transfer_signed, AccountClosed, BondsTransfered,
+        DealStateUpdated, HolderModeHandled,
+    },
+};
+
+#[derive(Accounts)]
+pub struct MigrateDeal<'info> {
+    /// CHECK:
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: in access_control if client_bond.is_some()
+    pub client_bond_mint: AccountInfo<'info>,
+    /// CHECK: in access_control. the deal state token account the first version kept the client bond in
+    #[account(mut)]
+    pub legacy_client_bond_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. the client bond vault, initialized if client_bond.is_some()
+    #[account(mut)]
+    pub deal_state_client_bond_ta: AccountInfo<'info>,
+
+    /// CHECK: in access_control if executor_bond.is_some()
+    pub executor_bond_mint: AccountInfo<'info>,
+    /// CHECK: in access_control. the deal state token account the first version kept the executor bond in
+    #[account(mut)]
+    pub legacy_executor_bond_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. the executor bond vault, initialized if executor_bond.is_some()
+    #[account(mut)]
+    pub deal_state_executor_bond_ta: AccountInfo<'info>,
+
+    /// CHECK: by address
+    #[account(mut, address = config.service_fee_owner)]
+    pub service_fee: AccountInfo<'info>,
+    #[account(mut,
+        constraint = deal_state.version < DEAL_STATE_VERSION @ ErrorCodes::AlreadyMigrated,
+        realloc = DealState::SPACE,
+        realloc::payer = payer,
+        realloc::zero = false,
+    )]
+    pub deal_state: Box<Account<'info, DealState>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    /// The first version only held SPL Token bonds
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    bonds_transfered: BondsTransfered,
+    holder_mode_handled: HolderModeHandled,
+    deal_state_updated: DealStateUpdated,
+}
+
+impl<'info> MigrateDeal<'info> {
+    fn check_accounts(ctx: &Context<MigrateDeal>) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_MIGRATE_DEAL)?;
+        let deal_state = ctx.accounts.deal_state.to_account_info();
+
+        if let Some(Bond { mint, .. }) = ctx.accounts.deal_state.client_bond.as_ref() {
+            if !cmp_pubkeys(mint, ctx.accounts.client_bond_mint.key) {
+                return Err(InvalidAccount::ClientBondMint.into());
+            }
+            check_token_account(&ctx.accounts.legacy_client_bond_ta, mint, deal_state.key)
+                .map_err(|_| InvalidAccount::DealStateClientBondTokenAccount)?;
+            init_bond_vault(
+                &ctx.accounts.payer,
+                &ctx.accounts.client_bond_mint,
+                &deal_state,
+                &ctx.accounts.deal_state_client_bond_ta,
+                CLIENT_BOND_SEED,
+                &ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+            )
+            .map_err(|_| InvalidAccount::DealStateClientBondTokenAccount)?;
+        }
+
+        if let Some(Bond { mint, .. }) = ctx.accounts.deal_state.executor_bond.as_ref() {
+            if !cmp_pubkeys(mint, ctx.accounts.executor_bond_mint.key) {
+                return Err(InvalidAccount::ExecutorBondMint.into());
+            }
+            check_token_account(&ctx.accounts.legacy_executor_bond_ta, mint, deal_state.key)
+                .map_err(|_| InvalidAccount::DealStateExecutorBondTokenAccount)?;
+            init_bond_vault(
+                &ctx.accounts.payer,
+                &ctx.accounts.executor_bond_mint,
+                &deal_state,
+                &ctx.accounts.deal_state_executor_bond_ta,
+                EXECUTOR_BOND_SEED,
+                &ctx.accounts.token_program.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+            )
+            .map_err(|_| InvalidAccount::DealStateExecutorBondTokenAccount)?;
+        }
+
+        Ok(())
+    }
+
+    /// Legacy bonds are plain SPL tokens, wrapped SOL included, so they never go through
+    /// the native SOL and Token-2022 paths of `DealEscrow`
+    fn transfer_legacy_bond(
+        &self,
+        from: &AccountInfo<'info>,
+        to: &AccountInfo<'info>,
+        mint: &AccountInfo<'info>,
+        amount: u64,
+    ) -> Result<()> {
+        transfer_signed(
+            from,
+            to,
+            mint,
+            &self.deal_state.to_account_info(),
+            &self.token_program.to_account_info(),
+            &self.deal_state.seeds(),
+            amount,
+        )
+    }
+
+    /// Emptied legacy accounts are closed, unless the deal amount is kept in the same account
+    fn close_legacy_bond_ta(&self, legacy_bond_ta: &AccountInfo<'info>) -> Result<AccountClosed> {
+        let token_account = InterfaceAccount::<TokenAccount>::try_from(legacy_bond_ta)?;
+        if token_account.amount > 0
+            || cmp_pubkeys(&token_account.mint, &self.deal_state.deal_token_mint)
+        {
+            return Ok(AccountClosed);
+        }
+        token_interface::close_account(CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            CloseAccount {
+                account: legacy_bond_ta.clone(),
+                destination: self.service_fee.clone(),
+                authority: self.deal_state.to_account_info(),
+            },
+            &[&self.deal_state.seeds()[..]],
+        ))?;
+        Ok(AccountClosed)
+    }
+
+    /// Moves the bonds into their vaults, where the settlement instructions expect them
+    fn transfer_bonds(&self) -> Result<BondsTransfered> {
+        if let Some(Bond { amount, .. }) = self.deal_state.client_bond {
+            self.transfer_legacy_bond(
+                &self.legacy_client_bond_ta,
+                &self.deal_state_client_bond_ta,
+                &self.client_bond_mint,
+                amount,
+            )?;
+            self.close_legacy_bond_ta(&self.legacy_client_bond_ta)?;
+        }
+        if let Some(Bond { amount, .. }) = self.deal_state.executor_bond {
+            self.transfer_legacy_bond(
+                &self.legacy_executor_bond_ta,
+                &self.deal_state_executor_bond_ta,
+                &self.executor_bond_mint,
+                amount,
+            )?;
+            // both bonds may have shared the account, which is then closed already
+            if self.legacy_executor_bond_ta.lamports() > 0 {
+                self.close_legacy_bond_ta(&self.legacy_executor_bond_ta)?;
+            }
+        }
+        Ok(BondsTransfered)
+    }
+
+    /// Legacy holder mode locked holder tokens in the deal instead of a `HolderStake`,
+    /// `recover_holder_tokens` returns them once the deal is settled
+    fn handle_holder_mode(&mut self) -> HolderModeHandled {
+        self.deal_state.holder_mode = None;
+
+        HolderModeHandled
+    }
+
+    /// Deals of the first version were active from their creation
+    fn update_deal_state(&mut self) -> DealStateUpdated {
+        self.deal_state.version = DEAL_STATE_VERSION;
+        self.deal_state.status = DealStatus::Active;
+        self.deal_state.forfeiture = BondForfeiture::default();
+        self.deal_state.with_milestones = false;
+        self.deal_state.with_panel = false;
+        self.deal_state.acceptance_deadline_ts = None;
+        self.deal_state.executor_accepted = true;
+        self.deal_state.checker_accepted = true;
+        self.deal_state.amendments = 0;
+        self.deal_state.review_deadline_ts = None;
+        self.deal_state.held_service_fee = 0;
+
+        DealStateUpdated
+    }
+}
+
+/// Upgrades a deal created by the first version of the program to the current `DealState`.
+/// Anyone may call it, the payer funds the larger account
+#[access_control(MigrateDeal::check_accounts(&ctx))]
+pub fn handle(ctx: Context<MigrateDeal>) -> Result<()> {
+    let bonds_transfered = ctx.accounts.transfer_bonds()?;
+    let holder_mode_handled = ctx.accounts.handle_holder_mode();
+    let deal_state_updated = ctx.accounts.update_deal_state();
+
+    emit!(DealMigrated {
+        deal_id: ctx.accounts.deal_state.id,
+        client: ctx.accounts.deal_state.client_key,
+        executor: ctx.accounts.deal_state.executor_key,
+        version: DEAL_STATE_VERSION,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    let _ = Checklist {
+        bonds_transfered,
+        holder_mode_handled,
+        deal_state_updated,
+    };
+
+    Ok(())
+}
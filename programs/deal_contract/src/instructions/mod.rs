@@ -12,3 +12,66 @@ pub use initialize::*;
 
 pub mod partially_pay;
 pub use partially_pay::*;
+
+pub mod init_config;
+pub use init_config::*;
+
+pub mod update_config;
+pub use update_config::*;
+
+pub mod open_dispute;
+pub use open_dispute::*;
+
+pub mod submit_evidence;
+pub use submit_evidence::*;
+
+pub mod resolve_dispute;
+pub use resolve_dispute::*;
+
+pub mod approve_milestone;
+pub use approve_milestone::*;
+
+pub mod reject_milestone;
+pub use reject_milestone::*;
+
+pub mod create_offer;
+pub use create_offer::*;
+
+pub mod accept_offer;
+pub use accept_offer::*;
+
+pub mod accept_role;
+pub use accept_role::*;
+
+pub mod withdraw_offer;
+pub use withdraw_offer::*;
+
+pub mod amend_deal;
+pub use amend_deal::*;
+
+pub mod mark_delivered;
+pub use mark_delivered::*;
+
+pub mod slash_bond;
+pub use slash_bond::*;
+
+pub mod checker_vote;
+pub use checker_vote::*;
+
+pub mod set_fee_schedule;
+pub use set_fee_schedule::*;
+
+pub mod stake;
+pub use stake::*;
+
+pub mod unstake;
+pub use unstake::*;
+
+pub mod recover_holder_tokens;
+pub use recover_holder_tokens::*;
+
+pub mod pause;
+pub use pause::*;
+
+pub mod migrate_deal;
+pub use migrate_deal::*;
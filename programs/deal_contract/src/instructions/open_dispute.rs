@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::spl_token_2022::cmp_pubkeys;
+
+use crate::{
+    constants::*,
+    errors::{ErrorCodes, InvalidAccount},
+    events::DealDisputed,
+    state::{DealState, DealStatus, Dispute, Evidence, ProgramConfig},
+    utils::{DealStateUpdated, DisputeOpened},
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct OpenDisputeArgs {
+    pub reason_hash: [u8; 32],
+}
+
+#[derive(Accounts)]
+pub struct OpenDispute<'info> {
+    /// CHECK:
+    #[account(signer, constraint =
+        cmp_pubkeys(initializer.key, &deal_state.client_key)
+        || cmp_pubkeys(initializer.key, &deal_state.executor_key)
+        @ InvalidAccount::DisputeParty
+    )]
+    pub initializer: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+
+    #[account(mut,
+        constraint = !deal_state.is_offer() @ ErrorCodes::OfferNotAccepted,
+        constraint = !deal_state.is_disputed() @ ErrorCodes::DealDisputed,
+        constraint = deal_state.is_active() @ ErrorCodes::DealNotActive,
+        constraint = !deal_state.review_expired() @ ErrorCodes::ReviewWindowExpired,
+    )]
+    pub deal_state: Box<Account<'info, DealState>>,
+    #[account(init,
+        seeds = [DISPUTE_SEED, deal_state.key().as_ref()],
+        bump,
+        payer = payer,
+        space = Dispute::SPACE
+    )]
+    pub dispute: Box<Account<'info, Dispute>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    dispute_opened: DisputeOpened,
+    deal_state_updated: DealStateUpdated,
+}
+
+#[access_control(ctx.accounts.config.check_not_paused(PAUSE_OPEN_DISPUTE))]
+pub fn handle(ctx: Context<OpenDispute>, args: OpenDisputeArgs) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+
+    let dispute_opened = {
+        **ctx.accounts.dispute = Dispute {
+            deal_state: ctx.accounts.deal_state.key(),
+            initiator: ctx.accounts.initializer.key(),
+            opened_ts: now,
+            evidence: vec![Evidence {
+                submitter: ctx.accounts.initializer.key(),
+                hash: args.reason_hash,
+                submitted_ts: now,
+            }],
+            bump: [*ctx.bumps.get("dispute").unwrap()],
+        };
+        DisputeOpened
+    };
+
+    let deal_state_updated = {
+        ctx.accounts.deal_state.status = DealStatus::Disputed;
+        DealStateUpdated
+    };
+
+    emit!(DealDisputed {
+        deal_id: ctx.accounts.deal_state.id,
+        client: ctx.accounts.deal_state.client_key,
+        executor: ctx.accounts.deal_state.executor_key,
+        initiator: ctx.accounts.initializer.key(),
+        reason_hash: args.reason_hash,
+        amount: ctx.accounts.deal_state.remaining_escrow()?,
+        timestamp: now,
+    });
+
+    let _ = Checklist {
+        dispute_opened,
+        deal_state_updated,
+    };
+
+    Ok(())
+}
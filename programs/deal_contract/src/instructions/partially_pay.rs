@@ -1,16 +1,25 @@
 use anchor_lang::prelude::*;
 
-use anchor_spl::{token::{
-    self, Mint, TokenAccount, Transfer, Token,
-}, token_interface::spl_token_2022::cmp_pubkeys, associated_token::AssociatedToken};
+use anchor_spl::{token::Token, token_2022::Token2022, token_interface::{spl_token_2022::cmp_pubkeys, Mint}, associated_token::AssociatedToken};
 
 use crate::{
-    state::DealState, 
-    utils::{PaymentTransfered, DealStateUpdated}};
+    constants::{CONFIG_SEED, PAUSE_PARTIALLY_PAY},
+    errors::ErrorCodes,
+    events::PartiallyPaid, state::{DealState, ProgramConfig}, 
+    utils::{PaymentTransfered, DealStateUpdated, DealEscrow, check_or_init_ata, check_token_account, transfer_from_wallet}};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentMode {
+    /// Releases part of the escrowed deposit to the executor, counts towards `paid_amount`
+    Release,
+    /// Pays the executor straight from the client wallet on top of the deal amount, the escrow is not touched
+    TopUp,
+}
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct PartiallyPayArgs {
     pub amount: u64,
+    pub mode: PaymentMode,
 }
 
 #[derive(Accounts)]
@@ -31,23 +40,25 @@ pub struct PartiallyPay<'info> {
     pub payer: AccountInfo<'info>,
 
     #[account(address=deal_state.deal_token_mint)]
-    pub deal_mint: Box<Account<'info, Mint>>,
+    pub deal_mint: Box<InterfaceAccount<'info, Mint>>,
     
-    #[account(mut,
-        associated_token::mint = deal_mint,
-        associated_token::authority = client,
-    )]
-    pub client_deal_ta: Box<Account<'info, TokenAccount>>,
-    #[account(init_if_needed, payer = payer,
-        associated_token::mint = deal_mint,
-        associated_token::authority = executor,
-    )]
-    pub executor_deal_ta: Box<Account<'info, TokenAccount>>,
-
+    /// CHECK: in access_control. the client itself for native SOL deals
+    #[account(mut)]
+    pub client_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. the deal state itself for native SOL deals
     #[account(mut)]
+    pub deal_state_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub executor_deal_ta: AccountInfo<'info>,
+
+    #[account(mut, constraint = !deal_state.is_offer() @ ErrorCodes::OfferNotAccepted)]
     pub deal_state: Box<Account<'info, DealState>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
@@ -59,29 +70,97 @@ struct Checklist {
 }
 
 impl<'info> PartiallyPay<'info> {
-    fn update_deal_state(&mut self, amount: u64) -> DealStateUpdated {
-        self.deal_state.paid_amount += amount;
+    fn check_accounts(ctx: &Context<PartiallyPay>, args: &PartiallyPayArgs) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_PARTIALLY_PAY)?;
+        if args.mode == PaymentMode::Release {
+            ctx.accounts.check_release(args.amount)?;
+        }
+        check_token_account(&ctx.accounts.client_deal_ta, &ctx.accounts.deal_mint.key(), ctx.accounts.client.key)?;
+        check_token_account(&ctx.accounts.deal_state_deal_ta, &ctx.accounts.deal_mint.key(), ctx.accounts.deal_state.to_account_info().key)?;
+        check_or_init_ata(
+            &ctx.accounts.payer,
+            &ctx.accounts.deal_mint.to_account_info(),
+            &ctx.accounts.executor,
+            &ctx.accounts.executor_deal_ta,
+            &ctx.accounts.escrow().token_program_for(&ctx.accounts.deal_mint.to_account_info()),
+        )?;
 
-        DealStateUpdated
+        Ok(())
     }
-    
-    fn transfer_payment(&self, amount: u64) -> Result<PaymentTransfered> {
-        let cpi_accounts = Transfer {
-            from: self.client_deal_ta.to_account_info(),
-            to: self.executor_deal_ta.to_account_info(),
-            authority: self.client.to_account_info(),
-        };
-        token::transfer(CpiContext::new(self.token_program.to_account_info(), cpi_accounts), amount)?;
+
+    /// Releases go through the escrow, so they may not exceed what is left of it.
+    /// Deals with milestones release their escrow only through `approve_milestone`
+    fn check_release(&self, amount: u64) -> Result<()> {
+        if self.deal_state.is_disputed() {
+            return Err(ErrorCodes::DealDisputed.into());
+        }
+        if !self.deal_state.is_active() {
+            return Err(ErrorCodes::DealNotActive.into());
+        }
+        if self.deal_state.with_milestones {
+            return Err(ErrorCodes::DealWithMilestones.into());
+        }
+        if amount > self.deal_state.remaining_escrow()? {
+            return Err(ErrorCodes::ReleaseExceedsEscrow.into());
+        }
+        Ok(())
+    }
+
+    fn escrow(&self) -> DealEscrow<'_, 'info> {
+        DealEscrow::new(&self.deal_state, &self.token_program, &self.token_2022_program)
+    }
+
+    fn update_deal_state(&mut self, amount: u64, mode: PaymentMode) -> Result<DealStateUpdated> {
+        if mode == PaymentMode::Release {
+            self.deal_state.release(amount)?;
+        }
+
+        Ok(DealStateUpdated)
+    }
+
+    fn release_payment(&self, amount: u64) -> Result<PaymentTransfered> {
+        self.escrow().transfer(&self.deal_state_deal_ta, &self.executor_deal_ta, &*self.deal_mint, amount)?;
+
+        Ok(PaymentTransfered)
+    }
+
+    /// Transfer-fee mints are grossed up, so that the executor receives exactly `amount`
+    fn top_up_payment(&self, amount: u64) -> Result<PaymentTransfered> {
+        let deal_mint = self.deal_mint.to_account_info();
+        transfer_from_wallet(
+            &self.client_deal_ta,
+            &self.executor_deal_ta,
+            &deal_mint,
+            &self.client,
+            &self.escrow().token_program_for(&deal_mint),
+            &self.system_program.to_account_info(),
+            amount,
+        )?;
 
         Ok(PaymentTransfered)
     }
 }
 
+#[access_control(PartiallyPay::check_accounts(&ctx, &args))]
 pub fn handle(ctx: Context<PartiallyPay>, args: PartiallyPayArgs) -> Result<()> {
-    let deal_state_updated = ctx.accounts.update_deal_state(args.amount);
-    let payment_transfered = ctx.accounts.transfer_payment(args.amount)?;
+    let deal_state_updated = ctx.accounts.update_deal_state(args.amount, args.mode)?;
+    let payment_transfered = match args.mode {
+        PaymentMode::Release => ctx.accounts.release_payment(args.amount)?,
+        PaymentMode::TopUp => ctx.accounts.top_up_payment(args.amount)?,
+    };
+
+    emit!(PartiallyPaid {
+        deal_id: ctx.accounts.deal_state.id,
+        client: ctx.accounts.deal_state.client_key,
+        executor: ctx.accounts.deal_state.executor_key,
+        deal_mint: ctx.accounts.deal_state.deal_token_mint,
+        amount: args.amount,
+        mode: args.mode,
+        paid_amount: ctx.accounts.deal_state.paid_amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
     
-    Checklist {
+    let _ = Checklist {
         payment_transfered,
         deal_state_updated
     };
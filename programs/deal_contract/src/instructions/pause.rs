@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::CONFIG_SEED,
+    errors::InvalidAccount,
+    events::{InstructionsPaused, InstructionsUnpaused},
+    state::ProgramConfig,
+};
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    /// CHECK:
+    #[account(signer, address = config.authority @ InvalidAccount::Authority)]
+    pub authority: AccountInfo<'info>,
+
+    #[account(mut, seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+}
+
+/// Stops the instructions in `flags`. `PAUSE_INITIALIZE | PAUSE_PARTIALLY_PAY` keeps new
+/// deals and payments out while the parties can still `finish` or `cancel`. Any of
+/// `PAUSE_EXITS` blocks the parties from getting their funds out
+pub fn handle_pause(ctx: Context<SetPaused>, flags: u32) -> Result<()> {
+    ctx.accounts.config.set_paused(flags, true)?;
+
+    emit!(InstructionsPaused {
+        authority: ctx.accounts.authority.key(),
+        flags,
+        paused: ctx.accounts.config.paused,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn handle_unpause(ctx: Context<SetPaused>, flags: u32) -> Result<()> {
+    ctx.accounts.config.set_paused(flags, false)?;
+
+    emit!(InstructionsUnpaused {
+        authority: ctx.accounts.authority.key(),
+        flags,
+        paused: ctx.accounts.config.paused,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::Token,
+    token_2022::Token2022,
+    token_interface::{self, CloseAccount, Mint, TokenAccount},
+};
+
+use crate::{
+    constants::{CONFIG_SEED, DEAL_STATE_SEED, PAUSE_RECOVER_HOLDER_TOKENS},
+    errors::{ErrorCodes, InvalidAccount},
+    events::HolderTokensReturned,
+    state::ProgramConfig,
+    utils::{
+        check_or_init_ata, check_token_account, harvest_withheld_tokens, token_program_for,
+        transfer_signed, AccountClosed, PaymentTransfered,
+    },
+};
+
+#[derive(Accounts)]
+#[instruction(deal_id: [u8; 16])]
+pub struct RecoverHolderTokens<'info> {
+    /// CHECK: receives the holder tokens
+    pub client: AccountInfo<'info>,
+    /// CHECK: only a seed of the deal state
+    pub executor: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+
+    #[account(mut, address = config.holder_mint)]
+    pub holder_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub client_holder_ta: AccountInfo<'info>,
+    /// CHECK: in access_control
+    #[account(mut)]
+    pub deal_state_holder_ta: AccountInfo<'info>,
+
+    /// CHECK: the address of a closed deal state, it signs for its holder token account
+    #[account(
+        seeds = [deal_id.as_ref(), DEAL_STATE_SEED, client.key.as_ref(), executor.key.as_ref()],
+        bump,
+        constraint = deal_state.data_is_empty() @ ErrorCodes::DealNotClosed,
+    )]
+    pub deal_state: AccountInfo<'info>,
+
+    /// CHECK: by address
+    #[account(mut, address = config.service_fee_owner)]
+    pub service_fee: AccountInfo<'info>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    payment_transfered: PaymentTransfered,
+    deal_state_holder_ta_closed: AccountClosed,
+}
+
+impl<'info> RecoverHolderTokens<'info> {
+    fn check_accounts(ctx: &Context<RecoverHolderTokens>) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_RECOVER_HOLDER_TOKENS)?;
+        let holder_mint = ctx.accounts.holder_mint.to_account_info();
+        check_token_account(
+            &ctx.accounts.deal_state_holder_ta,
+            holder_mint.key,
+            ctx.accounts.deal_state.key,
+        )
+        .map_err(|_| InvalidAccount::DealStateHolderTokenAccount)?;
+        check_or_init_ata(
+            &ctx.accounts.payer,
+            &holder_mint,
+            &ctx.accounts.client,
+            &ctx.accounts.client_holder_ta,
+            &ctx.accounts.token_program_for(&holder_mint),
+        )
+        .map_err(|_| InvalidAccount::ClientHolderTokenAccount)?;
+
+        Ok(())
+    }
+
+    fn token_program_for(&self, mint: &AccountInfo<'info>) -> AccountInfo<'info> {
+        token_program_for(
+            mint,
+            &self.token_program.to_account_info(),
+            &self.token_2022_program.to_account_info(),
+        )
+    }
+
+    fn transfer_holder_tokens(
+        &self,
+        signer_seeds: &[&[u8]],
+        amount: u64,
+    ) -> Result<PaymentTransfered> {
+        let holder_mint = self.holder_mint.to_account_info();
+        transfer_signed(
+            &self.deal_state_holder_ta,
+            &self.client_holder_ta,
+            &holder_mint,
+            &self.deal_state,
+            &self.token_program_for(&holder_mint),
+            signer_seeds,
+            amount,
+        )?;
+        Ok(PaymentTransfered)
+    }
+
+    fn close_deal_state_holder_ta(&self, signer_seeds: &[&[u8]]) -> Result<AccountClosed> {
+        let holder_mint = self.holder_mint.to_account_info();
+        let token_program = self.token_program_for(&holder_mint);
+        harvest_withheld_tokens(&token_program, &holder_mint, &self.deal_state_holder_ta)?;
+        token_interface::close_account(CpiContext::new_with_signer(
+            token_program,
+            CloseAccount {
+                account: self.deal_state_holder_ta.clone(),
+                destination: self.service_fee.to_account_info(),
+                authority: self.deal_state.clone(),
+            },
+            &[signer_seeds],
+        ))?;
+        Ok(AccountClosed)
+    }
+}
+
+/// Returns the holder tokens left in the holder token account of a deal that was cancelled
+/// before `cancel` handled holder mode, and closes that account
+#[access_control(RecoverHolderTokens::check_accounts(&ctx))]
+pub fn handle(ctx: Context<RecoverHolderTokens>, deal_id: [u8; 16]) -> Result<()> {
+    let bump = [ctx.bumps["deal_state"]];
+    let signer_seeds: [&[u8]; 5] = [
+        &deal_id[..],
+        DEAL_STATE_SEED,
+        ctx.accounts.client.key.as_ref(),
+        ctx.accounts.executor.key.as_ref(),
+        &bump,
+    ];
+    let amount =
+        InterfaceAccount::<TokenAccount>::try_from(&ctx.accounts.deal_state_holder_ta)?.amount;
+
+    let payment_transfered = ctx.accounts.transfer_holder_tokens(&signer_seeds, amount)?;
+    let deal_state_holder_ta_closed = ctx.accounts.close_deal_state_holder_ta(&signer_seeds)?;
+
+    emit!(HolderTokensReturned {
+        deal_id,
+        client: ctx.accounts.client.key(),
+        holder_mint: ctx.accounts.holder_mint.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    let _ = Checklist {
+        payment_transfered,
+        deal_state_holder_ta_closed,
+    };
+
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::Token,
+    token_2022::Token2022,
+    token_interface::{spl_token_2022::cmp_pubkeys, Mint},
+};
+
+use crate::{
+    constants::*,
+    errors::{ErrorCodes, InvalidAccount},
+    events::MilestoneRejected,
+    state::{Checker, DealMilestones, DealState, MilestoneStatus, ProgramConfig},
+    utils::{
+        check_or_init_ata, check_token_account, DealEscrow, DealStateUpdated, DepositTransfered,
+        MilestoneSettled,
+    },
+};
+
+use super::SettleMilestoneArgs;
+
+#[derive(Accounts)]
+pub struct RejectMilestone<'info> {
+    /// CHECK: the client (only after the milestone deadline, or the deal deadline for a milestone without one) or the checker of the deal
+    #[account(signer, constraint =
+        cmp_pubkeys(initializer.key, &deal_state.client_key)
+        || matches!(deal_state.checker.as_ref(), Some(Checker { checker_key, .. }) if cmp_pubkeys(initializer.key, checker_key))
+        @ InvalidAccount::Initializer
+    )]
+    pub initializer: AccountInfo<'info>,
+    /// CHECK:
+    #[account(address = deal_state.client_key)]
+    pub client: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+
+    #[account(address = deal_state.deal_token_mint)]
+    pub deal_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: in access_control. the deal state itself for native SOL deals
+    #[account(mut)]
+    pub deal_state_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub client_deal_ta: AccountInfo<'info>,
+
+    #[account(mut,
+        constraint = !deal_state.is_offer() @ ErrorCodes::OfferNotAccepted,
+        constraint = !deal_state.is_disputed() @ ErrorCodes::DealDisputed,
+        constraint = deal_state.is_active() @ ErrorCodes::DealNotActive,
+    )]
+    pub deal_state: Box<Account<'info, DealState>>,
+    #[account(mut,
+        seeds = [MILESTONES_SEED, deal_state.key().as_ref()],
+        bump = deal_milestones.bump(),
+    )]
+    pub deal_milestones: Box<Account<'info, DealMilestones>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    milestone_settled: MilestoneSettled,
+    deposit_transfered: DepositTransfered,
+    deal_state_updated: DealStateUpdated,
+}
+
+impl<'info> RejectMilestone<'info> {
+    fn check_accounts(ctx: &Context<RejectMilestone>) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_REJECT_MILESTONE)?;
+        check_token_account(
+            &ctx.accounts.deal_state_deal_ta,
+            &ctx.accounts.deal_mint.key(),
+            ctx.accounts.deal_state.to_account_info().key,
+        )?;
+        check_or_init_ata(
+            &ctx.accounts.payer,
+            &ctx.accounts.deal_mint.to_account_info(),
+            &ctx.accounts.client,
+            &ctx.accounts.client_deal_ta,
+            &ctx.accounts.escrow().token_program_for(&ctx.accounts.deal_mint.to_account_info()),
+        )?;
+
+        Ok(())
+    }
+
+    fn escrow(&self) -> DealEscrow<'_, 'info> {
+        DealEscrow::new(&self.deal_state, &self.token_program, &self.token_2022_program)
+    }
+
+    fn settle_milestone(&mut self, index: u8) -> Result<(u64, MilestoneSettled)> {
+        let by_client = cmp_pubkeys(self.initializer.key, &self.deal_state.client_key);
+        let milestone = self.deal_milestones.pending_milestone(index)?;
+        // a milestone without a deadline of its own falls back to the deal deadline,
+        // and with neither only the checker may reject it
+        let deadline_expired = match milestone.deadline_ts {
+            Some(_) => milestone.deadline_expired(),
+            None => self.deal_state.deadline_expired(),
+        };
+        if by_client && !deadline_expired {
+            return Err(ErrorCodes::DeadlineNotExpired.into());
+        }
+        milestone.status = MilestoneStatus::Rejected;
+
+        Ok((milestone.amount, MilestoneSettled))
+    }
+
+    fn transfer_deposit(&self, amount: u64) -> Result<DepositTransfered> {
+        self.escrow().transfer(&self.deal_state_deal_ta, &self.client_deal_ta, &*self.deal_mint, amount)?;
+        Ok(DepositTransfered)
+    }
+
+    /// The rejected milestone is no longer part of the deal
+    fn update_deal_state(&mut self, amount: u64) -> Result<DealStateUpdated> {
+        self.deal_state.reduce_amount(amount)?;
+
+        Ok(DealStateUpdated)
+    }
+}
+
+#[access_control(RejectMilestone::check_accounts(&ctx))]
+pub fn handle(ctx: Context<RejectMilestone>, args: SettleMilestoneArgs) -> Result<()> {
+    let (amount, milestone_settled) = ctx.accounts.settle_milestone(args.index)?;
+    let deposit_transfered = ctx.accounts.transfer_deposit(amount)?;
+    let deal_state_updated = ctx.accounts.update_deal_state(amount)?;
+
+    emit!(MilestoneRejected {
+        deal_id: ctx.accounts.deal_state.id,
+        client: ctx.accounts.deal_state.client_key,
+        executor: ctx.accounts.deal_state.executor_key,
+        initializer: ctx.accounts.initializer.key(),
+        index: args.index,
+        amount,
+        deal_amount: ctx.accounts.deal_state.amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    let _ = Checklist {
+        milestone_settled,
+        deposit_transfered,
+        deal_state_updated,
+    };
+
+    Ok(())
+}
@@ -0,0 +1,489 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::Token,
+    token_2022::Token2022,
+    token_interface::{spl_token_2022::cmp_pubkeys, Mint},
+};
+
+use crate::{
+    constants::*,
+    errors::{ErrorCodes, InvalidAccount},
+    events::{BondForfeited, BondReturned, DealFinished},
+    state::{
+        accounting, Bond, Checker, CheckerPanel, DealMilestones, DealReceipt, DealState, DealStatus, Dispute,
+        HolderStake, ProgramConfig,
+    },
+    utils::{
+        check_bond_vault, check_or_init_ata, check_token_account, release_holder_stake, split_bps,
+        AccountClosed, BondsTransfered, CheckerFeeTransfered, DealEscrow,
+        DepositTransfered, HolderModeHandled, PaymentTransfered, ReceiptRecorded,
+    },
+};
+
+/// Every share is the part (in basis points) that goes to the client, the rest goes to the executor
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ResolveDisputeArgs {
+    pub client_share_bps: u16,
+    pub client_bond_client_share_bps: u16,
+    pub executor_bond_client_share_bps: u16,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    /// CHECK: the checker of the deal or the service account
+    #[account(mut, signer, constraint =
+        cmp_pubkeys(initializer.key, &config.service_account)
+        || matches!(deal_state.checker.as_ref(), Some(Checker { checker_key, .. }) if cmp_pubkeys(initializer.key, checker_key))
+        @ InvalidAccount::Initializer
+    )]
+    pub initializer: AccountInfo<'info>,
+    /// CHECK: check in access_control
+    pub checker: AccountInfo<'info>,
+    /// CHECK:
+    #[account(address = deal_state.client_key)]
+    pub client: AccountInfo<'info>,
+    /// CHECK:
+    #[account(address = deal_state.executor_key)]
+    pub executor: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: in access_control. the deal state itself for native SOL deals
+    #[account(mut)]
+    pub deal_state_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub client_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub executor_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub checker_deal_ta: AccountInfo<'info>,
+
+    /// CHECK: in access_control
+    #[account(mut)]
+    pub deal_state_client_bond_ta: AccountInfo<'info>,
+    /// CHECK: in access_control
+    #[account(mut)]
+    pub deal_state_executor_bond_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub client_bond_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub executor_client_bond_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub executor_bond_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub client_executor_bond_ta: AccountInfo<'info>,
+
+    #[account(mut, address = deal_state.deal_token_mint)]
+    pub deal_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: in access_control
+    #[account(mut)]
+    pub client_bond_mint: AccountInfo<'info>,
+    /// CHECK: in access_control
+    #[account(mut)]
+    pub executor_bond_mint: AccountInfo<'info>,
+
+    /// CHECK: by address
+    #[account(mut, address = config.service_fee_owner)]
+    pub service_fee: AccountInfo<'info>,
+    /// CHECK: by address
+    #[account(mut, address = dispute.initiator)]
+    pub dispute_initiator: AccountInfo<'info>,
+    #[account(mut,
+        constraint = deal_state.is_disputed() @ ErrorCodes::DealNotDisputed,
+        close = service_fee
+    )]
+    pub deal_state: Box<Account<'info, DealState>>,
+    #[account(mut, seeds = [HOLDER_STAKE_SEED, deal_state.client_key.as_ref()], bump = holder_stake.bump())]
+    pub holder_stake: Option<Box<Account<'info, HolderStake>>>,
+    #[account(mut,
+        seeds = [DISPUTE_SEED, deal_state.key().as_ref()],
+        bump = dispute.bump(),
+        close = dispute_initiator
+    )]
+    pub dispute: Box<Account<'info, Dispute>>,
+    #[account(mut,
+        seeds = [MILESTONES_SEED, deal_state.key().as_ref()],
+        bump = deal_milestones.bump(),
+        close = service_fee
+    )]
+    pub deal_milestones: Option<Box<Account<'info, DealMilestones>>>,
+    #[account(mut,
+        seeds = [CHECKER_PANEL_SEED, deal_state.key().as_ref()],
+        bump = checker_panel.bump(),
+        close = service_fee
+    )]
+    pub checker_panel: Option<Box<Account<'info, CheckerPanel>>>,
+    #[account(init,
+        seeds = [RECEIPT_SEED, deal_state.key().as_ref()],
+        bump,
+        payer = payer,
+        space = DealReceipt::SPACE
+    )]
+    pub deal_receipt: Option<Box<Account<'info, DealReceipt>>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    payment_transfered: PaymentTransfered,
+    deposit_transfered: DepositTransfered,
+    checker_fee_transfered: CheckerFeeTransfered,
+    bonds_transfered: BondsTransfered,
+    holder_mode_handled: HolderModeHandled,
+    deal_state_deal_ta_closed: AccountClosed,
+    receipt_recorded: ReceiptRecorded,
+}
+
+impl<'info> ResolveDispute<'info> {
+    fn check_accounts(ctx: &Context<ResolveDispute>) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_RESOLVE_DISPUTE)?;
+        if ctx.accounts.deal_state.with_milestones && ctx.accounts.deal_milestones.is_none() {
+            return Err(InvalidAccount::DealMilestones.into());
+        }
+        if ctx.accounts.deal_state.with_panel && ctx.accounts.checker_panel.is_none() {
+            return Err(InvalidAccount::CheckerPanel.into());
+        }
+
+        check_token_account(
+            &ctx.accounts.deal_state_deal_ta,
+            &ctx.accounts.deal_state.deal_token_mint,
+            ctx.accounts.deal_state.to_account_info().key,
+        )?;
+
+        let deal_token_program =
+            ctx.accounts.escrow().token_program_for(&ctx.accounts.deal_mint.to_account_info());
+        check_or_init_ata(
+            &ctx.accounts.payer,
+            &ctx.accounts.deal_mint.to_account_info(),
+            &ctx.accounts.client,
+            &ctx.accounts.client_deal_ta,
+            &deal_token_program,
+        )?;
+        check_or_init_ata(
+            &ctx.accounts.payer,
+            &ctx.accounts.deal_mint.to_account_info(),
+            &ctx.accounts.executor,
+            &ctx.accounts.executor_deal_ta,
+            &deal_token_program,
+        )?;
+
+        if let Some(Checker { checker_key, .. }) = ctx.accounts.deal_state.checker.as_ref() {
+            if !cmp_pubkeys(ctx.accounts.checker.key, checker_key) {
+                return Err(InvalidAccount::Checker.into());
+            };
+        }
+        // a panel does not rule disputes, its fee goes back to the client
+        if ctx.accounts.deal_state.checker.is_some() && !ctx.accounts.deal_state.with_panel {
+            check_or_init_ata(
+                &ctx.accounts.payer,
+                &ctx.accounts.deal_mint.to_account_info(),
+                &ctx.accounts.checker,
+                &ctx.accounts.checker_deal_ta,
+                &deal_token_program,
+            )
+            .map_err(|_| InvalidAccount::CheckerDealTokenAccount)?;
+        }
+
+        if let Some(Bond { mint, .. }) = ctx.accounts.deal_state.client_bond.as_ref() {
+            if !cmp_pubkeys(mint, ctx.accounts.client_bond_mint.key) {
+                return Err(InvalidAccount::ClientBondMint.into());
+            }
+            check_bond_vault(
+                &ctx.accounts.deal_state_client_bond_ta,
+                mint,
+                CLIENT_BOND_SEED,
+                ctx.accounts.deal_state.to_account_info().key,
+            )
+            .map_err(|_| InvalidAccount::DealStateClientBondTokenAccount)?;
+
+            check_or_init_ata(
+                &ctx.accounts.payer,
+                &ctx.accounts.client_bond_mint,
+                &ctx.accounts.client,
+                &ctx.accounts.client_bond_ta,
+                &ctx.accounts.escrow().token_program_for(&ctx.accounts.client_bond_mint),
+            )
+            .map_err(|_| InvalidAccount::ClientBondTokenAccount)?;
+            check_or_init_ata(
+                &ctx.accounts.payer,
+                &ctx.accounts.client_bond_mint,
+                &ctx.accounts.executor,
+                &ctx.accounts.executor_client_bond_ta,
+                &ctx.accounts.escrow().token_program_for(&ctx.accounts.client_bond_mint),
+            )
+            .map_err(|_| InvalidAccount::ExecutorClientBondTokenAccount)?;
+        }
+
+        if let Some(Bond { mint, .. }) = ctx.accounts.deal_state.executor_bond.as_ref() {
+            if !cmp_pubkeys(mint, ctx.accounts.executor_bond_mint.key) {
+                return Err(InvalidAccount::ExecutorBondMint.into());
+            }
+            check_bond_vault(
+                &ctx.accounts.deal_state_executor_bond_ta,
+                mint,
+                EXECUTOR_BOND_SEED,
+                ctx.accounts.deal_state.to_account_info().key,
+            )
+            .map_err(|_| InvalidAccount::DealStateExecutorBondTokenAccount)?;
+
+            check_or_init_ata(
+                &ctx.accounts.payer,
+                &ctx.accounts.executor_bond_mint,
+                &ctx.accounts.executor,
+                &ctx.accounts.executor_bond_ta,
+                &ctx.accounts.escrow().token_program_for(&ctx.accounts.executor_bond_mint),
+            )
+            .map_err(|_| InvalidAccount::ExecutorBondTokenAccount)?;
+            check_or_init_ata(
+                &ctx.accounts.payer,
+                &ctx.accounts.executor_bond_mint,
+                &ctx.accounts.client,
+                &ctx.accounts.client_executor_bond_ta,
+                &ctx.accounts.escrow().token_program_for(&ctx.accounts.executor_bond_mint),
+            )
+            .map_err(|_| InvalidAccount::ClientExecutorBondTokenAccount)?;
+        }
+
+        if ctx.accounts.deal_state.holder_mode.is_some() && ctx.accounts.holder_stake.is_none() {
+            return Err(InvalidAccount::HolderStake.into());
+        }
+
+        Ok(())
+    }
+
+    fn escrow(&self) -> DealEscrow<'_, 'info> {
+        DealEscrow::new(
+            &self.deal_state,
+            &self.token_program,
+            &self.token_2022_program,
+        )
+    }
+
+    /// The ruling settles the deal, so the receipt records it as finished
+    fn record_receipt(&mut self, bump: Option<&u8>, client_share_bps: u16) -> Result<ReceiptRecorded> {
+        self.deal_state.status = DealStatus::Finished;
+        let (checker_fee, returned_checker_fee) = self.checker_fee_shares();
+        if let (Some(deal_receipt), Some(bump)) = (self.deal_receipt.as_mut(), bump) {
+            let remaining = self.deal_state.remaining_escrow()?;
+            let (to_client, to_executor) = split_bps(remaining, client_share_bps)?;
+            ***deal_receipt = DealReceipt {
+                deal_state: self.deal_state.key(),
+                id: self.deal_state.id,
+                client_key: self.deal_state.client_key,
+                executor_key: self.deal_state.executor_key,
+                deal_token_mint: self.deal_state.deal_token_mint,
+                status: DealStatus::Finished,
+                amount: self.deal_state.amount,
+                executor_received: accounting::add(self.deal_state.paid_amount, to_executor)?,
+                client_refunded: accounting::add(to_client, returned_checker_fee)?,
+                checker_fee,
+                settled_by: self.initializer.key(),
+                settled_ts: Clock::get()?.unix_timestamp,
+                bump: [*bump],
+            };
+        }
+        Ok(ReceiptRecorded)
+    }
+
+    fn transfer_escrow(&self, client_share_bps: u16) -> Result<(PaymentTransfered, DepositTransfered)> {
+        let remaining = self.deal_state.remaining_escrow()?;
+        let (to_client, to_executor) = split_bps(remaining, client_share_bps)?;
+
+        self.escrow().transfer(
+            &self.deal_state_deal_ta,
+            &self.client_deal_ta,
+            &self.deal_mint.to_account_info(),
+            to_client,
+        )?;
+        self.escrow().transfer(
+            &self.deal_state_deal_ta,
+            &self.executor_deal_ta,
+            &self.deal_mint.to_account_info(),
+            to_executor,
+        )?;
+
+        Ok((PaymentTransfered, DepositTransfered))
+    }
+
+    /// The checker fee as (paid to the checker, returned to the client)
+    fn checker_fee_shares(&self) -> (u64, u64) {
+        match self.deal_state.checker.as_ref() {
+            Some(Checker { checker_fee, checker_key }) if cmp_pubkeys(self.initializer.key, checker_key) => {
+                (*checker_fee, 0)
+            }
+            Some(Checker { checker_fee, .. }) => (0, *checker_fee),
+            None => (0, 0),
+        }
+    }
+
+    /// The checker gets the fee when it rules the dispute itself, otherwise the fee is returned to the client
+    fn transfer_checker_fee(&self) -> Result<CheckerFeeTransfered> {
+        if let Some(Checker { checker_fee, checker_key }) = self.deal_state.checker.as_ref() {
+            let to = if cmp_pubkeys(self.initializer.key, checker_key) {
+                self.checker_deal_ta.to_account_info()
+            } else {
+                self.client_deal_ta.to_account_info()
+            };
+            self.escrow().transfer(
+                &self.deal_state_deal_ta,
+                &to,
+                &self.deal_mint.to_account_info(),
+                *checker_fee,
+            )?;
+        }
+        Ok(CheckerFeeTransfered)
+    }
+
+    fn transfer_bonds(
+        &self,
+        client_bond_client_share_bps: u16,
+        executor_bond_client_share_bps: u16,
+    ) -> Result<BondsTransfered> {
+        if let Some(Bond { amount, mint }) = self.deal_state.client_bond {
+            let (to_client, to_executor) = split_bps(amount, client_bond_client_share_bps)?;
+            self.escrow().transfer(
+                &self.deal_state_client_bond_ta,
+                &self.client_bond_ta,
+                &self.client_bond_mint,
+                to_client,
+            )?;
+            self.escrow().transfer(
+                &self.deal_state_client_bond_ta,
+                &self.executor_client_bond_ta,
+                &self.client_bond_mint,
+                to_executor,
+            )?;
+            self.emit_bond_split(
+                self.deal_state.client_key,
+                self.deal_state.executor_key,
+                mint,
+                to_client,
+                to_executor,
+            )?;
+        }
+        if let Some(Bond { amount, mint }) = self.deal_state.executor_bond {
+            let (to_client, to_executor) = split_bps(amount, executor_bond_client_share_bps)?;
+            self.escrow().transfer(
+                &self.deal_state_executor_bond_ta,
+                &self.client_executor_bond_ta,
+                &self.executor_bond_mint,
+                to_client,
+            )?;
+            self.escrow().transfer(
+                &self.deal_state_executor_bond_ta,
+                &self.executor_bond_ta,
+                &self.executor_bond_mint,
+                to_executor,
+            )?;
+            self.emit_bond_split(
+                self.deal_state.executor_key,
+                self.deal_state.client_key,
+                mint,
+                to_executor,
+                to_client,
+            )?;
+        }
+
+        if self.deal_state.client_bond.is_some() {
+            self.escrow().close(&self.deal_state_client_bond_ta, &self.client_bond_mint, &self.service_fee)?;
+        }
+        if self.deal_state.executor_bond.is_some() {
+            self.escrow().close(&self.deal_state_executor_bond_ta, &self.executor_bond_mint, &self.service_fee)?;
+        }
+
+        Ok(BondsTransfered)
+    }
+
+    /// The share of a bond ruled to the other party is reported as forfeited
+    fn emit_bond_split(
+        &self,
+        owner: Pubkey,
+        other: Pubkey,
+        mint: Pubkey,
+        returned: u64,
+        forfeited: u64,
+    ) -> Result<()> {
+        let timestamp = Clock::get()?.unix_timestamp;
+        emit!(BondReturned {
+            deal_id: self.deal_state.id,
+            owner,
+            mint,
+            amount: returned,
+            timestamp,
+        });
+        if forfeited > 0 {
+            emit!(BondForfeited {
+                deal_id: self.deal_state.id,
+                owner,
+                recipient: other,
+                mint,
+                amount: forfeited,
+                timestamp,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[access_control(ResolveDispute::check_accounts(&ctx))]
+pub fn handle(ctx: Context<ResolveDispute>, args: ResolveDisputeArgs) -> Result<()> {
+    let (payment_transfered, deposit_transfered) =
+        ctx.accounts.transfer_escrow(args.client_share_bps)?;
+    let checker_fee_transfered = ctx.accounts.transfer_checker_fee()?;
+    let bonds_transfered = ctx.accounts.transfer_bonds(
+        args.client_bond_client_share_bps,
+        args.executor_bond_client_share_bps,
+    )?;
+    let holder_mode_handled =
+        release_holder_stake(&ctx.accounts.deal_state, ctx.accounts.holder_stake.as_deref_mut())?;
+
+    let deal_state_deal_ta_closed = ctx.accounts.escrow().close(
+        &ctx.accounts.deal_state_deal_ta,
+        &ctx.accounts.deal_mint.to_account_info(),
+        &ctx.accounts.service_fee,
+    )?;
+
+    let receipt_recorded =
+        ctx.accounts.record_receipt(ctx.bumps.get("deal_receipt"), args.client_share_bps)?;
+
+    let (refund, payment) =
+        split_bps(ctx.accounts.deal_state.remaining_escrow()?, args.client_share_bps)?;
+    let (checker_fee, returned_checker_fee) = ctx.accounts.checker_fee_shares();
+    emit!(DealFinished {
+        deal_id: ctx.accounts.deal_state.id,
+        client: ctx.accounts.deal_state.client_key,
+        executor: ctx.accounts.deal_state.executor_key,
+        checker: ctx.accounts.deal_state.checker.as_ref().map(|Checker { checker_key, .. }| *checker_key),
+        deal_mint: ctx.accounts.deal_state.deal_token_mint,
+        payment,
+        refund: accounting::add(refund, returned_checker_fee)?,
+        checker_fee,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    let _ = Checklist {
+        payment_transfered,
+        deposit_transfered,
+        checker_fee_transfered,
+        bonds_transfered,
+        holder_mode_handled,
+        deal_state_deal_ta_closed,
+        receipt_recorded,
+    };
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    constants::{CONFIG_SEED, FEE_SCHEDULE_SEED},
+    errors::InvalidAccount,
+    state::{FeeSchedule, FeeScheduleArgs, ProgramConfig},
+};
+
+#[derive(Accounts)]
+pub struct SetFeeSchedule<'info> {
+    /// CHECK:
+    #[account(signer, address = config.authority @ InvalidAccount::Authority)]
+    pub authority: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(init_if_needed,
+        seeds = [FEE_SCHEDULE_SEED, mint.key().as_ref()],
+        bump,
+        payer = payer,
+        space = FeeSchedule::SPACE,
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates or updates the service fee schedule of a deal mint
+pub fn handle(ctx: Context<SetFeeSchedule>, args: FeeScheduleArgs) -> Result<()> {
+    ctx.accounts.fee_schedule.mint = ctx.accounts.mint.key();
+    ctx.accounts.fee_schedule.bump = [*ctx.bumps.get("fee_schedule").unwrap()];
+    ctx.accounts.fee_schedule.apply(args)
+}
@@ -0,0 +1,180 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::Token,
+    token_2022::Token2022,
+    token_interface::spl_token_2022::cmp_pubkeys,
+};
+
+use crate::{
+    constants::{CONFIG_SEED, CLIENT_BOND_SEED, EXECUTOR_BOND_SEED, PAUSE_SLASH_BOND},
+    errors::{ErrorCodes, InvalidAccount},
+    events::BondForfeited,
+    state::{Bond, DealState, ProgramConfig},
+    utils::{
+        check_bond_vault, check_or_init_ata, BondsTransfered, DealEscrow, DealStateUpdated,
+    },
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum BondSide {
+    /// The client bond goes to the executor
+    Client,
+    /// The executor bond goes to the client
+    Executor,
+}
+
+#[derive(Accounts)]
+pub struct SlashBond<'info> {
+    /// CHECK: in access_control
+    #[account(signer)]
+    pub checker: AccountInfo<'info>,
+    /// CHECK:
+    #[account(address = deal_state.client_key)]
+    pub client: AccountInfo<'info>,
+    /// CHECK:
+    #[account(address = deal_state.executor_key)]
+    pub executor: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: in access_control
+    pub bond_mint: AccountInfo<'info>,
+    /// CHECK: in access_control. the vault of the slashed bond, the deal state itself for native SOL bonds
+    #[account(mut)]
+    pub deal_state_bond_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. the token account of the other party, may be uninitialized.
+    #[account(mut)]
+    pub recipient_bond_ta: AccountInfo<'info>,
+
+    #[account(mut,
+        constraint = !deal_state.is_offer() @ ErrorCodes::OfferNotAccepted,
+        constraint = !deal_state.is_disputed() @ ErrorCodes::DealDisputed,
+        constraint = deal_state.is_active() @ ErrorCodes::DealNotActive,
+    )]
+    pub deal_state: Box<Account<'info, DealState>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    bonds_transfered: BondsTransfered,
+    deal_state_updated: DealStateUpdated,
+}
+
+impl<'info> SlashBond<'info> {
+    fn check_accounts(ctx: &Context<SlashBond>, side: BondSide) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_SLASH_BOND)?;
+        let checker = ctx.accounts.deal_state.with_checker()?;
+        if !cmp_pubkeys(ctx.accounts.checker.key, &checker.checker_key) {
+            return Err(InvalidAccount::Checker.into());
+        }
+        if !ctx.accounts.deal_state.forfeiture.checker_slash {
+            return Err(ErrorCodes::SlashNotAllowed.into());
+        }
+
+        let (Bond { mint, .. }, seed, recipient, errors) = match side {
+            BondSide::Client => (
+                ctx.accounts.deal_state.client_bond()?,
+                CLIENT_BOND_SEED,
+                &ctx.accounts.executor,
+                (
+                    InvalidAccount::ClientBondMint,
+                    InvalidAccount::DealStateClientBondTokenAccount,
+                    InvalidAccount::ExecutorClientBondTokenAccount,
+                ),
+            ),
+            BondSide::Executor => (
+                ctx.accounts.deal_state.executor_bond()?,
+                EXECUTOR_BOND_SEED,
+                &ctx.accounts.client,
+                (
+                    InvalidAccount::ExecutorBondMint,
+                    InvalidAccount::DealStateExecutorBondTokenAccount,
+                    InvalidAccount::ClientExecutorBondTokenAccount,
+                ),
+            ),
+        };
+        let (mint_error, deal_state_ta_error, recipient_ta_error) = errors;
+        if !cmp_pubkeys(mint, ctx.accounts.bond_mint.key) {
+            return Err(mint_error.into());
+        }
+        check_bond_vault(
+            &ctx.accounts.deal_state_bond_ta,
+            mint,
+            seed,
+            ctx.accounts.deal_state.to_account_info().key,
+        )
+        .map_err(|_| deal_state_ta_error)?;
+        check_or_init_ata(
+            &ctx.accounts.payer,
+            &ctx.accounts.bond_mint,
+            recipient,
+            &ctx.accounts.recipient_bond_ta,
+            &ctx.accounts.escrow().token_program_for(&ctx.accounts.bond_mint),
+        )
+        .map_err(|_| recipient_ta_error)?;
+
+        Ok(())
+    }
+
+    fn escrow(&self) -> DealEscrow<'_, 'info> {
+        DealEscrow::new(&self.deal_state, &self.token_program, &self.token_2022_program)
+    }
+
+    /// Zeroes the bond, so that the settlement returns nothing of it to its owner
+    fn forfeit(&mut self, side: BondSide) -> Result<(Bond, DealStateUpdated)> {
+        let bond = match side {
+            BondSide::Client => self.deal_state.client_bond_mut()?,
+            BondSide::Executor => self.deal_state.executor_bond_mut()?,
+        };
+        if bond.amount == 0 {
+            return Err(ErrorCodes::BondAlreadyForfeited.into());
+        }
+        let forfeited = bond.clone();
+        bond.amount = 0;
+        Ok((forfeited, DealStateUpdated))
+    }
+
+    fn transfer_bond(&self, amount: u64) -> Result<BondsTransfered> {
+        self.escrow().transfer(&self.deal_state_bond_ta, &self.recipient_bond_ta, &self.bond_mint, amount)?;
+        Ok(BondsTransfered)
+    }
+}
+
+/// The checker hands a bond to the other party, when the deal allows it
+#[access_control(SlashBond::check_accounts(&ctx, side))]
+pub fn handle(ctx: Context<SlashBond>, side: BondSide) -> Result<()> {
+    let (Bond { mint, amount }, deal_state_updated) = ctx.accounts.forfeit(side)?;
+    let bonds_transfered = ctx.accounts.transfer_bond(amount)?;
+
+    let (owner, recipient) = match side {
+        BondSide::Client => {
+            (ctx.accounts.deal_state.client_key, ctx.accounts.deal_state.executor_key)
+        }
+        BondSide::Executor => {
+            (ctx.accounts.deal_state.executor_key, ctx.accounts.deal_state.client_key)
+        }
+    };
+    emit!(BondForfeited {
+        deal_id: ctx.accounts.deal_state.id,
+        owner,
+        recipient,
+        mint,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    let _ = Checklist {
+        bonds_transfered,
+        deal_state_updated,
+    };
+
+    Ok(())
+}
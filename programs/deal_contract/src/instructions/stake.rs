@@ -0,0 +1,136 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::Token,
+    token_2022::Token2022,
+    token_interface::Mint,
+};
+
+use crate::{
+    constants::{CONFIG_SEED, HOLDER_STAKE_SEED, PAUSE_STAKE},
+    errors::{ErrorCodes, InvalidAccount},
+    events::HolderStaked,
+    state::{HolderStake, ProgramConfig},
+    utils::{
+        check_or_init_ata, check_token_account, token_program_for, transfer_from_wallet,
+        DealStateUpdated, DepositTransfered,
+    },
+};
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    /// CHECK:
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+
+    #[account(address = config.holder_mint)]
+    pub holder_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: in access_control
+    #[account(mut)]
+    pub owner_holder_ta: AccountInfo<'info>,
+    /// CHECK: in access_control, may be uninitialized
+    #[account(mut)]
+    pub holder_stake_ta: AccountInfo<'info>,
+
+    #[account(init_if_needed,
+        seeds = [HOLDER_STAKE_SEED, owner.key().as_ref()],
+        bump,
+        payer = payer,
+        space = HolderStake::SPACE,
+    )]
+    pub holder_stake: Box<Account<'info, HolderStake>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    deposit_transfered: DepositTransfered,
+    stake_updated: DealStateUpdated,
+}
+
+impl<'info> Stake<'info> {
+    fn check_accounts(ctx: &Context<Stake>, amount: u64) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_STAKE)?;
+        if amount == 0 {
+            return Err(ErrorCodes::AmountTooLow.into());
+        }
+        let holder_mint = ctx.accounts.holder_mint.to_account_info();
+        check_token_account(&ctx.accounts.owner_holder_ta, holder_mint.key, ctx.accounts.owner.key)
+            .map_err(|_| InvalidAccount::ClientHolderTokenAccount)?;
+        check_or_init_ata(
+            &ctx.accounts.payer,
+            &holder_mint,
+            &ctx.accounts.holder_stake.to_account_info(),
+            &ctx.accounts.holder_stake_ta,
+            &ctx.accounts.token_program_for(&holder_mint),
+        )
+        .map_err(|_| InvalidAccount::HolderStake)?;
+
+        Ok(())
+    }
+
+    fn token_program_for(&self, mint: &AccountInfo<'info>) -> AccountInfo<'info> {
+        token_program_for(
+            mint,
+            &self.token_program.to_account_info(),
+            &self.token_2022_program.to_account_info(),
+        )
+    }
+
+    /// The owner covers the transfer fee of the holder mint, so the stake receives `amount`
+    fn transfer_deposit(&self, amount: u64) -> Result<DepositTransfered> {
+        let holder_mint = self.holder_mint.to_account_info();
+        transfer_from_wallet(
+            &self.owner_holder_ta,
+            &self.holder_stake_ta,
+            &holder_mint,
+            &self.owner,
+            &self.token_program_for(&holder_mint),
+            &self.system_program.to_account_info(),
+            amount,
+        )?;
+        Ok(DepositTransfered)
+    }
+}
+
+/// Stakes holder tokens of the owner, creating the stake on the first deposit.
+/// Every deposit restarts the cooldown of the whole stake.
+#[access_control(Stake::check_accounts(&ctx, amount))]
+pub fn handle(ctx: Context<Stake>, amount: u64) -> Result<()> {
+    let deposit_transfered = ctx.accounts.transfer_deposit(amount)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let stake_updated = {
+        let cooldown = ctx.accounts.config.holder_stake_cooldown;
+        let holder_stake = &mut ctx.accounts.holder_stake;
+        holder_stake.owner = ctx.accounts.owner.key();
+        holder_stake.bump = [*ctx.bumps.get("holder_stake").unwrap()];
+        holder_stake.amount =
+            holder_stake.amount.checked_add(amount).ok_or(ErrorCodes::MathOverflow)?;
+        holder_stake.unlock_ts = now.checked_add(cooldown).ok_or(ErrorCodes::MathOverflow)?;
+        DealStateUpdated
+    };
+
+    emit!(HolderStaked {
+        owner: ctx.accounts.owner.key(),
+        amount,
+        staked: ctx.accounts.holder_stake.amount,
+        unlock_ts: ctx.accounts.holder_stake.unlock_ts,
+        timestamp: now,
+    });
+
+    let _ = Checklist {
+        deposit_transfered,
+        stake_updated,
+    };
+
+    Ok(())
+}
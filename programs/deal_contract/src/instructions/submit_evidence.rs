@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::spl_token_2022::cmp_pubkeys;
+
+use crate::{
+    constants::*,
+    errors::{ErrorCodes, InvalidAccount},
+    state::{DealState, Dispute, Evidence, MAX_EVIDENCE, ProgramConfig},
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SubmitEvidenceArgs {
+    pub hash: [u8; 32],
+}
+
+#[derive(Accounts)]
+pub struct SubmitEvidence<'info> {
+    /// CHECK:
+    #[account(signer, constraint =
+        cmp_pubkeys(initializer.key, &deal_state.client_key)
+        || cmp_pubkeys(initializer.key, &deal_state.executor_key)
+        @ InvalidAccount::DisputeParty
+    )]
+    pub initializer: AccountInfo<'info>,
+
+    #[account(constraint = deal_state.is_disputed() @ ErrorCodes::DealNotDisputed)]
+    pub deal_state: Box<Account<'info, DealState>>,
+    #[account(mut,
+        seeds = [DISPUTE_SEED, deal_state.key().as_ref()],
+        bump = dispute.bump(),
+    )]
+    pub dispute: Box<Account<'info, Dispute>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+}
+
+#[access_control(ctx.accounts.config.check_not_paused(PAUSE_SUBMIT_EVIDENCE))]
+pub fn handle(ctx: Context<SubmitEvidence>, args: SubmitEvidenceArgs) -> Result<()> {
+    if ctx.accounts.dispute.evidence.len() >= MAX_EVIDENCE {
+        return Err(ErrorCodes::EvidenceLimitReached.into());
+    }
+
+    ctx.accounts.dispute.evidence.push(Evidence {
+        submitter: ctx.accounts.initializer.key(),
+        hash: args.hash,
+        submitted_ts: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
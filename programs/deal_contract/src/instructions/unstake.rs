@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::Token,
+    token_2022::Token2022,
+    token_interface::Mint,
+};
+
+use crate::{
+    constants::{CONFIG_SEED, HOLDER_STAKE_SEED, PAUSE_UNSTAKE},
+    errors::{ErrorCodes, InvalidAccount},
+    events::HolderUnstaked,
+    state::{accounting, HolderStake, ProgramConfig},
+    utils::{
+        check_or_init_ata, check_token_account, token_program_for, transfer_signed,
+        DealStateUpdated, PaymentTransfered,
+    },
+};
+
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    /// CHECK:
+    #[account(signer, address = holder_stake.owner @ InvalidAccount::Initializer)]
+    pub owner: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+
+    #[account(address = config.holder_mint)]
+    pub holder_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: in access_control, may be uninitialized
+    #[account(mut)]
+    pub owner_holder_ta: AccountInfo<'info>,
+    /// CHECK: in access_control
+    #[account(mut)]
+    pub holder_stake_ta: AccountInfo<'info>,
+
+    #[account(mut,
+        seeds = [HOLDER_STAKE_SEED, owner.key().as_ref()],
+        bump = holder_stake.bump(),
+    )]
+    pub holder_stake: Box<Account<'info, HolderStake>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    payment_transfered: PaymentTransfered,
+    stake_updated: DealStateUpdated,
+}
+
+impl<'info> Unstake<'info> {
+    fn check_accounts(ctx: &Context<Unstake>, amount: u64) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_UNSTAKE)?;
+        let holder_stake = &ctx.accounts.holder_stake;
+        if amount == 0 {
+            return Err(ErrorCodes::AmountTooLow.into());
+        }
+        if amount > holder_stake.amount {
+            return Err(ErrorCodes::InsufficientStake.into());
+        }
+        if holder_stake.active_deals > 0 {
+            return Err(ErrorCodes::StakeInUse.into());
+        }
+        if Clock::get()?.unix_timestamp < holder_stake.unlock_ts {
+            return Err(ErrorCodes::StakeInCooldown.into());
+        }
+
+        let holder_mint = ctx.accounts.holder_mint.to_account_info();
+        check_token_account(&ctx.accounts.holder_stake_ta, holder_mint.key, &holder_stake.key())
+            .map_err(|_| InvalidAccount::HolderStake)?;
+        check_or_init_ata(
+            &ctx.accounts.payer,
+            &holder_mint,
+            &ctx.accounts.owner,
+            &ctx.accounts.owner_holder_ta,
+            &ctx.accounts.token_program_for(&holder_mint),
+        )
+        .map_err(|_| InvalidAccount::ClientHolderTokenAccount)?;
+
+        Ok(())
+    }
+
+    fn token_program_for(&self, mint: &AccountInfo<'info>) -> AccountInfo<'info> {
+        token_program_for(
+            mint,
+            &self.token_program.to_account_info(),
+            &self.token_2022_program.to_account_info(),
+        )
+    }
+
+    fn transfer_withdrawal(&self, amount: u64) -> Result<PaymentTransfered> {
+        let holder_mint = self.holder_mint.to_account_info();
+        transfer_signed(
+            &self.holder_stake_ta,
+            &self.owner_holder_ta,
+            &holder_mint,
+            &self.holder_stake.to_account_info(),
+            &self.token_program_for(&holder_mint),
+            &self.holder_stake.seeds(),
+            amount,
+        )?;
+        Ok(PaymentTransfered)
+    }
+}
+
+/// Returns staked holder tokens to the owner, once no holder mode deal references the stake
+/// and its cooldown expired
+#[access_control(Unstake::check_accounts(&ctx, amount))]
+pub fn handle(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+    let payment_transfered = ctx.accounts.transfer_withdrawal(amount)?;
+
+    let stake_updated = {
+        let holder_stake = &mut ctx.accounts.holder_stake;
+        holder_stake.amount = accounting::sub(holder_stake.amount, amount)?;
+        DealStateUpdated
+    };
+
+    emit!(HolderUnstaked {
+        owner: ctx.accounts.owner.key(),
+        amount,
+        staked: ctx.accounts.holder_stake.amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    let _ = Checklist {
+        payment_transfered,
+        stake_updated,
+    };
+
+    Ok(())
+}
@@ -1,63 +1,219 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{token::Token, token_interface::spl_token_2022::cmp_pubkeys};
+use anchor_spl::{
+    token::Token,
+    token_2022::Token2022,
+    token_interface::{spl_token_2022::cmp_pubkeys, Mint},
+};
 
 use crate::{
     constants::*,
-    errors::ErrorCodes,
-    state::{Checker, DealState},
+    errors::{ErrorCodes, InvalidAccount},
+    events::CheckerUpdated,
+    state::{Checker, DealState, ProgramConfig},
+    utils::{
+        check_token_account, transfer_from_wallet, CheckerFeeTransfered, DealEscrow,
+        DealStateUpdated, SignaturesChecked,
+    },
 };
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CheckerUpdate {
+    /// The current checker steps down, its fee is refunded to the client
+    Resign,
+    /// `new_checker` takes over from the current checker. A higher fee is deposited by the client,
+    /// a lower one is refunded to the client
+    Replace { checker_fee: u64 },
+    /// `new_checker` checks a deal without a checker, the client deposits its fee
+    Add { checker_fee: u64 },
+}
+
 #[derive(Accounts)]
 pub struct UpdateChecker<'info> {
-    /// CHECK:
-    #[account(mut, signer)]
+    /// CHECK: the current checker on resignation, otherwise the client or the service account
+    #[account(signer)]
     pub initializer: AccountInfo<'info>,
-    /// CHECK:
+    /// CHECK: must sign with the executor unless the initializer is the service account
     #[account(mut, address = deal_state.client_key)]
     pub client: AccountInfo<'info>,
     /// CHECK:
     #[account(mut, address = deal_state.executor_key)]
     pub executor: AccountInfo<'info>,
-    /// CHECK:
-    #[account(signer)]
+    /// CHECK: in access_control. the initializer for a deal without a checker
     pub current_checker: AccountInfo<'info>,
-    /// CHECK:
-    #[account(signer)]
+    /// CHECK: signs to accept the role, unused on resignation
     pub new_checker: AccountInfo<'info>,
 
+    #[account(address = deal_state.deal_token_mint)]
+    pub deal_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: in access_control. the client itself for native SOL deals
     #[account(mut)]
+    pub client_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. the deal state itself for native SOL deals
+    #[account(mut)]
+    pub deal_state_deal_ta: AccountInfo<'info>,
+
+    #[account(mut,
+        constraint = !deal_state.is_offer() @ ErrorCodes::OfferNotAccepted,
+        constraint = !deal_state.is_disputed() @ ErrorCodes::DealDisputed,
+        constraint = deal_state.is_active() @ ErrorCodes::DealNotActive,
+    )]
     pub deal_state: Box<Account<'info, DealState>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    signatures_checked: SignaturesChecked,
+    deal_state_updated: DealStateUpdated,
+    checker_fee_transfered: CheckerFeeTransfered,
 }
 
 impl<'info> UpdateChecker<'info> {
-    fn check_accounts(ctx: &Context<UpdateChecker>) -> Result<()> {
-        if ctx.accounts.deal_state.checker.is_some() {}
+    fn check_accounts(ctx: &Context<UpdateChecker>, update: CheckerUpdate) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_UPDATE_CHECKER)?;
+        let deal_state = &ctx.accounts.deal_state;
+        if deal_state.with_panel {
+            return Err(ErrorCodes::DealWithCheckerPanel.into());
+        }
+
+        match (update, deal_state.checker.as_ref()) {
+            (CheckerUpdate::Add { .. }, Some(_)) => return Err(ErrorCodes::DealStateWithChecker.into()),
+            (CheckerUpdate::Add { .. }, None) => {}
+            (_, None) => return Err(ErrorCodes::DealStateNotWithChecker.into()),
+            (_, Some(Checker { checker_key, .. })) => {
+                if !cmp_pubkeys(ctx.accounts.current_checker.key, checker_key) {
+                    return Err(InvalidAccount::Checker.into());
+                }
+            }
+        }
+
+        if update != CheckerUpdate::Resign {
+            let new_checker = ctx.accounts.new_checker.key;
+            if cmp_pubkeys(new_checker, &deal_state.client_key)
+                || cmp_pubkeys(new_checker, &deal_state.executor_key)
+                || deal_state.checker.as_ref().is_some_and(|Checker { checker_key, .. }| cmp_pubkeys(new_checker, checker_key))
+            {
+                return Err(InvalidAccount::Checker.into());
+            }
+        }
+
+        check_token_account(
+            &ctx.accounts.client_deal_ta,
+            &ctx.accounts.deal_mint.key(),
+            ctx.accounts.client.key,
+        )?;
+        check_token_account(
+            &ctx.accounts.deal_state_deal_ta,
+            &ctx.accounts.deal_mint.key(),
+            ctx.accounts.deal_state.to_account_info().key,
+        )?;
+
         Ok(())
     }
+
+    fn escrow(&self) -> DealEscrow<'_, 'info> {
+        DealEscrow::new(&self.deal_state, &self.token_program, &self.token_2022_program)
+    }
+
+    /// Only the checker itself resigns. A new checker is approved by both parties, or by the service
+    /// account alone, and accepts the role by signing
+    fn check_signatures(&self, update: CheckerUpdate) -> Result<SignaturesChecked> {
+        if update == CheckerUpdate::Resign {
+            if !cmp_pubkeys(self.initializer.key, self.current_checker.key) {
+                return Err(InvalidAccount::Initializer.into());
+            }
+            return Ok(SignaturesChecked);
+        }
+
+        if !cmp_pubkeys(self.initializer.key, &self.config.service_account) {
+            require!(self.client.is_signer, ErrorCode::AccountNotSigner);
+            require!(self.executor.is_signer, ErrorCode::AccountNotSigner);
+        }
+        require!(self.new_checker.is_signer, ErrorCode::AccountNotSigner);
+
+        Ok(SignaturesChecked)
+    }
+
+    /// Transfer-fee mints are grossed up, so that the escrow holds exactly the checker fee
+    fn transfer_deposit(&self, deposit: u64) -> Result<()> {
+        require!(self.client.is_signer, ErrorCode::AccountNotSigner);
+
+        let deal_mint = self.deal_mint.to_account_info();
+        transfer_from_wallet(
+            &self.client_deal_ta,
+            &self.deal_state_deal_ta,
+            &deal_mint,
+            &self.client,
+            &self.escrow().token_program_for(&deal_mint),
+            &self.system_program.to_account_info(),
+            deposit,
+        )
+    }
+
+    fn transfer_refund(&self, refund: u64) -> Result<()> {
+        self.escrow().transfer(&self.deal_state_deal_ta, &self.client_deal_ta, &*self.deal_mint, refund)
+    }
+
+    fn update_deal_state(&mut self, update: CheckerUpdate) -> Result<(u64, u64, DealStateUpdated)> {
+        let checker = match update {
+            CheckerUpdate::Resign => None,
+            CheckerUpdate::Replace { checker_fee } | CheckerUpdate::Add { checker_fee } => Some(Checker {
+                checker_fee,
+                checker_key: self.new_checker.key(),
+            }),
+        };
+        let (deposit, refund) = self.deal_state.set_checker(checker)?;
+
+        Ok((deposit, refund, DealStateUpdated))
+    }
 }
 
-#[access_control(UpdateChecker::check_accounts(&ctx))]
-pub fn handle(ctx: Context<UpdateChecker>, new_checker_fee: u64) -> Result<()> {
-    if !cmp_pubkeys(ctx.accounts.initializer.key, &SERVICE_ACCOUNT_ADDRESS) {
-        require!(ctx.accounts.client.is_signer, ErrorCode::AccountNotSigner);
-        require!(ctx.accounts.executor.is_signer, ErrorCode::AccountNotSigner);
+#[access_control(UpdateChecker::check_accounts(&ctx, update))]
+pub fn handle(ctx: Context<UpdateChecker>, update: CheckerUpdate) -> Result<()> {
+    let signatures_checked = ctx.accounts.check_signatures(update)?;
 
-        if let Some(Checker { .. }) = ctx.accounts.deal_state.checker {
-            return Err(ErrorCodes::DealStateWithChecker.into());
+    let old_checker = ctx
+        .accounts
+        .deal_state
+        .checker
+        .as_ref()
+        .map(|Checker { checker_key, .. }| *checker_key);
+    let (deposit, refund, deal_state_updated) = ctx.accounts.update_deal_state(update)?;
 
-            // require_keys_eq!(
-            //     checker_key,
-            //     ctx.accounts.current_checker.key(),
-            //     InvalidAccount::Checker
-            // );
+    let checker_fee_transfered = {
+        if deposit > 0 {
+            ctx.accounts.transfer_deposit(deposit)?;
+        }
+        if refund > 0 {
+            ctx.accounts.transfer_refund(refund)?;
         }
+        CheckerFeeTransfered
     };
 
-    ctx.accounts.deal_state.checker = Some(Checker {
-        checker_fee: new_checker_fee,
-        checker_key: ctx.accounts.new_checker.key(),
+    emit!(CheckerUpdated {
+        deal_id: ctx.accounts.deal_state.id,
+        client: ctx.accounts.deal_state.client_key,
+        executor: ctx.accounts.deal_state.executor_key,
+        old_checker,
+        new_checker: ctx
+            .accounts
+            .deal_state
+            .checker
+            .as_ref()
+            .map(|Checker { checker_key, .. }| *checker_key),
+        checker_fee: ctx.accounts.deal_state.checker_fee(),
+        timestamp: Clock::get()?.unix_timestamp,
     });
 
+    let _ = Checklist {
+        signatures_checked,
+        deal_state_updated,
+        checker_fee_transfered,
+    };
+
     Ok(())
 }
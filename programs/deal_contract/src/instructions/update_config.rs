@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::CONFIG_SEED,
+    errors::InvalidAccount,
+    state::{ConfigArgs, ProgramConfig},
+};
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    /// CHECK:
+    #[account(signer, address = config.authority @ InvalidAccount::Authority)]
+    pub authority: AccountInfo<'info>,
+
+    #[account(mut, seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+}
+
+pub fn handle(ctx: Context<UpdateConfig>, args: ConfigArgs) -> Result<()> {
+    ctx.accounts.config.apply(args)
+}
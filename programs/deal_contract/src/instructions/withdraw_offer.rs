@@ -0,0 +1,260 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::Token,
+    token_2022::Token2022,
+    token_interface::{spl_token_2022::cmp_pubkeys, Mint},
+};
+
+use crate::{
+    constants::*,
+    errors::{ErrorCodes, InvalidAccount},
+    events::{BondReturned, DealCancelled},
+    state::{accounting, Bond, DealState, HolderStake, ProgramConfig},
+    utils::{
+        check_bond_vault, check_or_init_ata, check_token_account, release_holder_stake,
+        AccountClosed, BondsTransfered, DealEscrow, DeadlineChecked, DepositTransfered,
+        HolderModeHandled,
+    },
+};
+
+#[derive(Accounts)]
+pub struct WithdrawOffer<'info> {
+    /// CHECK:
+    #[account(mut, signer, address = deal_state.client_key)]
+    pub client: AccountInfo<'info>,
+    /// CHECK:
+    #[account(address = deal_state.executor_key)]
+    pub executor: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut, signer)]
+    pub payer: AccountInfo<'info>,
+
+    /// CHECK: in access_control. the deal state itself for native SOL deals
+    #[account(mut)]
+    pub deal_state_deal_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub client_deal_ta: AccountInfo<'info>,
+
+    /// CHECK: in access_control
+    #[account(mut)]
+    pub deal_state_client_bond_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub client_bond_ta: AccountInfo<'info>,
+    /// CHECK: in access_control
+    #[account(mut)]
+    pub deal_state_executor_bond_ta: AccountInfo<'info>,
+    /// CHECK: in access_control. may be uninitialized.
+    #[account(mut)]
+    pub executor_bond_ta: AccountInfo<'info>,
+
+    #[account(mut, address = deal_state.deal_token_mint)]
+    pub deal_mint: Box<InterfaceAccount<'info, Mint>>,
+    /// CHECK: in access_control
+    #[account(mut)]
+    pub client_bond_mint: AccountInfo<'info>,
+    /// CHECK: in access_control
+    #[account(mut)]
+    pub executor_bond_mint: AccountInfo<'info>,
+
+    /// CHECK: by address
+    #[account(mut, address = config.service_fee_owner)]
+    pub service_fee: AccountInfo<'info>,
+    #[account(mut,
+        constraint = deal_state.is_offer() @ ErrorCodes::OfferAlreadyAccepted,
+        close = client
+    )]
+    pub deal_state: Box<Account<'info, DealState>>,
+    #[account(mut, seeds = [HOLDER_STAKE_SEED, deal_state.client_key.as_ref()], bump = holder_stake.bump())]
+    pub holder_stake: Option<Box<Account<'info, HolderStake>>>,
+    #[account(seeds = [CONFIG_SEED], bump = config.bump())]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(dead_code)]
+struct Checklist {
+    deadline_checked: DeadlineChecked,
+    deposit_transfered: DepositTransfered,
+    bonds_transfered: BondsTransfered,
+    holder_mode_handled: HolderModeHandled,
+    deal_state_deal_ta_closed: AccountClosed,
+}
+
+impl<'info> WithdrawOffer<'info> {
+    fn check_accounts(ctx: &Context<WithdrawOffer>) -> Result<()> {
+        ctx.accounts.config.check_not_paused(PAUSE_WITHDRAW_OFFER)?;
+        let deal_state_key = ctx.accounts.deal_state.key();
+
+        check_token_account(
+            &ctx.accounts.deal_state_deal_ta,
+            &ctx.accounts.deal_state.deal_token_mint,
+            &deal_state_key,
+        )?;
+        check_or_init_ata(
+            &ctx.accounts.payer,
+            &ctx.accounts.deal_mint.to_account_info(),
+            &ctx.accounts.client,
+            &ctx.accounts.client_deal_ta,
+            &ctx.accounts.escrow().token_program_for(&ctx.accounts.deal_mint.to_account_info()),
+        )?;
+
+        if let Some(Bond { mint, .. }) = ctx.accounts.deal_state.client_bond.as_ref() {
+            if !cmp_pubkeys(mint, ctx.accounts.client_bond_mint.key) {
+                return Err(InvalidAccount::ClientBondMint.into());
+            }
+            check_bond_vault(&ctx.accounts.deal_state_client_bond_ta, mint, CLIENT_BOND_SEED, &deal_state_key)
+                .map_err(|_| InvalidAccount::DealStateClientBondTokenAccount)?;
+            check_or_init_ata(
+                &ctx.accounts.payer,
+                &ctx.accounts.client_bond_mint,
+                &ctx.accounts.client,
+                &ctx.accounts.client_bond_ta,
+                &ctx.accounts.escrow().token_program_for(&ctx.accounts.client_bond_mint),
+            )
+            .map_err(|_| InvalidAccount::ClientBondTokenAccount)?;
+        }
+
+        if ctx.accounts.deal_state.executor_accepted {
+            if let Some(Bond { mint, .. }) = ctx.accounts.deal_state.executor_bond.as_ref() {
+                if !cmp_pubkeys(mint, ctx.accounts.executor_bond_mint.key) {
+                    return Err(InvalidAccount::ExecutorBondMint.into());
+                }
+                check_bond_vault(&ctx.accounts.deal_state_executor_bond_ta, mint, EXECUTOR_BOND_SEED, &deal_state_key)
+                    .map_err(|_| InvalidAccount::DealStateExecutorBondTokenAccount)?;
+                check_or_init_ata(
+                    &ctx.accounts.payer,
+                    &ctx.accounts.executor_bond_mint,
+                    &ctx.accounts.executor,
+                    &ctx.accounts.executor_bond_ta,
+                    &ctx.accounts.escrow().token_program_for(&ctx.accounts.executor_bond_mint),
+                )
+                .map_err(|_| InvalidAccount::ExecutorBondTokenAccount)?;
+            }
+        }
+
+        if ctx.accounts.deal_state.holder_mode.is_some() && ctx.accounts.holder_stake.is_none() {
+            return Err(InvalidAccount::HolderStake.into());
+        }
+
+        Ok(())
+    }
+
+    fn escrow(&self) -> DealEscrow<'_, 'info> {
+        DealEscrow::new(
+            &self.deal_state,
+            &self.token_program,
+            &self.token_2022_program,
+        )
+    }
+
+    /// The offer can be withdrawn only if it hasn't been accepted by everyone in time
+    fn check_acceptance_deadline(&self) -> Result<DeadlineChecked> {
+        if !self.deal_state.acceptance_expired() {
+            return Err(ErrorCodes::AcceptanceDeadlineNotExpired.into());
+        }
+        Ok(DeadlineChecked)
+    }
+
+    /// The deposit goes back with the service fee the escrow held for the offer
+    fn refund(&self) -> Result<u64> {
+        accounting::add(self.deal_state.refund(true)?, self.deal_state.held_service_fee)
+    }
+
+    fn transfer_deposit(&self) -> Result<DepositTransfered> {
+        self.escrow().transfer(
+            &self.deal_state_deal_ta,
+            &self.client_deal_ta,
+            &self.deal_mint.to_account_info(),
+            self.refund()?,
+        )?;
+        Ok(DepositTransfered)
+    }
+
+    fn transfer_bonds(&self) -> Result<BondsTransfered> {
+        if let Some(Bond { amount, mint }) = self.deal_state.client_bond {
+            self.escrow().transfer(
+                &self.deal_state_client_bond_ta,
+                &self.client_bond_ta,
+                &self.client_bond_mint,
+                amount,
+            )?;
+            emit!(BondReturned {
+                deal_id: self.deal_state.id,
+                owner: self.deal_state.client_key,
+                mint,
+                amount,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+        // the executor bond is posted only with `accept_offer`
+        let executor_bond_posted =
+            self.deal_state.executor_bond.is_some() && self.deal_state.executor_accepted;
+        if let Some(Bond { amount, mint }) = self.deal_state.executor_bond {
+            if executor_bond_posted {
+                self.escrow().transfer(
+                    &self.deal_state_executor_bond_ta,
+                    &self.executor_bond_ta,
+                    &self.executor_bond_mint,
+                    amount,
+                )?;
+                emit!(BondReturned {
+                    deal_id: self.deal_state.id,
+                    owner: self.deal_state.executor_key,
+                    mint,
+                    amount,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+            }
+        }
+
+        if self.deal_state.client_bond.is_some() {
+            self.escrow().close(&self.deal_state_client_bond_ta, &self.client_bond_mint, &self.service_fee)?;
+        }
+        if executor_bond_posted {
+            self.escrow().close(&self.deal_state_executor_bond_ta, &self.executor_bond_mint, &self.service_fee)?;
+        }
+
+        Ok(BondsTransfered)
+    }
+}
+
+#[access_control(WithdrawOffer::check_accounts(&ctx))]
+pub fn handle(ctx: Context<WithdrawOffer>) -> Result<()> {
+    let deadline_checked = ctx.accounts.check_acceptance_deadline()?;
+    let deposit_transfered = ctx.accounts.transfer_deposit()?;
+    let bonds_transfered = ctx.accounts.transfer_bonds()?;
+    let holder_mode_handled =
+        release_holder_stake(&ctx.accounts.deal_state, ctx.accounts.holder_stake.as_deref_mut())?;
+    let deal_state_deal_ta_closed = ctx.accounts.escrow().close(
+        &ctx.accounts.deal_state_deal_ta,
+        &ctx.accounts.deal_mint.to_account_info(),
+        &ctx.accounts.service_fee,
+    )?;
+
+    emit!(DealCancelled {
+        deal_id: ctx.accounts.deal_state.id,
+        client: ctx.accounts.deal_state.client_key,
+        executor: ctx.accounts.deal_state.executor_key,
+        initializer: ctx.accounts.client.key(),
+        deal_mint: ctx.accounts.deal_state.deal_token_mint,
+        refund: ctx.accounts.refund()?,
+        checker_fee: 0,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    let _ = Checklist {
+        deadline_checked,
+        deposit_transfered,
+        bonds_transfered,
+        holder_mode_handled,
+        deal_state_deal_ta_closed,
+    };
+
+    Ok(())
+}
@@ -1,13 +1,23 @@
+// `anchor_lang::error::Error` is large, every `Result` returning function would warn
+#![allow(clippy::result_large_err)]
+
 use anchor_lang::prelude::*;
 use std::convert::Into;
 
-mod constants;
-mod errors;
+pub mod constants;
+pub mod errors;
+pub mod events;
 mod instructions;
-mod state;
+pub mod state;
 mod utils;
 
 use instructions::*;
+pub use instructions::{
+    AmendDealArgs, BondSide, CheckerUpdate, CreateOfferArgs, DeadlineAmendment, InitializeArgs,
+    OpenDisputeArgs, PartiallyPayArgs, PaymentMode, ResolveDisputeArgs, SettleMilestoneArgs,
+    SubmitEvidenceArgs,
+};
+use state::{ConfigArgs, FeeScheduleArgs, PanelVote};
 
 declare_id!("GKNkN4uDJWmidEC9h5Q9GQXNg48Go6q5bdnkDj6bSopz");
 
@@ -20,19 +30,115 @@ pub mod deal_contract {
         instructions::initialize::handle(ctx, args)
     }
 
-    pub fn finish(ctx: Context<Finish>) -> Result<()> {
+    pub fn finish<'info>(ctx: Context<'_, '_, '_, 'info, Finish<'info>>) -> Result<()> {
         instructions::finish::handle(ctx)
     }
 
-    pub fn cancel(ctx: Context<Cancel>) -> Result<()> {
+    pub fn cancel<'info>(ctx: Context<'_, '_, '_, 'info, Cancel<'info>>) -> Result<()> {
         instructions::cancel::handle(ctx)
     }
 
-    pub fn update_checker(ctx: Context<UpdateChecker>, new_checker_fee: u64) -> Result<()> {
-        instructions::update_checker::handle(ctx, new_checker_fee)
+    pub fn mutual_cancel<'info>(ctx: Context<'_, '_, '_, 'info, Cancel<'info>>) -> Result<()> {
+        instructions::cancel::handle_mutual(ctx)
+    }
+
+    pub fn update_checker(ctx: Context<UpdateChecker>, update: CheckerUpdate) -> Result<()> {
+        instructions::update_checker::handle(ctx, update)
     }
 
     pub fn partially_pay(ctx: Context<PartiallyPay>, args: PartiallyPayArgs) -> Result<()> {
         instructions::partially_pay::handle(ctx, args)
     }
+
+    pub fn init_config(ctx: Context<InitConfig>, args: ConfigArgs) -> Result<()> {
+        instructions::init_config::handle(ctx, args)
+    }
+
+    pub fn update_config(ctx: Context<UpdateConfig>, args: ConfigArgs) -> Result<()> {
+        instructions::update_config::handle(ctx, args)
+    }
+
+    pub fn set_fee_schedule(ctx: Context<SetFeeSchedule>, args: FeeScheduleArgs) -> Result<()> {
+        instructions::set_fee_schedule::handle(ctx, args)
+    }
+
+    pub fn pause(ctx: Context<SetPaused>, flags: u32) -> Result<()> {
+        instructions::pause::handle_pause(ctx, flags)
+    }
+
+    pub fn unpause(ctx: Context<SetPaused>, flags: u32) -> Result<()> {
+        instructions::pause::handle_unpause(ctx, flags)
+    }
+
+    pub fn open_dispute(ctx: Context<OpenDispute>, args: OpenDisputeArgs) -> Result<()> {
+        instructions::open_dispute::handle(ctx, args)
+    }
+
+    pub fn submit_evidence(ctx: Context<SubmitEvidence>, args: SubmitEvidenceArgs) -> Result<()> {
+        instructions::submit_evidence::handle(ctx, args)
+    }
+
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, args: ResolveDisputeArgs) -> Result<()> {
+        instructions::resolve_dispute::handle(ctx, args)
+    }
+
+    pub fn approve_milestone(ctx: Context<ApproveMilestone>, args: SettleMilestoneArgs) -> Result<()> {
+        instructions::approve_milestone::handle(ctx, args)
+    }
+
+    pub fn reject_milestone(ctx: Context<RejectMilestone>, args: SettleMilestoneArgs) -> Result<()> {
+        instructions::reject_milestone::handle(ctx, args)
+    }
+
+    pub fn create_offer(ctx: Context<CreateOffer>, args: CreateOfferArgs) -> Result<()> {
+        instructions::create_offer::handle(ctx, args)
+    }
+
+    pub fn accept_offer(ctx: Context<AcceptOffer>) -> Result<()> {
+        instructions::accept_offer::handle(ctx)
+    }
+
+    pub fn accept_role(ctx: Context<AcceptRole>) -> Result<()> {
+        instructions::accept_role::handle(ctx)
+    }
+
+    pub fn withdraw_offer(ctx: Context<WithdrawOffer>) -> Result<()> {
+        instructions::withdraw_offer::handle(ctx)
+    }
+
+    pub fn amend_deal(ctx: Context<AmendDeal>, args: AmendDealArgs) -> Result<()> {
+        instructions::amend_deal::handle(ctx, args)
+    }
+
+    pub fn mark_delivered(ctx: Context<MarkDelivered>) -> Result<()> {
+        instructions::mark_delivered::handle(ctx)
+    }
+
+    pub fn claim_after_review<'info>(ctx: Context<'_, '_, '_, 'info, Finish<'info>>) -> Result<()> {
+        instructions::finish::handle_claim(ctx)
+    }
+
+    pub fn slash_bond(ctx: Context<SlashBond>, side: BondSide) -> Result<()> {
+        instructions::slash_bond::handle(ctx, side)
+    }
+
+    pub fn checker_vote(ctx: Context<CheckerVote>, vote: PanelVote) -> Result<()> {
+        instructions::checker_vote::handle(ctx, vote)
+    }
+
+    pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+        instructions::stake::handle(ctx, amount)
+    }
+
+    pub fn unstake(ctx: Context<Unstake>, amount: u64) -> Result<()> {
+        instructions::unstake::handle(ctx, amount)
+    }
+
+    pub fn recover_holder_tokens(ctx: Context<RecoverHolderTokens>, deal_id: [u8; 16]) -> Result<()> {
+        instructions::recover_holder_tokens::handle(ctx, deal_id)
+    }
+
+    pub fn migrate_deal(ctx: Context<MigrateDeal>) -> Result<()> {
+        instructions::migrate_deal::handle(ctx)
+    }
 }
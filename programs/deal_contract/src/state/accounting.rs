@@ -0,0 +1,103 @@
+//! Amount accounting of a deal. Every amount a handler moves or records goes through here,
+//! so an inconsistent deal state fails with `MathOverflow` instead of wrapping or saturating.
+
+use anchor_lang::prelude::*;
+
+use super::{Checker, DealState};
+use crate::{constants::BASIS_POINTS, errors::ErrorCodes};
+
+pub fn add(a: u64, b: u64) -> Result<u64> {
+    Ok(a.checked_add(b).ok_or(ErrorCodes::MathOverflow)?)
+}
+
+pub fn sub(a: u64, b: u64) -> Result<u64> {
+    Ok(a.checked_sub(b).ok_or(ErrorCodes::MathOverflow)?)
+}
+
+/// Escrowed on deal creation: the deal amount and the checker fee
+pub fn deposit(deal_amount: u64, checker_fee: Option<u64>) -> Result<u64> {
+    add(deal_amount, checker_fee.unwrap_or_default())
+}
+
+/// `max_advance_bps` of `deal_amount`, rounded down
+pub fn max_advance(deal_amount: u64, max_advance_bps: u16) -> u64 {
+    (deal_amount as u128 * max_advance_bps as u128 / BASIS_POINTS as u128) as u64
+}
+
+/// Splits `amount` into `parts` shares, the first `amount % parts` ones get the remainder
+pub fn split_even(amount: u64, parts: usize) -> Result<Vec<u64>> {
+    let parts = parts as u64;
+    let share = amount.checked_div(parts).ok_or(ErrorCodes::MathOverflow)?;
+    let remainder = amount % parts;
+    Ok((0..parts).map(|part| share + u64::from(part < remainder)).collect())
+}
+
+impl DealState {
+    pub fn checker_fee(&self) -> u64 {
+        self.checker.as_ref().map_or(0, |Checker { checker_fee, .. }| *checker_fee)
+    }
+
+    pub fn deposit(&self) -> Result<u64> {
+        deposit(self.amount, Some(self.checker_fee()))
+    }
+
+    /// The part of the deal amount still held by the deal state.
+    /// Goes to the executor on finish and back to the client on cancel
+    pub fn remaining_escrow(&self) -> Result<u64> {
+        sub(self.amount, self.paid_amount)
+    }
+
+    /// Returned to the client on cancel. The checker fee is included when the checker is not paid
+    pub fn refund(&self, with_checker_fee: bool) -> Result<u64> {
+        let remaining_escrow = self.remaining_escrow()?;
+        if with_checker_fee {
+            add(remaining_escrow, self.checker_fee())
+        } else {
+            Ok(remaining_escrow)
+        }
+    }
+
+    /// Records `amount` released from the escrow to the executor
+    pub fn release(&mut self, amount: u64) -> Result<()> {
+        if amount > self.remaining_escrow()? {
+            return Err(ErrorCodes::ReleaseExceedsEscrow.into());
+        }
+        self.paid_amount = add(self.paid_amount, amount)?;
+        Ok(())
+    }
+
+    /// Sets a renegotiated deal amount, returns the extra deposit and the refund to the client.
+    /// The amount may not drop below what is already paid to the executor
+    pub fn amend_amount(&mut self, amount: u64) -> Result<(u64, u64)> {
+        if amount < self.paid_amount {
+            return Err(ErrorCodes::AmountBelowPaid.into());
+        }
+        let (deposit, refund) = if amount >= self.amount {
+            (sub(amount, self.amount)?, 0)
+        } else {
+            (0, sub(self.amount, amount)?)
+        };
+        self.amount = amount;
+        Ok((deposit, refund))
+    }
+
+    /// Sets the checker of the deal, returns the extra deposit and the refund to the client
+    /// for the difference between the old and the new checker fee
+    pub fn set_checker(&mut self, checker: Option<Checker>) -> Result<(u64, u64)> {
+        let old_fee = self.checker_fee();
+        let new_fee = checker.as_ref().map_or(0, |Checker { checker_fee, .. }| *checker_fee);
+        self.checker = checker;
+        if new_fee >= old_fee {
+            Ok((sub(new_fee, old_fee)?, 0))
+        } else {
+            Ok((0, sub(old_fee, new_fee)?))
+        }
+    }
+
+    /// Drops `amount` of the escrow from the deal, e.g. a rejected milestone refunded to the client
+    pub fn reduce_amount(&mut self, amount: u64) -> Result<()> {
+        self.amount = sub(self.amount, amount)?;
+        self.remaining_escrow()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use anchor_lang::prelude::*;
+
+use super::HolderStake;
+use crate::{
+    constants::{BASIS_POINTS, PAUSE_ALL},
+    errors::{ErrorCodes, InvalidAccount},
+};
+
+pub const MAX_HOLDER_TIERS: usize = 4;
+
+/// Locking `amount` of holder tokens takes `discount_bps` off the service fee
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct HolderTier {
+    pub amount: u64,
+    pub discount_bps: u16,
+}
+
+impl HolderTier {
+    pub fn discounted(&self, service_fee: u64) -> u64 {
+        let discount = (service_fee as u128 * self.discount_bps as u128 / BASIS_POINTS as u128) as u64;
+        service_fee - discount
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ConfigArgs {
+    pub authority: Pubkey,
+    pub service_account: Pubkey,
+    pub service_fee_owner: Pubkey,
+    pub holder_mint: Pubkey,
+    pub holder_tiers: Vec<HolderTier>,
+    pub holder_stake_cooldown: i64,
+    pub max_advance_bps: u16,
+    pub review_window: i64,
+}
+
+/// Bumped whenever fields are appended to `ProgramConfig`
+pub const CONFIG_VERSION: u8 = 1;
+
+/// Cluster specific settings. A single PDA per program, so the same binary
+/// can be deployed to devnet, mainnet and localnet.
+///
+/// Fields are only appended after `bump`.
+#[account]
+pub struct ProgramConfig {
+    /// Allowed to call `update_config`
+    pub authority: Pubkey,
+    /// Admin level account (may cancel any deal or replace a checker)
+    pub service_account: Pubkey,
+    pub service_fee_owner: Pubkey,
+
+    pub holder_mint: Pubkey,
+
+    pub bump: [u8; 1],
+
+    pub version: u8,
+
+    /// Ascending by amount, unused tiers are zeroed
+    pub holder_tiers: [HolderTier; MAX_HOLDER_TIERS],
+    /// Seconds a stake stays locked after its last deposit
+    pub holder_stake_cooldown: i64,
+
+    /// Largest advance payment allowed on `initialize`, in basis points of the deal amount
+    pub max_advance_bps: u16,
+    /// Seconds the client has to finish or dispute a deal after `mark_delivered`
+    pub review_window: i64,
+
+    /// Bitmask of the paused instructions, see the `PAUSE_*` constants
+    pub paused: u32,
+}
+
+impl ProgramConfig {
+    pub const SPACE: usize = 8 + std::mem::size_of::<ProgramConfig>(); // 8 is for anchor discriminator
+
+    pub fn bump(&self) -> u8 {
+        self.bump[0]
+    }
+
+    pub fn apply(&mut self, args: ConfigArgs) -> Result<()> {
+        if args.max_advance_bps > BASIS_POINTS {
+            return Err(ErrorCodes::InvalidBasisPoints.into());
+        }
+        if args.review_window <= 0 {
+            return Err(ErrorCodes::InvalidReviewWindow.into());
+        }
+        if args.holder_stake_cooldown < 0 {
+            return Err(ErrorCodes::InvalidStakeCooldown.into());
+        }
+        if args.holder_tiers.len() > MAX_HOLDER_TIERS {
+            return Err(ErrorCodes::InvalidHolderTiers.into());
+        }
+        let mut previous = HolderTier::default();
+        for tier in args.holder_tiers.iter() {
+            if tier.discount_bps > BASIS_POINTS {
+                return Err(ErrorCodes::InvalidBasisPoints.into());
+            }
+            if tier.amount <= previous.amount || tier.discount_bps <= previous.discount_bps {
+                return Err(ErrorCodes::InvalidHolderTiers.into());
+            }
+            previous = *tier;
+        }
+        self.authority = args.authority;
+        self.service_account = args.service_account;
+        self.service_fee_owner = args.service_fee_owner;
+        self.holder_mint = args.holder_mint;
+        self.holder_tiers = [HolderTier::default(); MAX_HOLDER_TIERS];
+        self.holder_tiers[..args.holder_tiers.len()].copy_from_slice(&args.holder_tiers);
+        self.holder_stake_cooldown = args.holder_stake_cooldown;
+        self.max_advance_bps = args.max_advance_bps;
+        self.review_window = args.review_window;
+        Ok(())
+    }
+
+    pub fn check_not_paused(&self, instruction: u32) -> Result<()> {
+        if self.paused & instruction != 0 {
+            return Err(ErrorCodes::InstructionPaused.into());
+        }
+        Ok(())
+    }
+
+    /// `flags` is a non-empty set of `PAUSE_*` bits
+    pub fn set_paused(&mut self, flags: u32, paused: bool) -> Result<()> {
+        if flags == 0 || flags & !PAUSE_ALL != 0 {
+            return Err(ErrorCodes::InvalidPauseFlags.into());
+        }
+        if paused {
+            self.paused |= flags;
+        } else {
+            self.paused &= !flags;
+        }
+        Ok(())
+    }
+
+    /// The largest tier `holder_balance` covers
+    pub fn holder_tier(&self, holder_balance: u64) -> Option<HolderTier> {
+        self.holder_tiers
+            .iter()
+            .rev()
+            .find(|tier| tier.amount > 0 && tier.amount <= holder_balance)
+            .copied()
+    }
+
+    /// The largest tier the client stake covers, for a holder mode deal
+    pub fn stake_tier(&self, holder_stake: Option<&Account<HolderStake>>) -> Result<HolderTier> {
+        let holder_stake = holder_stake.ok_or(InvalidAccount::HolderStake)?;
+        Ok(self.holder_tier(holder_stake.amount).ok_or(ErrorCodes::HolderModeUnavailable)?)
+    }
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+pub const MAX_EVIDENCE: usize = 16;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Evidence {
+    pub submitter: Pubkey,
+    /// Hash of the off-chain content (documents, screenshots, chat logs, ...)
+    pub hash: [u8; 32],
+    pub submitted_ts: i64,
+}
+
+#[account]
+pub struct Dispute {
+    pub deal_state: Pubkey,
+    pub initiator: Pubkey,
+    pub opened_ts: i64,
+    pub evidence: Vec<Evidence>,
+    pub bump: [u8; 1],
+}
+
+impl Dispute {
+    pub const SPACE: usize = 8 // anchor discriminator
+        + 32 + 32 + 8
+        + 4 + MAX_EVIDENCE * (32 + 32 + 8)
+        + 1;
+
+    pub fn bump(&self) -> u8 {
+        self.bump[0]
+    }
+}
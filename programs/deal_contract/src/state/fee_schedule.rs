@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+
+use super::HolderTier;
+use crate::{constants::BASIS_POINTS, errors::ErrorCodes};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct FeeScheduleArgs {
+    pub fee_bps: u16,
+    pub min_fee: u64,
+    pub max_fee: u64,
+}
+
+/// Service fee rate of a deal mint, a single PDA per mint. Deals can't be created
+/// in a mint without one.
+#[account]
+pub struct FeeSchedule {
+    pub mint: Pubkey,
+    /// Charged on the deal amount, in basis points
+    pub fee_bps: u16,
+    /// Bounds of the fee, in base units of the mint
+    pub min_fee: u64,
+    pub max_fee: u64,
+
+    pub bump: [u8; 1],
+}
+
+impl FeeSchedule {
+    pub const SPACE: usize = 8 // anchor discriminator
+        + 32
+        + 2
+        + 8
+        + 8
+        + 1;
+
+    pub fn bump(&self) -> u8 {
+        self.bump[0]
+    }
+
+    pub fn apply(&mut self, args: FeeScheduleArgs) -> Result<()> {
+        if args.fee_bps > BASIS_POINTS {
+            return Err(ErrorCodes::InvalidBasisPoints.into());
+        }
+        if args.min_fee > args.max_fee {
+            return Err(ErrorCodes::InvalidFeeSchedule.into());
+        }
+        self.fee_bps = args.fee_bps;
+        self.min_fee = args.min_fee;
+        self.max_fee = args.max_fee;
+        Ok(())
+    }
+
+    /// `fee_bps` of `deal_amount` rounded down, clamped to `[min_fee, max_fee]`
+    pub fn service_fee(&self, deal_amount: u64) -> u64 {
+        let fee = (deal_amount as u128 * self.fee_bps as u128 / BASIS_POINTS as u128) as u64;
+        fee.clamp(self.min_fee, self.max_fee)
+    }
+
+    /// `service_fee` less the holder tier discount, up to the `limit` set by the client
+    pub fn discounted_fee(
+        &self,
+        deal_amount: u64,
+        holder_tier: Option<HolderTier>,
+        limit: Option<u64>,
+    ) -> Result<u64> {
+        let service_fee = self.service_fee(deal_amount);
+        let service_fee =
+            holder_tier.map_or(service_fee, |holder_tier| holder_tier.discounted(service_fee));
+        if limit.is_some_and(|limit| service_fee > limit) {
+            return Err(ErrorCodes::ServiceFeeAboveLimit.into());
+        }
+        Ok(service_fee)
+    }
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::{constants::HOLDER_STAKE_SEED, errors::ErrorCodes};
+
+/// Holder tokens staked by a client, a single PDA per owner holding them in its holder ATA.
+/// Holder mode deals reference the stake instead of locking tokens each, so its tier backs
+/// any number of deals at once.
+#[account]
+pub struct HolderStake {
+    pub owner: Pubkey,
+    pub amount: u64,
+    /// Holder mode deals of the owner not settled yet, the stake can't be withdrawn meanwhile
+    pub active_deals: u32,
+    /// Set to `holder_stake_cooldown` after every deposit
+    pub unlock_ts: i64,
+
+    pub bump: [u8; 1],
+}
+
+impl HolderStake {
+    pub const SPACE: usize = 8 // anchor discriminator
+        + 32
+        + 8
+        + 4
+        + 8
+        + 1;
+
+    pub fn bump(&self) -> u8 {
+        self.bump[0]
+    }
+
+    pub fn seeds(&self) -> [&[u8]; 3] {
+        [HOLDER_STAKE_SEED, self.owner.as_ref(), &self.bump]
+    }
+
+    pub fn acquire(&mut self) -> Result<()> {
+        self.active_deals = self.active_deals.checked_add(1).ok_or(ErrorCodes::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Fails with `MathOverflow` when no deal references the stake
+    pub fn release(&mut self) -> Result<()> {
+        self.active_deals = self.active_deals.checked_sub(1).ok_or(ErrorCodes::MathOverflow)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCodes;
+
+pub const MAX_MILESTONES: usize = 10;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MilestoneStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MilestoneArgs {
+    pub amount: u64,
+    pub deadline_ts: Option<i64>,
+    pub description_hash: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Milestone {
+    pub amount: u64,
+    pub deadline_ts: Option<i64>,
+    pub description_hash: [u8; 32],
+    pub status: MilestoneStatus,
+}
+
+impl From<&MilestoneArgs> for Milestone {
+    fn from(args: &MilestoneArgs) -> Self {
+        Milestone {
+            amount: args.amount,
+            deadline_ts: args.deadline_ts,
+            description_hash: args.description_hash,
+            status: MilestoneStatus::Pending,
+        }
+    }
+}
+
+impl Milestone {
+    pub fn deadline_expired(&self) -> bool {
+        match self.deadline_ts {
+            Some(deadline_ts) => {
+                let current_ts = Clock::get().expect("Failed to get Clock SysVar").unix_timestamp;
+                deadline_ts < current_ts
+            }
+            None => false,
+        }
+    }
+}
+
+/// Child account of a deal created with a milestone list
+#[account]
+pub struct DealMilestones {
+    pub deal_state: Pubkey,
+    pub milestones: Vec<Milestone>,
+    pub bump: [u8; 1],
+}
+
+impl DealMilestones {
+    pub const SPACE: usize = 8 // anchor discriminator
+        + 32
+        + 4 + MAX_MILESTONES * (8 + 9 + 32 + 1)
+        + 1;
+
+    pub fn bump(&self) -> u8 {
+        self.bump[0]
+    }
+
+    /// The milestone at `index`, as long as it is neither approved nor rejected
+    pub fn pending_milestone(&mut self, index: u8) -> Result<&mut Milestone> {
+        let milestone = self
+            .milestones
+            .get_mut(index as usize)
+            .ok_or(ErrorCodes::MilestoneNotFound)?;
+        if milestone.status != MilestoneStatus::Pending {
+            return Err(ErrorCodes::MilestoneAlreadySettled.into());
+        }
+        Ok(milestone)
+    }
+}
@@ -1,17 +1,90 @@
 use crate::{constants::DEAL_STATE_SEED, errors::ErrorCodes};
 pub use anchor_lang::prelude::*;
 
+pub mod accounting;
+
+pub mod config;
+pub use config::*;
+
+pub mod dispute;
+pub use dispute::*;
+
+pub mod fee_schedule;
+pub use fee_schedule::*;
+
+pub mod holder_stake;
+pub use holder_stake::*;
+
+pub mod milestones;
+pub use milestones::*;
+
+pub mod panel;
+pub use panel::*;
+
+pub mod receipt;
+pub use receipt::*;
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct Bond {
     pub mint: Pubkey,
     pub amount: u64,
 }
+/// When a bond goes to the other party instead of being returned to its owner
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BondForfeiture {
+    /// The executor bond goes to the client when the deal is cancelled after the deadline
+    /// without the executor having marked it delivered
+    pub executor_on_deadline: bool,
+    /// The checker may slash either bond to the other party with `slash_bond`
+    pub checker_slash: bool,
+}
+
+impl BondForfeiture {
+    /// A rule needs the bond it forfeits and whatever triggers it
+    pub fn check(
+        &self,
+        deadline_ts: Option<i64>,
+        client_bond: Option<u64>,
+        executor_bond: Option<u64>,
+        checker_fee: Option<u64>,
+        with_panel: bool,
+    ) -> Result<()> {
+        if self.executor_on_deadline && (executor_bond.is_none() || deadline_ts.is_none()) {
+            return Err(ErrorCodes::InvalidForfeitureRules.into());
+        }
+        if self.checker_slash
+            && (checker_fee.is_none() || with_panel || (client_bond.is_none() && executor_bond.is_none()))
+        {
+            return Err(ErrorCodes::InvalidForfeitureRules.into());
+        }
+        Ok(())
+    }
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct Checker {
     pub checker_fee: u64,
     pub checker_key: Pubkey,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DealStatus {
+    /// Created before the status was recorded, `migrate_deal` activates it
+    Legacy,
+    /// Created by `create_offer`, waits for the executor (and the checker) to accept it
+    Created,
+    Active,
+    Disputed,
+    /// Settled. `deal_state` is closed right after, the status is kept only in `DealReceipt`
+    Finished,
+    Cancelled,
+}
+
+/// Bumped whenever fields are appended to `DealState`
+pub const DEAL_STATE_VERSION: u8 = 1;
+
+/// Fields are only appended after `bump`, so that deals of the first version keep their layout.
+/// Their zeroed padding reads as `version` 0 and `DealStatus::Legacy` until `migrate_deal`.
 #[account]
 pub struct DealState {
     pub id: [u8; 16],
@@ -25,6 +98,7 @@ pub struct DealState {
 
     pub checker: Option<Checker>,
 
+    /// The holder tier amount of the client stake backing the deal, see `HolderStake`
     pub holder_mode: Option<u64>,
 
     pub amount: u64,
@@ -33,9 +107,53 @@ pub struct DealState {
     pub deadline_ts: Option<i64>,
 
     pub bump: [u8; 1],
+
+    pub version: u8,
+    pub status: DealStatus,
+    pub forfeiture: BondForfeiture,
+    pub with_milestones: bool,
+    /// The checker is a `CheckerPanel`, its key is the panel address
+    pub with_panel: bool,
+
+    pub acceptance_deadline_ts: Option<i64>,
+    pub executor_accepted: bool,
+    pub checker_accepted: bool,
+
+    /// Incremented by every `amend_deal`
+    pub amendments: u32,
+
+    /// Set by `mark_delivered`. Once it passes, the executor may settle the deal with `claim_after_review`
+    pub review_deadline_ts: Option<i64>,
+
+    /// The service fee of an offer, held in the escrow until the offer is active. `withdraw_offer` refunds it
+    pub held_service_fee: u64,
+}
+
+/// `DealState` of the first version of the program, the prefix of the current layout
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LegacyDealState {
+    pub id: [u8; 16],
+    pub client_key: Pubkey,
+    pub executor_key: Pubkey,
+    pub deal_token_mint: Pubkey,
+    pub client_bond: Option<Bond>,
+    pub executor_bond: Option<Bond>,
+    pub checker: Option<Checker>,
+    /// Holder tokens locked in the deal state holder token account
+    pub holder_mode: Option<u64>,
+    pub amount: u64,
+    pub paid_amount: u64,
+    pub deadline_ts: Option<i64>,
+    pub bump: [u8; 1],
+}
+
+impl LegacyDealState {
+    pub const SPACE: usize = 8 + std::mem::size_of::<LegacyDealState>(); // 8 is for anchor discriminator
 }
 
 impl DealState {
+    pub const SPACE: usize = 8 + std::mem::size_of::<DealState>(); // 8 is for anchor discriminator
+
     pub fn id(&self) -> u128 {
         u128::from_le_bytes(self.id)
     }
@@ -73,15 +191,50 @@ impl DealState {
         Ok(self.checker.as_mut().ok_or(ErrorCodes::DealStateNotWithChecker)?)
     }
 
+    pub fn is_active(&self) -> bool {
+        self.status == DealStatus::Active
+    }
+
+    pub fn is_disputed(&self) -> bool {
+        self.status == DealStatus::Disputed
+    }
+
+    pub fn is_offer(&self) -> bool {
+        self.status == DealStatus::Created
+    }
+
+    /// The offer becomes an active deal once every party accepted its role
+    pub fn try_activate(&mut self) {
+        if self.executor_accepted && (self.checker.is_none() || self.checker_accepted) {
+            self.status = DealStatus::Active;
+        }
+    }
+
+    pub fn acceptance_expired(&self) -> bool {
+        match self.acceptance_deadline_ts {
+            Some(acceptance_deadline_ts) => {
+                let current_ts = Clock::get().expect("Failed to get Clock SysVar").unix_timestamp;
+                acceptance_deadline_ts < current_ts
+            }
+            None => false,
+        }
+    }
+
+    pub fn review_expired(&self) -> bool {
+        match self.review_deadline_ts {
+            Some(review_deadline_ts) => {
+                let current_ts = Clock::get().expect("Failed to get Clock SysVar").unix_timestamp;
+                review_deadline_ts < current_ts
+            }
+            None => false,
+        }
+    }
+
     pub fn deadline_expired(&self) -> bool {
         match self.deadline_ts {
             Some(deadline_ts) => {
                 let current_ts = Clock::get().expect("Failed to get Clock SysVar").unix_timestamp;
-                if deadline_ts < current_ts {
-                    true
-                } else {
-                    false
-                }
+                deadline_ts < current_ts
             }
             None => false,
         }
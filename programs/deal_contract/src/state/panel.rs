@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::{ErrorCodes, InvalidAccount};
+
+pub const MAX_PANEL_CHECKERS: usize = 7;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PanelVote {
+    /// The deal is finished, the payment goes to the executor
+    Approve,
+    /// The deal is cancelled, the deposit goes back to the client
+    Cancel,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CheckerPanelArgs {
+    pub checkers: Vec<Pubkey>,
+    /// Matching votes needed to settle the deal, more than half of the panel
+    pub threshold: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PanelMember {
+    pub checker_key: Pubkey,
+    pub vote: Option<PanelVote>,
+}
+
+/// Child account of a deal checked by a panel instead of a single checker.
+/// The checker key of such a deal is the panel address.
+#[account]
+pub struct CheckerPanel {
+    pub deal_state: Pubkey,
+    pub members: Vec<PanelMember>,
+    pub threshold: u8,
+    pub bump: [u8; 1],
+}
+
+impl CheckerPanel {
+    pub const SPACE: usize = 8 // anchor discriminator
+        + 32
+        + 4 + MAX_PANEL_CHECKERS * (32 + 2)
+        + 1
+        + 1;
+
+    pub fn bump(&self) -> u8 {
+        self.bump[0]
+    }
+
+    pub fn is_member(&self, key: &Pubkey) -> bool {
+        self.members.iter().any(|member| member.checker_key == *key)
+    }
+
+    /// Members signing among `accounts`, each counted once
+    pub fn signers(&self, accounts: &[AccountInfo]) -> usize {
+        self.members
+            .iter()
+            .filter(|member| {
+                accounts.iter().any(|account| account.is_signer && *account.key == member.checker_key)
+            })
+            .count()
+    }
+
+    pub fn votes(&self, vote: PanelVote) -> usize {
+        self.members.iter().filter(|member| member.vote == Some(vote)).count()
+    }
+
+    /// The threshold is more than half of the panel, so at most one side reaches it
+    pub fn decision(&self) -> Option<PanelVote> {
+        [PanelVote::Approve, PanelVote::Cancel]
+            .into_iter()
+            .find(|vote| self.votes(*vote) >= self.threshold as usize)
+    }
+
+    pub fn vote(&mut self, checker: &Pubkey, vote: PanelVote) -> Result<()> {
+        if self.decision().is_some() {
+            return Err(ErrorCodes::PanelAlreadyDecided.into());
+        }
+        let member = self
+            .members
+            .iter_mut()
+            .find(|member| member.checker_key == *checker)
+            .ok_or(InvalidAccount::Checker)?;
+        if member.vote.is_some() {
+            return Err(ErrorCodes::AlreadyVoted.into());
+        }
+        member.vote = Some(vote);
+        Ok(())
+    }
+
+    /// The checkers who voted share the checker fee
+    pub fn fee_recipients(&self) -> Vec<Pubkey> {
+        self.members
+            .iter()
+            .filter(|member| member.vote.is_some())
+            .map(|member| member.checker_key)
+            .collect()
+    }
+}
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use super::DealStatus;
+
+/// Compact record of a settled deal, kept after `deal_state` is closed
+#[account]
+pub struct DealReceipt {
+    pub deal_state: Pubkey,
+    pub id: [u8; 16],
+    pub client_key: Pubkey,
+    pub executor_key: Pubkey,
+    pub deal_token_mint: Pubkey,
+
+    /// `Finished` or `Cancelled`
+    pub status: DealStatus,
+
+    pub amount: u64,
+    /// paid to the executor over the whole deal, advance and partial payments included
+    pub executor_received: u64,
+    /// returned to the client on settlement
+    pub client_refunded: u64,
+    /// paid to the checker
+    pub checker_fee: u64,
+
+    pub settled_by: Pubkey,
+    pub settled_ts: i64,
+
+    pub bump: [u8; 1],
+}
+
+impl DealReceipt {
+    pub const SPACE: usize = 8 // anchor discriminator
+        + 32 + 16 + 32 + 32 + 32
+        + 1
+        + 8 + 8 + 8 + 8
+        + 32 + 8
+        + 1;
+
+    pub fn bump(&self) -> u8 {
+        self.bump[0]
+    }
+}
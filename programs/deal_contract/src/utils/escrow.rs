@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, CloseAccount};
+
+use super::{
+    harvest_withheld_tokens, is_native_mint, token_program_for, transfer_lamports, transfer_signed,
+    AccountClosed,
+};
+use crate::state::DealState;
+
+/// The token accounts owned by a deal state, which signs every transfer out of them.
+/// Native SOL deals keep their lamports in the deal state itself, see `is_native_mint`
+pub(crate) struct DealEscrow<'a, 'info> {
+    deal_state: &'a Account<'info, DealState>,
+    token_program: AccountInfo<'info>,
+    token_2022_program: AccountInfo<'info>,
+}
+
+impl<'a, 'info> DealEscrow<'a, 'info> {
+    pub fn new(
+        deal_state: &'a Account<'info, DealState>,
+        token_program: &impl ToAccountInfo<'info>,
+        token_2022_program: &impl ToAccountInfo<'info>,
+    ) -> Self {
+        Self {
+            deal_state,
+            token_program: token_program.to_account_info(),
+            token_2022_program: token_2022_program.to_account_info(),
+        }
+    }
+
+    pub fn token_program_for(&self, mint: &AccountInfo<'info>) -> AccountInfo<'info> {
+        token_program_for(mint, &self.token_program, &self.token_2022_program)
+    }
+
+    pub fn transfer(
+        &self,
+        from: &AccountInfo<'info>,
+        to: &AccountInfo<'info>,
+        mint: &impl ToAccountInfo<'info>,
+        amount: u64,
+    ) -> Result<()> {
+        let mint = mint.to_account_info();
+        if amount == 0 {
+            return Ok(());
+        }
+        if is_native_mint(mint.key) {
+            return transfer_lamports(from, to, amount);
+        }
+        transfer_signed(
+            from,
+            to,
+            &mint,
+            &self.deal_state.to_account_info(),
+            &self.token_program_for(&mint),
+            &self.deal_state.seeds(),
+            amount,
+        )
+    }
+
+    /// The rent goes to `destination`. Nothing to close for the native mint, the deal state
+    /// itself is closed by anchor, or for a token account that was never created
+    pub fn close(
+        &self,
+        token_account: &AccountInfo<'info>,
+        mint: &impl ToAccountInfo<'info>,
+        destination: &AccountInfo<'info>,
+    ) -> Result<AccountClosed> {
+        let mint = mint.to_account_info();
+        if is_native_mint(mint.key) || token_account.lamports() == 0 {
+            return Ok(AccountClosed);
+        }
+        let token_program = self.token_program_for(&mint);
+        harvest_withheld_tokens(&token_program, &mint, token_account)?;
+        token_interface::close_account(CpiContext::new_with_signer(
+            token_program,
+            CloseAccount {
+                account: token_account.clone(),
+                destination: destination.clone(),
+                authority: self.deal_state.to_account_info(),
+            },
+            &[&self.deal_state.seeds()[..]],
+        ))?;
+        Ok(AccountClosed)
+    }
+}
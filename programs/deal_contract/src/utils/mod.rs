@@ -1,17 +1,37 @@
-use anchor_lang::prelude::*;
-use anchor_spl::{token::TokenAccount, token_interface::spl_token_2022::cmp_pubkeys};
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::{token::spl_token::native_mint, token_interface::{
+    self,
+    spl_token_2022::{
+        self, cmp_pubkeys,
+        extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    },
+    InitializeAccount3, Mint, TokenAccount, TransferChecked,
+}};
 
-use crate::errors::ErrorCodes;
+use crate::{
+    constants::BASIS_POINTS,
+    errors::{ErrorCodes, InvalidAccount},
+    events::{CheckerVoted, HolderStakeReleased},
+    state::{accounting, CheckerPanel, DealState, HolderStake, PanelVote},
+};
 
 pub(crate) mod checklist;
 
-// pub(crate) struct SignaturesChecked;
+mod escrow;
+pub(crate) use escrow::*;
+
+pub(crate) struct SignaturesChecked;
 pub(crate) struct DealStateCreated;
 pub(crate) struct DealStateUpdated;
 pub(crate) struct DeadlineChecked;
 pub(crate) struct DealAmountChecked;
+pub(crate) struct MilestonesChecked;
+pub(crate) struct AdvancePaymentChecked;
+pub(crate) struct ForfeitureChecked;
+pub(crate) struct CheckerPanelChecked;
 
 pub(crate) struct CheckerFeeTransfered;
+pub(crate) struct ServiceFeeTransfered;
 pub(crate) struct DepositTransfered;
 pub(crate) struct BondsTransfered;
 pub(crate) struct HolderModeHandled;
@@ -25,6 +45,10 @@ pub(crate) struct AdvancePaymentTransfered;
 
 pub(crate) struct AccountClosed;
 
+pub(crate) struct DisputeOpened;
+pub(crate) struct MilestoneSettled;
+pub(crate) struct ReceiptRecorded;
+
 pub fn init_ata<'a, 'info>(
     payer: &'a AccountInfo<'info>,
     mint: &'a AccountInfo<'info>,
@@ -32,6 +56,9 @@ pub fn init_ata<'a, 'info>(
     ata: &'a AccountInfo<'info>,
     token_program: &'a AccountInfo<'info>,
 ) -> Result<()> {
+    if is_native_mint(mint.key) {
+        return check_native_account(ata, authority.key);
+    }
     solana_program::program::invoke(
         &spl_associated_token_account::instruction::create_associated_token_account(
             payer.key,
@@ -51,16 +78,358 @@ pub fn init_ata<'a, 'info>(
     Ok(())
 }
 
+/// Every bond is kept in its own token account at `[seed, deal_state]`, owned by the deal state,
+/// so the client and the executor bonds never share an account even with the same mint
+pub fn bond_vault(seed: &[u8], deal_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[seed, deal_state.as_ref()], &crate::ID)
+}
+
+/// Native SOL bonds are kept in the deal state itself, see `is_native_mint`
+pub fn check_bond_vault(vault: &AccountInfo, mint: &Pubkey, seed: &[u8], deal_state: &Pubkey) -> Result<()> {
+    if is_native_mint(mint) {
+        return check_native_account(vault, deal_state);
+    }
+    if !cmp_pubkeys(vault.key, &bond_vault(seed, deal_state).0) {
+        return Err(ErrorCode::ConstraintSeeds.into());
+    }
+    check_token_account(vault, mint, deal_state)
+}
+
+pub fn init_bond_vault<'a, 'info>(
+    payer: &'a AccountInfo<'info>,
+    mint: &'a AccountInfo<'info>,
+    deal_state: &'a AccountInfo<'info>,
+    vault: &'a AccountInfo<'info>,
+    seed: &[u8],
+    token_program: &'a AccountInfo<'info>,
+    system_program: &'a AccountInfo<'info>,
+) -> Result<()> {
+    if is_native_mint(mint.key) {
+        return check_native_account(vault, deal_state.key);
+    }
+    let (address, bump) = bond_vault(seed, deal_state.key);
+    if !cmp_pubkeys(vault.key, &address) {
+        return Err(ErrorCode::ConstraintSeeds.into());
+    }
+
+    let account_extensions = if cmp_pubkeys(mint.owner, &spl_token_2022::ID) {
+        let data = mint.try_borrow_data()?;
+        let mint_extensions = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?.get_extension_types()?;
+        ExtensionType::get_required_init_account_extensions(&mint_extensions)
+    } else {
+        vec![]
+    };
+    let space = ExtensionType::get_account_len::<spl_token_2022::state::Account>(&account_extensions);
+    let lamports = Rent::get()?.minimum_balance(space);
+    let signer_seeds: &[&[&[u8]]] = &[&[seed, deal_state.key.as_ref(), &[bump]]];
+
+    // the address may already hold lamports, `create_account` would fail on it
+    if vault.lamports() == 0 {
+        system_program::create_account(
+            CpiContext::new_with_signer(system_program.clone(), system_program::CreateAccount {
+                from: payer.clone(),
+                to: vault.clone(),
+            }, signer_seeds),
+            lamports,
+            space as u64,
+            token_program.key,
+        )?;
+    } else {
+        let top_up = lamports.saturating_sub(vault.lamports());
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(system_program.clone(), system_program::Transfer {
+                    from: payer.clone(),
+                    to: vault.clone(),
+                }),
+                top_up,
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(system_program.clone(), system_program::Allocate {
+                account_to_allocate: vault.clone(),
+            }, signer_seeds),
+            space as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(system_program.clone(), system_program::Assign {
+                account_to_assign: vault.clone(),
+            }, signer_seeds),
+            token_program.key,
+        )?;
+    }
+
+    token_interface::initialize_account3(CpiContext::new(token_program.clone(), InitializeAccount3 {
+        account: vault.clone(),
+        mint: mint.clone(),
+        authority: deal_state.clone(),
+    }))
+}
+
+/// Native SOL deals keep lamports in the deal state itself and pay out straight to the wallets,
+/// so for the native mint the "token account" of an owner is the owner account itself
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    cmp_pubkeys(mint, &native_mint::ID)
+}
+
+fn check_native_account(account: &AccountInfo, owner: &Pubkey) -> Result<()> {
+    if !cmp_pubkeys(account.key, owner) {
+        return Err(ErrorCodes::InvalidOwner.into());
+    }
+    Ok(())
+}
+
+/// Moves lamports out of an account owned by the program (the deal state)
+pub fn transfer_lamports<'info>(from: &AccountInfo<'info>, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+    **from.try_borrow_mut_lamports()? = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(ErrorCodes::MathOverflow)?;
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(ErrorCodes::MathOverflow)?;
+    Ok(())
+}
+
+/// Token program owning `mint`, so deal, bond, holder and service mints may live in different programs
+pub fn token_program_for<'info>(
+    mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    token_2022_program: &AccountInfo<'info>,
+) -> AccountInfo<'info> {
+    if cmp_pubkeys(mint.owner, token_2022_program.key) {
+        token_2022_program.clone()
+    } else {
+        token_program.clone()
+    }
+}
+
+fn transfer_fee_config(mint: &AccountInfo) -> Result<Option<TransferFeeConfig>> {
+    if !cmp_pubkeys(mint.owner, &spl_token_2022::ID) {
+        return Ok(None);
+    }
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(mint.get_extension::<TransferFeeConfig>().ok().copied())
+}
+
+/// Amount to send, so that the receiver gets exactly `amount` after the transfer fee is withheld
+pub fn amount_with_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    match transfer_fee_config(mint)? {
+        Some(config) => {
+            let fee = config
+                .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+                .ok_or(ErrorCodes::MathOverflow)?;
+            accounting::add(amount, fee)
+        }
+        None => Ok(amount),
+    }
+}
+
+/// Transfers from a wallet of `authority`, grossed up on transfer-fee mints so that `to` receives
+/// exactly `amount`. For the native mint `from` is the wallet itself, see `is_native_mint`
+pub fn transfer_from_wallet<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    if is_native_mint(mint.key) {
+        return system_program::transfer(
+            CpiContext::new(system_program.clone(), system_program::Transfer {
+                from: from.clone(),
+                to: to.clone(),
+            }),
+            amount,
+        );
+    }
+    let decimals = InterfaceAccount::<Mint>::try_from(mint)?.decimals;
+    token_interface::transfer_checked(
+        CpiContext::new(token_program.clone(), TransferChecked {
+            from: from.clone(),
+            mint: mint.clone(),
+            to: to.clone(),
+            authority: authority.clone(),
+        }),
+        amount_with_transfer_fee(mint, amount)?,
+        decimals,
+    )
+}
+
+/// Transfers out of a token account owned by the PDA `authority`
+pub fn transfer_signed<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let decimals = InterfaceAccount::<Mint>::try_from(mint)?.decimals;
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            TransferChecked {
+                from: from.clone(),
+                mint: mint.clone(),
+                to: to.clone(),
+                authority: authority.clone(),
+            },
+            &[signer_seeds],
+        ),
+        amount,
+        decimals,
+    )
+}
+
+/// A holder mode deal references the client stake until it is settled
+pub fn release_holder_stake(
+    deal_state: &DealState,
+    holder_stake: Option<&mut Account<HolderStake>>,
+) -> Result<HolderModeHandled> {
+    if deal_state.holder_mode.is_some() {
+        let holder_stake = holder_stake.ok_or(InvalidAccount::HolderStake)?;
+        holder_stake.release()?;
+        emit!(HolderStakeReleased {
+            deal_id: deal_state.id,
+            client: deal_state.client_key,
+            active_deals: holder_stake.active_deals,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+    Ok(HolderModeHandled)
+}
+
+/// A token account holding withheld transfer fees can't be closed, so the fees are moved to the mint first
+pub fn harvest_withheld_tokens<'info>(
+    token_program: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    token_account: &AccountInfo<'info>,
+) -> Result<()> {
+    if transfer_fee_config(mint)?.is_none() {
+        return Ok(());
+    }
+    solana_program::program::invoke(
+        &spl_token_2022::extension::transfer_fee::instruction::harvest_withheld_tokens_to_mint(
+            token_program.key,
+            mint.key,
+            &[token_account.key],
+        )?,
+        &[mint.clone(), token_account.clone(), token_program.clone()],
+    )?;
+    Ok(())
+}
+
+pub fn check_or_init_ata<'a, 'info>(
+    payer: &'a AccountInfo<'info>,
+    mint: &'a AccountInfo<'info>,
+    authority: &'a AccountInfo<'info>,
+    ata: &'a AccountInfo<'info>,
+    token_program: &'a AccountInfo<'info>,
+) -> Result<()> {
+    if is_native_mint(mint.key) {
+        return check_native_account(ata, authority.key);
+    }
+    match InterfaceAccount::<TokenAccount>::try_from(ata) {
+        Ok(token_account) => check_ta(&token_account, mint.key, authority.key),
+        Err(_) => init_ata(payer, mint, authority, ata, token_program),
+    }
+}
+
+/// Same as `check_ta` for an account that is not deserialized yet
+pub fn check_token_account(account: &AccountInfo, expected_mint: &Pubkey, expected_owner: &Pubkey) -> Result<()> {
+    if is_native_mint(expected_mint) {
+        return check_native_account(account, expected_owner);
+    }
+    let token_account = InterfaceAccount::<TokenAccount>::try_from(account)?;
+    check_ta(&token_account, expected_mint, expected_owner)
+}
+
 pub fn check_ta<'info>(
-    token_account: &Account<'info, TokenAccount>,
+    token_account: &InterfaceAccount<'info, TokenAccount>,
     expected_mint: &Pubkey,
     expected_owner: &Pubkey,
 ) -> Result<()> {
-    if !cmp_pubkeys(&token_account.mint, &expected_mint) {
+    if !cmp_pubkeys(&token_account.mint, expected_mint) {
         return Err(ErrorCodes::InvalidMint.into());
     };
-    if !cmp_pubkeys(&token_account.owner, &expected_owner) {
+    if !cmp_pubkeys(&token_account.owner, expected_owner) {
         return Err(ErrorCodes::InvalidOwner.into());
     };
     Ok(())
 }
+
+/// The vote of a panel member settling the deal, it must bring the panel to `vote`.
+/// `checker_vote` never reaches the threshold, so the decision and the settlement happen together
+pub fn cast_settling_vote(
+    checker_panel: &mut CheckerPanel,
+    deal_id: [u8; 16],
+    checker: &Pubkey,
+    vote: PanelVote,
+) -> Result<()> {
+    checker_panel.vote(checker, vote)?;
+    if checker_panel.decision() != Some(vote) {
+        return Err(ErrorCodes::PanelNotDecided.into());
+    }
+    emit!(CheckerVoted {
+        deal_id,
+        checker: *checker,
+        vote,
+        approvals: checker_panel.votes(PanelVote::Approve) as u8,
+        cancellations: checker_panel.votes(PanelVote::Cancel) as u8,
+        decision: Some(vote),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Pairs every panel member paid on settlement with its deal token account and its share of `checker_fee`.
+/// The remaining accounts hold `[checker, checker_deal_ta]` for each of them, in panel order.
+/// When nobody on the panel voted, e.g. the client finished on its own, they hold `[client, client_deal_ta]`
+/// and the fee goes back to the client
+pub fn panel_fee_shares<'a, 'info>(
+    checker_panel: &CheckerPanel,
+    checker_fee: u64,
+    client: &Pubkey,
+    remaining_accounts: &'a [AccountInfo<'info>],
+) -> Result<Vec<(&'a AccountInfo<'info>, &'a AccountInfo<'info>, u64)>> {
+    let mut recipients = checker_panel.fee_recipients();
+    if recipients.is_empty() {
+        recipients.push(*client);
+    }
+    if remaining_accounts.len() != recipients.len() * 2 {
+        return Err(InvalidAccount::CheckerDealTokenAccount.into());
+    }
+    let shares = accounting::split_even(checker_fee, recipients.len())?;
+    recipients
+        .iter()
+        .zip(shares)
+        .zip(remaining_accounts.chunks(2))
+        .map(|((recipient, share), accounts)| {
+            if !cmp_pubkeys(accounts[0].key, recipient) {
+                return Err(InvalidAccount::Checker.into());
+            }
+            Ok((&accounts[0], &accounts[1], share))
+        })
+        .collect()
+}
+
+/// Splits `amount` into `(client_part, executor_part)` where the client gets `client_bps` basis points
+pub fn split_bps(amount: u64, client_bps: u16) -> Result<(u64, u64)> {
+    if client_bps > BASIS_POINTS {
+        return Err(ErrorCodes::InvalidBasisPoints.into());
+    }
+    let client_part = (amount as u128 * client_bps as u128 / BASIS_POINTS as u128) as u64;
+    Ok((client_part, amount - client_part))
+}
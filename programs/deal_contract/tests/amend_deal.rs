@@ -0,0 +1,190 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use deal_contract::{
+    errors::{ErrorCodes, InvalidAccount},
+    AmendDealArgs, DeadlineAmendment,
+};
+use deal_contract_client::{CancelBuilder, FinishBuilder, PartiallyPayBuilder};
+use solana_sdk::{instruction::AccountMeta, signature::Keypair, signer::Signer};
+
+fn amend(deadline: DeadlineAmendment, amount: Option<u64>) -> AmendDealArgs {
+    AmendDealArgs { deadline, amount }
+}
+
+#[tokio::test]
+async fn deadline_is_extended_and_removed() {
+    let (mut env, p) = Env::start().await;
+    let now = env.now().await;
+
+    let initialize = env.initialize(&p, 100_000).deadline(now + 3_600);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let amend_deal = env.amend_deal_ix(&deal, amend(DeadlineAmendment::Set(now - 1), None));
+    assert_error(
+        env.send(&[amend_deal], &[&p.client, &p.executor]).await,
+        ErrorCodes::DeadlineExpired,
+    );
+
+    let amend_deal = env.amend_deal_ix(&deal, amend(DeadlineAmendment::Set(now + 7_200), None));
+    env.send(&[amend_deal], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(deal.deadline_ts, Some(now + 7_200));
+    assert_eq!(deal.amendments, 1);
+
+    // the original deadline has passed, the extended one has not
+    env.warp_by(3_601).await;
+    let cancel = CancelBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    assert_error(env.send(&[cancel], &[&p.client]).await, ErrorCodes::DeadlineNotExpired);
+
+    let amend_deal = env.amend_deal_ix(&deal, amend(DeadlineAmendment::Remove, None));
+    env.send(&[amend_deal], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(deal.deadline_ts, None);
+    assert_eq!(deal.amendments, 2);
+}
+
+#[tokio::test]
+async fn amount_is_raised_and_lowered() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000).checker(p.checker.pubkey(), 2_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    // the checker has to agree as well
+    let mut amend_deal = env.amend_deal_ix(&deal, amend(DeadlineAmendment::Keep, Some(150_000)));
+    replace_account(&mut amend_deal, &p.checker.pubkey(), &p.outsider.pubkey());
+    assert_error(
+        env.send(&[amend_deal], &[&p.client, &p.executor, &p.outsider]).await,
+        InvalidAccount::Checker,
+    );
+    let mut amend_deal = env.amend_deal_ix(&deal, amend(DeadlineAmendment::Keep, Some(150_000)));
+    for meta in amend_deal.accounts.iter_mut() {
+        if meta.pubkey == p.checker.pubkey() {
+            meta.is_signer = false;
+        }
+    }
+    assert_error(
+        env.send(&[amend_deal], &[&p.client, &p.executor]).await,
+        ErrorCode::AccountNotSigner,
+    );
+
+    let amend_deal = env.amend_deal_ix(&deal, amend(DeadlineAmendment::Keep, Some(150_000)));
+    env.send(&[amend_deal], &[&p.client, &p.executor, &p.checker]).await.unwrap();
+    assert_eq!(env.balance(&deal_mint, &deal_state).await, 152_000);
+    assert_eq!(env.deal(&deal_state).await.amount, 150_000);
+
+    let release = PartiallyPayBuilder::new(&deal, 60_000).instruction();
+    env.send(&[release], &[&p.client]).await.unwrap();
+
+    let amend_deal = env.amend_deal_ix(&deal, amend(DeadlineAmendment::Keep, Some(59_999)));
+    assert_error(
+        env.send(&[amend_deal], &[&p.client, &p.executor, &p.checker]).await,
+        ErrorCodes::AmountBelowPaid,
+    );
+    let amend_deal = env.amend_deal_ix(&deal, amend(DeadlineAmendment::Keep, Some(80_000)));
+    env.send(&[amend_deal], &[&p.client, &p.executor, &p.checker]).await.unwrap();
+    assert_eq!(
+        env.balance(&deal_mint, &p.client.pubkey()).await,
+        BALANCE - SERVICE_FEE - 80_000 - 2_000
+    );
+
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(deal.amendments, 2);
+    let finish = FinishBuilder::new(&deal, &env.config, p.checker.pubkey()).instruction();
+    env.send(&[finish], &[&p.checker]).await.unwrap();
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 80_000);
+}
+
+#[tokio::test]
+async fn milestone_deals_keep_their_amount() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000).milestones(vec![milestone(100_000, None)]);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let amend_deal = env.amend_deal_ix(&deal, amend(DeadlineAmendment::Keep, Some(120_000)));
+    assert_error(
+        env.send(&[amend_deal], &[&p.client, &p.executor]).await,
+        ErrorCodes::DealWithMilestones,
+    );
+    let amend_deal = env.amend_deal_ix(&deal, amend(DeadlineAmendment::Keep, Some(0)));
+    assert_error(
+        env.send(&[amend_deal], &[&p.client, &p.executor]).await,
+        ErrorCodes::AmountTooLow,
+    );
+}
+
+#[tokio::test]
+async fn native_deal_amount_is_amended() {
+    let (mut env, p) = Env::start().await;
+    let native = env.mints.native;
+
+    let initialize = env.initialize_in(&p, native, 1_000_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let before = env.lamports(&deal_state).await;
+    let amend_deal = env.amend_deal_ix(&deal, amend(DeadlineAmendment::Keep, Some(1_500_000)));
+    env.send(&[amend_deal], &[&p.client, &p.executor]).await.unwrap();
+    assert_eq!(env.lamports(&deal_state).await, before + 500_000);
+
+    let amend_deal = env.amend_deal_ix(&deal, amend(DeadlineAmendment::Keep, Some(400_000)));
+    env.send(&[amend_deal], &[&p.client, &p.executor]).await.unwrap();
+    assert_eq!(env.lamports(&deal_state).await, before - 600_000);
+}
+
+#[tokio::test]
+async fn panel_deal_is_amended_by_its_threshold() {
+    let (mut env, p) = Env::start().await;
+    let now = env.now().await;
+    let third = Keypair::new();
+
+    let initialize = env.initialize(&p, 100_000).deadline(now + 3_600).checker_panel(
+        vec![p.checker.pubkey(), p.outsider.pubkey(), third.pubkey()],
+        2,
+        3_000,
+    );
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let amend_deal = |signers: &[&Keypair]| {
+        let mut amend_deal =
+            env.amend_deal_ix(&deal, amend(DeadlineAmendment::Set(now + 7_200), None));
+        amend_deal.accounts.extend(
+            signers.iter().map(|signer| AccountMeta::new_readonly(signer.pubkey(), true)),
+        );
+        amend_deal
+    };
+    // a member signing twice still counts once
+    let below_threshold = amend_deal(&[&p.checker, &p.checker]);
+    let outsider_signed = amend_deal(&[&p.checker, &p.client]);
+    let threshold_signed = amend_deal(&[&p.checker, &third]);
+
+    assert_error(
+        env.send(&[below_threshold], &[&p.client, &p.executor, &p.checker]).await,
+        ErrorCode::AccountNotSigner,
+    );
+    assert_error(
+        env.send(&[outsider_signed], &[&p.client, &p.executor, &p.checker]).await,
+        ErrorCode::AccountNotSigner,
+    );
+    env.send(&[threshold_signed], &[&p.client, &p.executor, &p.checker, &third])
+        .await
+        .unwrap();
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(deal.deadline_ts, Some(now + 7_200));
+    assert_eq!(deal.amendments, 1);
+}
@@ -0,0 +1,383 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use deal_contract::{
+    errors::{ErrorCodes, InvalidAccount},
+    state::{BondForfeiture, DealReceipt},
+    BondSide,
+};
+use deal_contract_client::{pda, CancelBuilder, FinishBuilder};
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn finish_returns_both_bonds() {
+    let (mut env, p) = Env::start().await;
+    let bond_mint = env.mints.bond;
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .client_bond(bond_mint, 3_000)
+        .executor_bond(bond_mint, 4_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let client_vault = pda::client_bond_vault(&deal_state, &bond_mint.address);
+    let executor_vault = pda::executor_bond_vault(&deal_state, &bond_mint.address);
+    assert_eq!(env.token_balance(&client_vault).await, 3_000);
+    assert_eq!(env.token_balance(&executor_vault).await, 4_000);
+    assert_eq!(env.balance(&bond_mint, &p.client.pubkey()).await, BALANCE - 3_000);
+    assert_eq!(env.balance(&bond_mint, &p.executor.pubkey()).await, BALANCE - 4_000);
+
+    let deal = env.deal(&deal_state).await;
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[finish], &[&p.client]).await.unwrap();
+
+    assert_eq!(env.balance(&bond_mint, &p.client.pubkey()).await, BALANCE);
+    assert_eq!(env.balance(&bond_mint, &p.executor.pubkey()).await, BALANCE);
+    assert!(!env.exists(&client_vault).await);
+    assert!(!env.exists(&executor_vault).await);
+}
+
+#[tokio::test]
+async fn bonds_in_different_mints_are_kept_apart() {
+    let (mut env, p) = Env::start().await;
+    let bond_mint = env.mints.bond;
+    let token_2022_mint = env.mints.token_2022;
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .client_bond(bond_mint, 3_000)
+        .executor_bond(token_2022_mint, 4_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let client_vault = pda::client_bond_vault(&deal_state, &bond_mint.address);
+    let executor_vault = pda::executor_bond_vault(&deal_state, &token_2022_mint.address);
+    assert_eq!(env.token_balance(&client_vault).await, 3_000);
+    assert_eq!(env.token_balance(&executor_vault).await, 4_000);
+
+    let deal = env.deal(&deal_state).await;
+    let cancel = CancelBuilder::new(&deal, &env.config, p.executor.pubkey())
+        .mint(token_2022_mint)
+        .instruction();
+    assert_error(env.send(&[cancel], &[&p.executor]).await, ErrorCodes::DealWithClientBond);
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey())
+        .mint(token_2022_mint)
+        .instruction();
+    env.send(&[finish], &[&p.client]).await.unwrap();
+
+    assert_eq!(env.balance(&bond_mint, &p.client.pubkey()).await, BALANCE);
+    assert_eq!(env.balance(&token_2022_mint, &p.executor.pubkey()).await, BALANCE);
+    assert!(!env.exists(&client_vault).await);
+    assert!(!env.exists(&executor_vault).await);
+}
+
+#[tokio::test]
+async fn client_cancel_returns_client_bond() {
+    let (mut env, p) = Env::start().await;
+    let bond_mint = env.mints.bond;
+
+    let initialize = env.initialize(&p, 100_000).client_bond(bond_mint, 3_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let cancel = CancelBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[cancel], &[&p.client]).await.unwrap();
+
+    assert_eq!(env.balance(&bond_mint, &p.client.pubkey()).await, BALANCE);
+    assert!(!env.exists(&pda::client_bond_vault(&deal_state, &bond_mint.address)).await);
+}
+
+#[tokio::test]
+async fn client_cancel_with_executor_bond_fails() {
+    let (mut env, p) = Env::start().await;
+    let bond_mint = env.mints.bond;
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .client_bond(bond_mint, 3_000)
+        .executor_bond(bond_mint, 4_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let cancel = CancelBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    assert_error(env.send(&[cancel], &[&p.client]).await, ErrorCodes::DealWithExecutorBond);
+}
+
+#[tokio::test]
+async fn executor_cancel_with_client_bond_fails() {
+    let (mut env, p) = Env::start().await;
+    let bond_mint = env.mints.bond;
+
+    let initialize = env.initialize(&p, 100_000).client_bond(bond_mint, 3_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let cancel = CancelBuilder::new(&deal, &env.config, p.executor.pubkey()).instruction();
+    assert_error(env.send(&[cancel], &[&p.executor]).await, ErrorCodes::DealWithClientBond);
+}
+
+#[tokio::test]
+async fn checker_cancel_returns_both_bonds() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let bond_mint = env.mints.bond;
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .checker(p.checker.pubkey(), 2_000)
+        .client_bond(bond_mint, 3_000)
+        .executor_bond(bond_mint, 4_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let cancel = CancelBuilder::new(&deal, &env.config, p.checker.pubkey()).instruction();
+    env.send(&[cancel], &[&p.checker]).await.unwrap();
+
+    assert_eq!(env.balance(&bond_mint, &p.client.pubkey()).await, BALANCE);
+    assert_eq!(env.balance(&bond_mint, &p.executor.pubkey()).await, BALANCE);
+    assert_eq!(
+        env.balance(&deal_mint, &p.client.pubkey()).await,
+        BALANCE - SERVICE_FEE - 2_000
+    );
+    assert!(!env.exists(&deal_state).await);
+}
+
+#[tokio::test]
+async fn mutual_cancel_returns_bonds_before_the_deadline() {
+    let (mut env, p) = Env::start().await;
+    let bond_mint = env.mints.bond;
+    let deal_mint = env.mints.deal;
+    let now = env.now().await;
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .advance_payment(20_000)
+        .deadline(now + 3_600)
+        .client_bond(bond_mint, 3_000)
+        .executor_bond(bond_mint, 4_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    // a single party still needs the other one
+    let mut cancel =
+        CancelBuilder::new(&deal, &env.config, p.client.pubkey()).mutual().instruction();
+    for meta in cancel.accounts.iter_mut() {
+        if meta.pubkey == p.executor.pubkey() {
+            meta.is_signer = false;
+        }
+    }
+    assert_error(env.send(&[cancel], &[&p.client]).await, ErrorCode::AccountNotSigner);
+
+    let cancel = CancelBuilder::new(&deal, &env.config, p.executor.pubkey())
+        .mutual()
+        .with_receipt()
+        .instruction();
+    env.send(&[cancel], &[&p.client, &p.executor]).await.unwrap();
+
+    assert_eq!(env.balance(&bond_mint, &p.client.pubkey()).await, BALANCE);
+    assert_eq!(env.balance(&bond_mint, &p.executor.pubkey()).await, BALANCE);
+    assert_eq!(
+        env.balance(&deal_mint, &p.client.pubkey()).await,
+        BALANCE - SERVICE_FEE - 20_000
+    );
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 20_000);
+    assert!(!env.exists(&deal_state).await);
+    assert!(!env.exists(&pda::client_bond_vault(&deal_state, &bond_mint.address)).await);
+    assert!(!env.exists(&pda::executor_bond_vault(&deal_state, &bond_mint.address)).await);
+}
+
+#[tokio::test]
+async fn mutual_cancel_is_for_the_parties_only() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000).checker(p.checker.pubkey(), 2_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let cancel = CancelBuilder::new(&deal, &env.config, p.checker.pubkey())
+        .mutual()
+        .instruction();
+    assert_error(
+        env.send(&[cancel], &[&p.client, &p.executor, &p.checker]).await,
+        InvalidAccount::Initializer,
+    );
+}
+
+#[tokio::test]
+async fn mutual_cancel_refunds_the_checker_fee() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000).checker(p.checker.pubkey(), 2_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(
+        env.balance(&deal_mint, &p.client.pubkey()).await,
+        BALANCE - SERVICE_FEE - 102_000
+    );
+
+    let cancel = CancelBuilder::new(&deal, &env.config, p.client.pubkey())
+        .mutual()
+        .with_receipt()
+        .instruction();
+    env.send(&[cancel], &[&p.client, &p.executor]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.client.pubkey()).await, BALANCE - SERVICE_FEE);
+    assert_eq!(env.balance(&deal_mint, &p.checker.pubkey()).await, BALANCE);
+    let receipt: DealReceipt = env.account(&pda::deal_receipt(&deal_state).0).await;
+    assert_eq!(receipt.client_refunded, 102_000);
+    assert_eq!(receipt.checker_fee, 0);
+}
+
+#[tokio::test]
+async fn executor_bond_is_forfeited_after_the_deadline() {
+    let (mut env, p) = Env::start().await;
+    let bond_mint = env.mints.bond;
+    let now = env.now().await;
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .deadline(now + 3_600)
+        .executor_bond(bond_mint, 4_000)
+        .forfeiture(BondForfeiture {
+            executor_on_deadline: true,
+            checker_slash: false,
+        });
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    env.warp_by(3_601).await;
+    let cancel = CancelBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[cancel], &[&p.client]).await.unwrap();
+
+    assert_eq!(env.balance(&bond_mint, &p.client.pubkey()).await, BALANCE + 4_000);
+    assert_eq!(env.balance(&bond_mint, &p.executor.pubkey()).await, BALANCE - 4_000);
+    assert!(!env.exists(&deal_state).await);
+}
+
+#[tokio::test]
+async fn executor_bond_is_returned_when_delivered_before_the_deadline() {
+    let (mut env, p) = Env::start().await;
+    let bond_mint = env.mints.bond;
+    let now = env.now().await;
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .deadline(now + 3_600)
+        .checker(p.checker.pubkey(), 2_000)
+        .executor_bond(bond_mint, 4_000)
+        .forfeiture(BondForfeiture {
+            executor_on_deadline: true,
+            checker_slash: false,
+        });
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let mark = env.mark_delivered_ix(&deal);
+    env.send(&[mark], &[&p.executor]).await.unwrap();
+
+    env.warp_by(3_601).await;
+    let deal = env.deal(&deal_state).await;
+    let cancel = CancelBuilder::new(&deal, &env.config, p.checker.pubkey()).instruction();
+    env.send(&[cancel], &[&p.checker]).await.unwrap();
+
+    assert_eq!(env.balance(&bond_mint, &p.client.pubkey()).await, BALANCE);
+    assert_eq!(env.balance(&bond_mint, &p.executor.pubkey()).await, BALANCE);
+    assert!(!env.exists(&deal_state).await);
+}
+
+#[tokio::test]
+async fn checker_slashes_a_bond() {
+    let (mut env, p) = Env::start().await;
+    let bond_mint = env.mints.bond;
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .checker(p.checker.pubkey(), 2_000)
+        .client_bond(bond_mint, 3_000)
+        .executor_bond(bond_mint, 4_000)
+        .forfeiture(BondForfeiture {
+            executor_on_deadline: false,
+            checker_slash: true,
+        });
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let mut slash = env.slash_bond_ix(&deal, &p.outsider.pubkey(), BondSide::Client);
+    replace_account(&mut slash, &p.checker.pubkey(), &p.outsider.pubkey());
+    assert_error(env.send(&[slash], &[&p.outsider]).await, InvalidAccount::Checker);
+
+    let slash = env.slash_bond_ix(&deal, &p.checker.pubkey(), BondSide::Client);
+    env.send(&[slash], &[&p.checker]).await.unwrap();
+    assert_eq!(
+        env.balance(&bond_mint, &p.executor.pubkey()).await,
+        BALANCE - 4_000 + 3_000
+    );
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(deal.client_bond().unwrap().amount, 0);
+
+    let slash = env.slash_bond_ix(&deal, &p.checker.pubkey(), BondSide::Client);
+    assert_error(env.send(&[slash], &[&p.checker]).await, ErrorCodes::BondAlreadyForfeited);
+
+    // the settlement returns only what is left of the bonds
+    let finish = FinishBuilder::new(&deal, &env.config, p.checker.pubkey()).instruction();
+    env.send(&[finish], &[&p.checker]).await.unwrap();
+    assert_eq!(env.balance(&bond_mint, &p.client.pubkey()).await, BALANCE - 3_000);
+    assert_eq!(env.balance(&bond_mint, &p.executor.pubkey()).await, BALANCE + 3_000);
+}
+
+#[tokio::test]
+async fn forfeiture_rules_are_validated() {
+    let (mut env, p) = Env::start().await;
+    let bond_mint = env.mints.bond;
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .executor_bond(bond_mint, 4_000)
+        .forfeiture(BondForfeiture {
+            executor_on_deadline: true,
+            checker_slash: false,
+        })
+        .instruction();
+    assert_error(
+        env.send(&[initialize], &[&p.client, &p.executor]).await,
+        ErrorCodes::InvalidForfeitureRules,
+    );
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .checker(p.checker.pubkey(), 2_000)
+        .client_bond(bond_mint, 3_000)
+        .executor_bond(bond_mint, 4_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let slash = env.slash_bond_ix(&deal, &p.checker.pubkey(), BondSide::Executor);
+    assert_error(env.send(&[slash], &[&p.checker]).await, ErrorCodes::SlashNotAllowed);
+}
@@ -0,0 +1,187 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use deal_contract::errors::{ErrorCodes, InvalidAccount};
+use deal_contract_client::{CheckerUpdate, FinishBuilder, UpdateCheckerBuilder};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+#[tokio::test]
+async fn checker_resigns_and_refunds_the_fee() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000).checker(p.checker.pubkey(), 2_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+    let deal = env.deal(&deal_state).await;
+    let client_balance = env.balance(&deal_mint, &p.client.pubkey()).await;
+
+    // only the checker itself resigns
+    let resign =
+        UpdateCheckerBuilder::new(&deal, &env.config, p.client.pubkey(), CheckerUpdate::Resign)
+            .instruction();
+    assert_error(env.send(&[resign], &[&p.client]).await, InvalidAccount::Initializer);
+
+    let resign =
+        UpdateCheckerBuilder::new(&deal, &env.config, p.checker.pubkey(), CheckerUpdate::Resign)
+            .instruction();
+    env.send(std::slice::from_ref(&resign), &[&p.checker]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+    assert!(deal.checker.is_none());
+    assert_eq!(env.balance(&deal_mint, &p.client.pubkey()).await, client_balance + 2_000);
+    assert_eq!(env.balance(&deal_mint, &deal_state).await, 100_000);
+
+    assert_error(
+        env.send(&[resign], &[&p.checker]).await,
+        ErrorCodes::DealStateNotWithChecker,
+    );
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[finish], &[&p.client]).await.unwrap();
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 100_000);
+    assert_eq!(env.balance(&deal_mint, &p.checker.pubkey()).await, BALANCE);
+}
+
+#[tokio::test]
+async fn parties_replace_the_checker() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let third = Keypair::new();
+
+    let initialize = env.initialize(&p, 100_000).checker(p.checker.pubkey(), 2_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+    let deal = env.deal(&deal_state).await;
+    let client_balance = env.balance(&deal_mint, &p.client.pubkey()).await;
+
+    let config = env.config.clone();
+    let replace = |deal, initializer: &Keypair, new_checker: &Keypair, checker_fee| {
+        UpdateCheckerBuilder::new(
+            deal,
+            &config,
+            initializer.pubkey(),
+            CheckerUpdate::Replace { checker_fee },
+        )
+        .new_checker(new_checker.pubkey())
+        .instruction()
+    };
+
+    // the executor can't check its own deal
+    let update = replace(&deal, &p.client, &p.executor, 2_000);
+    assert_error(
+        env.send(&[update], &[&p.client, &p.executor]).await,
+        InvalidAccount::Checker,
+    );
+
+    // both parties sign, the current checker does not
+    let mut update = replace(&deal, &p.client, &p.outsider, 5_000);
+    update
+        .accounts
+        .iter_mut()
+        .find(|meta| meta.pubkey == p.executor.pubkey())
+        .unwrap()
+        .is_signer = false;
+    assert_error(
+        env.send(&[update], &[&p.client, &p.outsider]).await,
+        ErrorCode::AccountNotSigner,
+    );
+
+    // a higher fee is deposited by the client
+    let update = replace(&deal, &p.client, &p.outsider, 5_000);
+    env.send(&[update], &[&p.client, &p.executor, &p.outsider]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(deal.with_checker().unwrap().checker_key, p.outsider.pubkey());
+    assert_eq!(env.balance(&deal_mint, &p.client.pubkey()).await, client_balance - 3_000);
+    assert_eq!(env.balance(&deal_mint, &deal_state).await, 105_000);
+
+    // the service account can't raise the fee on behalf of the client
+    let update = replace(&deal, &p.service, &third, 6_000);
+    assert_error(
+        env.send(&[update], &[&p.service, &third]).await,
+        ErrorCode::AccountNotSigner,
+    );
+
+    // a lower one is refunded
+    let update = replace(&deal, &p.service, &third, 1_000);
+    env.send(&[update], &[&p.service, &third]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(env.balance(&deal_mint, &p.client.pubkey()).await, client_balance + 1_000);
+    assert_eq!(env.balance(&deal_mint, &deal_state).await, 101_000);
+
+    let finish = FinishBuilder::new(&deal, &env.config, third.pubkey())
+        .payer(p.client.pubkey())
+        .instruction();
+    env.send(&[finish], &[&third, &p.client]).await.unwrap();
+    assert_eq!(env.balance(&deal_mint, &third.pubkey()).await, 1_000);
+    assert_eq!(env.balance(&deal_mint, &p.checker.pubkey()).await, BALANCE);
+    assert_eq!(env.balance(&deal_mint, &p.outsider.pubkey()).await, BALANCE);
+}
+
+#[tokio::test]
+async fn checker_added_to_a_deal_without_one() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+    let client_balance = env.balance(&deal_mint, &p.client.pubkey()).await;
+
+    let resign =
+        UpdateCheckerBuilder::new(&deal, &env.config, p.checker.pubkey(), CheckerUpdate::Resign)
+            .instruction();
+    assert_error(
+        env.send(&[resign], &[&p.checker]).await,
+        ErrorCodes::DealStateNotWithChecker,
+    );
+    let replace = UpdateCheckerBuilder::new(
+        &deal,
+        &env.config,
+        p.client.pubkey(),
+        CheckerUpdate::Replace { checker_fee: 1_500 },
+    )
+    .new_checker(p.checker.pubkey())
+    .instruction();
+    assert_error(
+        env.send(&[replace], &[&p.client, &p.executor, &p.checker]).await,
+        ErrorCodes::DealStateNotWithChecker,
+    );
+
+    // the new checker accepts the role by signing
+    let add = UpdateCheckerBuilder::new(
+        &deal,
+        &env.config,
+        p.client.pubkey(),
+        CheckerUpdate::Add { checker_fee: 1_500 },
+    )
+    .new_checker(p.checker.pubkey())
+    .instruction();
+    let mut unsigned = add.clone();
+    unsigned
+        .accounts
+        .iter_mut()
+        .find(|meta| meta.pubkey == p.checker.pubkey())
+        .unwrap()
+        .is_signer = false;
+    assert_error(
+        env.send(&[unsigned], &[&p.client, &p.executor]).await,
+        ErrorCode::AccountNotSigner,
+    );
+
+    env.send(&[add], &[&p.client, &p.executor, &p.checker]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(deal.checker_fee(), 1_500);
+    assert_eq!(env.balance(&deal_mint, &p.client.pubkey()).await, client_balance - 1_500);
+    assert_eq!(env.balance(&deal_mint, &deal_state).await, 101_500);
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.checker.pubkey()).instruction();
+    env.send(&[finish], &[&p.checker]).await.unwrap();
+    assert_eq!(env.balance(&deal_mint, &p.checker.pubkey()).await, BALANCE + 1_500);
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 100_000);
+}
@@ -0,0 +1,1224 @@
+//! In-process harness for the integration tests: a bank with the program loaded as a builtin,
+//! pre-funded parties and mints, and an injected `ProgramConfig`. Nothing touches the network.
+
+#![allow(dead_code)]
+
+use std::sync::Once;
+
+use anchor_lang::{
+    prelude::{AccountInfo, Clock, Pubkey},
+    solana_program::{
+        entrypoint::ProgramResult, instruction::Instruction, program_option::COption,
+        program_pack::Pack, program_stubs, system_program,
+    },
+    AccountDeserialize, AccountSerialize, AnchorSerialize, Discriminator, InstructionData,
+    ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token,
+    token::spl_token,
+    token_interface::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::{TransferFee, TransferFeeConfig},
+            ExtensionType, StateWithExtensions, StateWithExtensionsMut,
+        },
+    },
+};
+use deal_contract::{
+    state::{
+        Bond, Checker, ConfigArgs, DealState, Dispute, FeeSchedule, FeeScheduleArgs, HolderTier,
+        LegacyDealState, MilestoneArgs, PanelVote, ProgramConfig, CONFIG_VERSION,
+        MAX_HOLDER_TIERS,
+    },
+    AmendDealArgs, BondSide, CreateOfferArgs, OpenDisputeArgs, ResolveDisputeArgs,
+    SettleMilestoneArgs, SubmitEvidenceArgs,
+};
+use deal_contract_client::{pda, InitializeBuilder, Mints, TokenMint};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account as SolanaAccount,
+    instruction::InstructionError,
+    rent::Rent,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+};
+
+pub const DECIMALS: u8 = 6;
+pub const LAMPORTS: u64 = 100_000_000_000;
+pub const BALANCE: u64 = 1_000_000_000;
+pub const HOLDER_MODE_AMOUNT: u64 = 5_000;
+pub const SERVICE_FEE: u64 = 1_000;
+pub const MAX_ADVANCE_BPS: u16 = 5_000;
+pub const REVIEW_WINDOW: i64 = 3_600;
+pub const STAKE_COOLDOWN: i64 = 86_400;
+
+/// Anchor entrypoints borrow the accounts for `'info`, the builtin processor hands out a shorter slice
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    deal_contract::entry(program_id, accounts, data)
+}
+
+/// The builtin CPI stub wants an account info for every account of the instruction, executable ones
+/// included, while the runtime skips them. `init_ata` doesn't pass the system program to the
+/// associated token program, so it is added here.
+struct SyscallStubs {
+    inner: Box<dyn program_stubs::SyscallStubs>,
+    system_program: SolanaAccount,
+}
+
+impl program_stubs::SyscallStubs for SyscallStubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let missing_system_program =
+            instruction.accounts.iter().any(|meta| meta.pubkey == system_program::ID)
+                && !account_infos.iter().any(|info| *info.key == system_program::ID);
+        if !missing_system_program {
+            return self.inner.sol_invoke_signed(instruction, account_infos, signers_seeds);
+        }
+        let mut account_infos = account_infos.to_vec();
+        account_infos.push(AccountInfo::new(
+            &system_program::ID,
+            false,
+            false,
+            Box::leak(Box::new(self.system_program.lamports)),
+            Box::leak(self.system_program.data.clone().into_boxed_slice()),
+            Box::leak(Box::new(self.system_program.owner)),
+            true,
+            self.system_program.rent_epoch,
+        ));
+        self.inner.sol_invoke_signed(instruction, &account_infos, signers_seeds)
+    }
+
+    fn sol_log(&self, message: &str) {
+        self.inner.sol_log(message)
+    }
+    fn sol_log_compute_units(&self) {
+        self.inner.sol_log_compute_units()
+    }
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_clock_sysvar(var_addr)
+    }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_fees_sysvar(var_addr)
+    }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_rent_sysvar(var_addr)
+    }
+    unsafe fn sol_memcpy(&self, dst: *mut u8, src: *const u8, n: usize) {
+        self.inner.sol_memcpy(dst, src, n)
+    }
+    unsafe fn sol_memmove(&self, dst: *mut u8, src: *const u8, n: usize) {
+        self.inner.sol_memmove(dst, src, n)
+    }
+    unsafe fn sol_memcmp(&self, s1: *const u8, s2: *const u8, n: usize, result: *mut i32) {
+        self.inner.sol_memcmp(s1, s2, n, result)
+    }
+    unsafe fn sol_memset(&self, s: *mut u8, c: u8, n: usize) {
+        self.inner.sol_memset(s, c, n)
+    }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.inner.sol_get_return_data()
+    }
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.inner.sol_set_return_data(data)
+    }
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        self.inner.sol_log_data(fields)
+    }
+    fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<Instruction> {
+        self.inner.sol_get_processed_sibling_instruction(index)
+    }
+    fn sol_get_stack_height(&self) -> u64 {
+        self.inner.sol_get_stack_height()
+    }
+}
+
+/// Wraps the stubs installed by the first `ProgramTest`. Every test goes through here before
+/// sending a transaction, so none runs while the placeholder is set.
+fn install_syscall_stubs(system_program: SolanaAccount) {
+    struct Placeholder;
+    impl program_stubs::SyscallStubs for Placeholder {}
+
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        let inner = program_stubs::set_syscall_stubs(Box::new(Placeholder));
+        program_stubs::set_syscall_stubs(Box::new(SyscallStubs {
+            inner,
+            system_program,
+        }));
+    });
+}
+
+/// Every party holds lamports and `BALANCE` of each test mint
+pub struct Parties {
+    pub client: Keypair,
+    pub executor: Keypair,
+    pub checker: Keypair,
+    /// `ProgramConfig::service_account` and `authority`
+    pub service: Keypair,
+    pub outsider: Keypair,
+}
+
+pub struct TestMints {
+    /// Deal mint of the SPL deals, also the config service fee mint
+    pub deal: TokenMint,
+    pub bond: TokenMint,
+    pub holder: TokenMint,
+    pub token_2022: TokenMint,
+    pub native: TokenMint,
+}
+
+pub struct Env {
+    pub ctx: ProgramTestContext,
+    pub config: ProgramConfig,
+    pub mints: TestMints,
+    pub registry: Mints,
+    next_deal_id: u128,
+}
+
+fn mint_account(token_program: &Pubkey, authority: &Pubkey) -> SolanaAccount {
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint::pack(
+        spl_token::state::Mint {
+            mint_authority: COption::Some(*authority),
+            supply: u64::MAX / 2,
+            decimals: DECIMALS,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        },
+        &mut data,
+    )
+    .unwrap();
+    SolanaAccount {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: *token_program,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn program_account<T: AccountSerialize>(account: &T, space: usize) -> SolanaAccount {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    data.resize(space, 0);
+    SolanaAccount {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: deal_contract::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn token_account(mint: &TokenMint, owner: &Pubkey, amount: u64) -> SolanaAccount {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account::pack(
+        spl_token::state::Account {
+            mint: mint.address,
+            owner: *owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        },
+        &mut data,
+    )
+    .unwrap();
+    SolanaAccount {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: mint.token_program,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+impl Env {
+    pub async fn start() -> (Self, Parties) {
+        let mut program_test =
+            ProgramTest::new("deal_contract", deal_contract::ID, processor!(process_instruction));
+
+        let parties = Parties {
+            client: Keypair::new(),
+            executor: Keypair::new(),
+            checker: Keypair::new(),
+            service: Keypair::new(),
+            outsider: Keypair::new(),
+        };
+        let service_fee_owner = Pubkey::new_unique();
+        let mint_authority = Pubkey::new_unique();
+
+        let mints = TestMints {
+            deal: TokenMint::spl_token(Pubkey::new_unique()),
+            bond: TokenMint::spl_token(Pubkey::new_unique()),
+            holder: TokenMint::spl_token(Pubkey::new_unique()),
+            token_2022: TokenMint::token_2022(Pubkey::new_unique()),
+            native: TokenMint::native(),
+        };
+        for mint in [&mints.deal, &mints.bond, &mints.holder, &mints.token_2022] {
+            program_test
+                .add_account(mint.address, mint_account(&mint.token_program, &mint_authority));
+        }
+
+        for party in [
+            &parties.client,
+            &parties.executor,
+            &parties.checker,
+            &parties.service,
+            &parties.outsider,
+        ] {
+            let owner = party.pubkey();
+            program_test.add_account(owner, SolanaAccount::new(LAMPORTS, 0, &system_program::ID));
+            for mint in [&mints.deal, &mints.bond, &mints.holder, &mints.token_2022] {
+                program_test
+                    .add_account(mint.token_account(&owner), token_account(mint, &owner, BALANCE));
+            }
+        }
+        program_test
+            .add_account(service_fee_owner, SolanaAccount::new(LAMPORTS, 0, &system_program::ID));
+
+        // `init_config` needs the upgrade authority of a deployed program, so the config is injected
+        let (config_pk, config_bump) = pda::config();
+        let config = ProgramConfig {
+            authority: parties.service.pubkey(),
+            service_account: parties.service.pubkey(),
+            service_fee_owner,
+            holder_mint: mints.holder.address,
+            bump: [config_bump],
+            version: CONFIG_VERSION,
+            holder_tiers: holder_tiers(&[(HOLDER_MODE_AMOUNT, 10_000)]),
+            holder_stake_cooldown: STAKE_COOLDOWN,
+            max_advance_bps: MAX_ADVANCE_BPS,
+            review_window: REVIEW_WINDOW,
+            paused: 0,
+        };
+        program_test.add_account(config_pk, program_account(&config, ProgramConfig::SPACE));
+
+        // a flat `SERVICE_FEE` in every test mint, `service_fee.rs` sets its own schedules
+        for mint in [&mints.deal, &mints.bond, &mints.token_2022, &mints.native] {
+            let (fee_schedule_pk, fee_schedule_bump) = pda::fee_schedule(&mint.address);
+            let fee_schedule = FeeSchedule {
+                mint: mint.address,
+                fee_bps: 0,
+                min_fee: SERVICE_FEE,
+                max_fee: SERVICE_FEE,
+                bump: [fee_schedule_bump],
+            };
+            program_test
+                .add_account(fee_schedule_pk, program_account(&fee_schedule, FeeSchedule::SPACE));
+        }
+
+        let mut registry = Mints::default();
+        registry.add(mints.token_2022);
+
+        let mut ctx = program_test.start_with_context().await;
+        let system_program =
+            ctx.banks_client.get_account(system_program::ID).await.unwrap().unwrap();
+        install_syscall_stubs(system_program);
+
+        let env = Self {
+            ctx,
+            config,
+            mints,
+            registry,
+            next_deal_id: 1,
+        };
+        (env, parties)
+    }
+
+    pub fn new_deal_id(&mut self) -> [u8; 16] {
+        self.next_deal_id += 1;
+        self.next_deal_id.to_le_bytes()
+    }
+
+    /// An SPL deal paying `SERVICE_FEE` in the deal mint
+    pub fn initialize(&mut self, parties: &Parties, amount: u64) -> InitializeBuilder {
+        self.initialize_in(parties, self.mints.deal, amount)
+    }
+
+    pub fn initialize_in(
+        &mut self,
+        parties: &Parties,
+        mint: TokenMint,
+        amount: u64,
+    ) -> InitializeBuilder {
+        let deal_id = self.new_deal_id();
+        InitializeBuilder::new(
+            &self.config,
+            deal_id,
+            parties.client.pubkey(),
+            parties.executor.pubkey(),
+            mint,
+            amount,
+        )
+    }
+
+    pub async fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        // a fresh blockhash, so that repeating the same instruction is not deduplicated
+        let blockhash = self.ctx.get_new_latest_blockhash().await.unwrap();
+        let mut all_signers = vec![&self.ctx.payer];
+        for signer in signers {
+            if !all_signers.iter().any(|known| known.pubkey() == signer.pubkey()) {
+                all_signers.push(signer);
+            }
+        }
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.ctx.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        self.ctx.banks_client.process_transaction(transaction).await
+    }
+
+    pub async fn account_data(&mut self, address: &Pubkey) -> Option<Vec<u8>> {
+        self.ctx
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .map(|account| account.data)
+    }
+
+    pub async fn exists(&mut self, address: &Pubkey) -> bool {
+        self.account_data(address).await.is_some()
+    }
+
+    pub async fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let data = self.account_data(address).await.expect("account not found");
+        T::try_deserialize(&mut data.as_slice()).unwrap()
+    }
+
+    pub async fn deal(&mut self, deal_state: &Pubkey) -> DealState {
+        self.account(deal_state).await
+    }
+
+    pub async fn lamports(&mut self, address: &Pubkey) -> u64 {
+        self.ctx.banks_client.get_balance(*address).await.unwrap()
+    }
+
+    /// Token balance of `owner`, lamports for the native mint
+    pub async fn balance(&mut self, mint: &TokenMint, owner: &Pubkey) -> u64 {
+        if mint.is_native() {
+            return self.lamports(owner).await;
+        }
+        self.token_balance(&mint.token_account(owner)).await
+    }
+
+    /// Balance of the token account at `address`, e.g. a bond vault
+    pub async fn token_balance(&mut self, address: &Pubkey) -> u64 {
+        match self.account_data(address).await {
+            Some(data) => {
+                StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)
+                    .unwrap()
+                    .base
+                    .amount
+            }
+            None => 0,
+        }
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        self.ctx.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp
+    }
+
+    pub async fn warp_by(&mut self, seconds: i64) {
+        let mut clock = self.ctx.banks_client.get_sysvar::<Clock>().await.unwrap();
+        clock.unix_timestamp += seconds;
+        self.ctx.set_sysvar(&clock);
+    }
+
+    pub fn token_account(&self, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+        self.registry.get(mint).token_account(owner)
+    }
+
+    pub fn open_dispute_ix(&self, deal: &DealState, initializer: &Pubkey) -> Instruction {
+        let deal_state = deal_state_pk(deal);
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::OpenDispute {
+                initializer: *initializer,
+                payer: *initializer,
+                deal_state,
+                dispute: pda::dispute(&deal_state).0,
+                config: pda::config().0,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::OpenDispute {
+                args: OpenDisputeArgs {
+                    reason_hash: [1; 32],
+                },
+            }
+            .data(),
+        }
+    }
+
+    pub fn submit_evidence_ix(
+        &self,
+        deal: &DealState,
+        initializer: &Pubkey,
+        hash: [u8; 32],
+    ) -> Instruction {
+        let deal_state = deal_state_pk(deal);
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::SubmitEvidence {
+                initializer: *initializer,
+                deal_state,
+                dispute: pda::dispute(&deal_state).0,
+                config: pda::config().0,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::SubmitEvidence {
+                args: SubmitEvidenceArgs { hash },
+            }
+            .data(),
+        }
+    }
+
+    /// Injects a dispute for a deal that is not disputed, the program never creates one
+    pub async fn inject_dispute(&mut self, deal: &DealState, initiator: &Pubkey) {
+        let (address, bump) = pda::dispute(&deal_state_pk(deal));
+        let dispute = Dispute {
+            deal_state: deal_state_pk(deal),
+            initiator: *initiator,
+            opened_ts: self.now().await,
+            evidence: vec![],
+            bump: [bump],
+        };
+        let mut data = Vec::new();
+        dispute.try_serialize(&mut data).unwrap();
+        data.resize(Dispute::SPACE, 0);
+        let account = SolanaAccount {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: deal_contract::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx.set_account(&address, &account.into());
+    }
+
+    /// Injects a deal written by the first version of the program, returns its address
+    pub fn inject_legacy_deal(&mut self, legacy: &LegacyDealState) -> Pubkey {
+        let address = pda::deal_state(&legacy.id, &legacy.client_key, &legacy.executor_key).0;
+        let mut data = DealState::discriminator().to_vec();
+        legacy.serialize(&mut data).unwrap();
+        data.resize(LegacyDealState::SPACE, 0);
+        let account = SolanaAccount {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: deal_contract::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx.set_account(&address, &account.into());
+        address
+    }
+
+    /// Injects a token account, e.g. holder tokens left behind by a cancelled legacy deal
+    pub fn inject_token_account(&mut self, mint: &TokenMint, owner: &Pubkey, amount: u64) {
+        let account = token_account(mint, owner, amount);
+        self.ctx.set_account(&mint.token_account(owner), &account.into());
+    }
+
+    /// Replaces a Token-2022 test mint with one that withholds `fee_bps` of every transfer
+    pub fn inject_transfer_fee(&mut self, mint: &TokenMint, fee_bps: u16) {
+        let transfer_fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: u64::MAX.into(),
+            transfer_fee_basis_points: fee_bps.into(),
+        };
+        let mut data = vec![
+            0;
+            ExtensionType::get_account_len::<spl_token_2022::state::Mint>(&[
+                ExtensionType::TransferFeeConfig
+            ])
+        ];
+        let mut state =
+            StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack_uninitialized(&mut data)
+                .unwrap();
+        let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        config.older_transfer_fee = transfer_fee;
+        config.newer_transfer_fee = transfer_fee;
+        state.base = spl_token_2022::state::Mint {
+            mint_authority: COption::Some(Pubkey::new_unique()),
+            supply: u64::MAX / 2,
+            decimals: DECIMALS,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        let account = SolanaAccount {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: mint.token_program,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx.set_account(&mint.address, &account.into());
+    }
+
+    pub fn resolve_dispute_ix(
+        &self,
+        deal: &DealState,
+        initializer: &Pubkey,
+        dispute_initiator: &Pubkey,
+        args: ResolveDisputeArgs,
+        with_receipt: bool,
+    ) -> Instruction {
+        let deal_state = deal_state_pk(deal);
+        let deal_mint = deal.deal_token_mint;
+        let client_deal_ta = self.token_account(&deal_mint, &deal.client_key);
+        let executor_deal_ta = self.token_account(&deal_mint, &deal.executor_key);
+        let deal_state_deal_ta = self.token_account(&deal_mint, &deal_state);
+        let checker = deal
+            .checker
+            .as_ref()
+            .map_or(*initializer, |Checker { checker_key, .. }| *checker_key);
+
+        // (mint, deal state ta, owner ta, counterparty ta), the deal ones when there is no bond
+        let bond_accounts = |bond: &Option<Bond>,
+                             vault: fn(&Pubkey, &Pubkey) -> Pubkey,
+                             owner: &Pubkey,
+                             counterparty: &Pubkey,
+                             owner_deal_ta,
+                             counterparty_deal_ta| {
+            match bond {
+                Some(Bond { mint, .. }) => (
+                    *mint,
+                    vault(&deal_state, mint),
+                    self.token_account(mint, owner),
+                    self.token_account(mint, counterparty),
+                ),
+                None => (deal_mint, deal_state_deal_ta, owner_deal_ta, counterparty_deal_ta),
+            }
+        };
+        let (client_bond_mint, deal_state_client_bond_ta, client_bond_ta, executor_client_bond_ta) =
+            bond_accounts(
+                &deal.client_bond,
+                pda::client_bond_vault,
+                &deal.client_key,
+                &deal.executor_key,
+                client_deal_ta,
+                executor_deal_ta,
+            );
+        let (
+            executor_bond_mint,
+            deal_state_executor_bond_ta,
+            executor_bond_ta,
+            client_executor_bond_ta,
+        ) = bond_accounts(
+            &deal.executor_bond,
+            pda::executor_bond_vault,
+            &deal.executor_key,
+            &deal.client_key,
+            executor_deal_ta,
+            client_deal_ta,
+        );
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::ResolveDispute {
+                initializer: *initializer,
+                checker,
+                client: deal.client_key,
+                executor: deal.executor_key,
+                payer: *initializer,
+                deal_state_deal_ta,
+                client_deal_ta,
+                executor_deal_ta,
+                checker_deal_ta: self.token_account(&deal_mint, &checker),
+                deal_state_client_bond_ta,
+                deal_state_executor_bond_ta,
+                client_bond_ta,
+                executor_client_bond_ta,
+                executor_bond_ta,
+                client_executor_bond_ta,
+                deal_mint,
+                client_bond_mint,
+                executor_bond_mint,
+                service_fee: self.config.service_fee_owner,
+                dispute_initiator: *dispute_initiator,
+                deal_state,
+                holder_stake: deal.holder_mode.map(|_| pda::holder_stake(&deal.client_key).0),
+                dispute: pda::dispute(&deal_state).0,
+                deal_milestones: deal.with_milestones.then(|| pda::deal_milestones(&deal_state).0),
+                checker_panel: deal.with_panel.then(|| pda::checker_panel(&deal_state).0),
+                deal_receipt: with_receipt.then(|| pda::deal_receipt(&deal_state).0),
+                config: pda::config().0,
+                associated_token_program: associated_token::ID,
+                token_program: anchor_spl::token::ID,
+                token_2022_program: anchor_spl::token_2022::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::ResolveDispute { args }.data(),
+        }
+    }
+
+    pub fn approve_milestone_ix(
+        &self,
+        deal: &DealState,
+        initializer: &Pubkey,
+        index: u8,
+    ) -> Instruction {
+        let deal_state = deal_state_pk(deal);
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::ApproveMilestone {
+                initializer: *initializer,
+                executor: deal.executor_key,
+                payer: *initializer,
+                deal_mint: deal.deal_token_mint,
+                deal_state_deal_ta: self.token_account(&deal.deal_token_mint, &deal_state),
+                executor_deal_ta: self.token_account(&deal.deal_token_mint, &deal.executor_key),
+                deal_state,
+                deal_milestones: pda::deal_milestones(&deal_state).0,
+                config: pda::config().0,
+                system_program: system_program::ID,
+                token_program: anchor_spl::token::ID,
+                token_2022_program: anchor_spl::token_2022::ID,
+                associated_token_program: associated_token::ID,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::ApproveMilestone {
+                args: SettleMilestoneArgs { index },
+            }
+            .data(),
+        }
+    }
+
+    pub fn reject_milestone_ix(
+        &self,
+        deal: &DealState,
+        initializer: &Pubkey,
+        index: u8,
+    ) -> Instruction {
+        let deal_state = deal_state_pk(deal);
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::RejectMilestone {
+                initializer: *initializer,
+                client: deal.client_key,
+                payer: *initializer,
+                deal_mint: deal.deal_token_mint,
+                deal_state_deal_ta: self.token_account(&deal.deal_token_mint, &deal_state),
+                client_deal_ta: self.token_account(&deal.deal_token_mint, &deal.client_key),
+                deal_state,
+                deal_milestones: pda::deal_milestones(&deal_state).0,
+                config: pda::config().0,
+                system_program: system_program::ID,
+                token_program: anchor_spl::token::ID,
+                token_2022_program: anchor_spl::token_2022::ID,
+                associated_token_program: associated_token::ID,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::RejectMilestone {
+                args: SettleMilestoneArgs { index },
+            }
+            .data(),
+        }
+    }
+
+    /// An offer from `parties.client` to `parties.executor` in the deal mint.
+    /// Bonds are posted in the bond mint.
+    pub fn create_offer_ix(&mut self, parties: &Parties, args: CreateOfferArgs) -> Instruction {
+        let client = parties.client.pubkey();
+        let executor = parties.executor.pubkey();
+        let deal_state = pda::deal_state(&args.id, &client, &executor).0;
+        let deal_mint = self.mints.deal;
+        let bond_mint = self.mints.bond;
+
+        let client_deal_ta = deal_mint.token_account(&client);
+        let deal_state_deal_ta = deal_mint.token_account(&deal_state);
+        let (client_bond_ta, deal_state_client_bond_ta) = if args.client_bond.is_some() {
+            (
+                bond_mint.token_account(&client),
+                pda::client_bond_vault(&deal_state, &bond_mint.address),
+            )
+        } else {
+            (client_deal_ta, deal_state_deal_ta)
+        };
+
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::CreateOffer {
+                client,
+                executor,
+                checker: parties.checker.pubkey(),
+                payer: client,
+                deal_mint: deal_mint.address,
+                client_bond_mint: bond_mint.address,
+                executor_bond_mint: bond_mint.address,
+                service_fee_owner: self.config.service_fee_owner,
+                service_fee_ta: deal_mint.token_account(&self.config.service_fee_owner),
+                client_deal_ta,
+                deal_state_deal_ta,
+                client_bond_ta,
+                deal_state_client_bond_ta,
+                deal_state,
+                holder_stake: args.holder_mode.then(|| pda::holder_stake(&client).0),
+                config: pda::config().0,
+                fee_schedule: pda::fee_schedule(&deal_mint.address).0,
+                system_program: system_program::ID,
+                token_program: anchor_spl::token::ID,
+                token_2022_program: anchor_spl::token_2022::ID,
+                associated_token_program: associated_token::ID,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::CreateOffer { args }.data(),
+        }
+    }
+
+    pub fn accept_offer_ix(&self, deal: &DealState) -> Instruction {
+        let deal_state = deal_state_pk(deal);
+        let bond_mint = deal
+            .executor_bond
+            .as_ref()
+            .map_or(deal.deal_token_mint, |Bond { mint, .. }| *mint);
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::AcceptOffer {
+                executor: deal.executor_key,
+                payer: deal.executor_key,
+                executor_bond_mint: bond_mint,
+                executor_bond_ta: self.token_account(&bond_mint, &deal.executor_key),
+                deal_state_executor_bond_ta: pda::executor_bond_vault(&deal_state, &bond_mint),
+                deal_mint: deal.deal_token_mint,
+                deal_state_deal_ta: self.token_account(&deal.deal_token_mint, &deal_state),
+                service_fee_owner: self.config.service_fee_owner,
+                service_fee_ta: self
+                    .token_account(&deal.deal_token_mint, &self.config.service_fee_owner),
+                deal_state,
+                config: pda::config().0,
+                system_program: system_program::ID,
+                token_program: anchor_spl::token::ID,
+                token_2022_program: anchor_spl::token_2022::ID,
+                associated_token_program: associated_token::ID,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::AcceptOffer {}.data(),
+        }
+    }
+
+    pub fn accept_role_ix(&self, deal: &DealState, checker: &Pubkey) -> Instruction {
+        let deal_state = deal_state_pk(deal);
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::AcceptRole {
+                checker: *checker,
+                deal_mint: deal.deal_token_mint,
+                deal_state_deal_ta: self.token_account(&deal.deal_token_mint, &deal_state),
+                service_fee_ta: self
+                    .token_account(&deal.deal_token_mint, &self.config.service_fee_owner),
+                deal_state,
+                config: pda::config().0,
+                token_program: anchor_spl::token::ID,
+                token_2022_program: anchor_spl::token_2022::ID,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::AcceptRole {}.data(),
+        }
+    }
+
+    pub fn withdraw_offer_ix(&self, deal: &DealState) -> Instruction {
+        let deal_state = deal_state_pk(deal);
+        let deal_mint = deal.deal_token_mint;
+        let client_deal_ta = self.token_account(&deal_mint, &deal.client_key);
+        let executor_deal_ta = self.token_account(&deal_mint, &deal.executor_key);
+        let deal_state_deal_ta = self.token_account(&deal_mint, &deal_state);
+
+        let bond_accounts = |bond: &Option<Bond>,
+                             vault: fn(&Pubkey, &Pubkey) -> Pubkey,
+                             owner: &Pubkey,
+                             owner_deal_ta| match bond {
+            Some(Bond { mint, .. }) => {
+                (*mint, self.token_account(mint, owner), vault(&deal_state, mint))
+            }
+            None => (deal_mint, owner_deal_ta, deal_state_deal_ta),
+        };
+        let (client_bond_mint, client_bond_ta, deal_state_client_bond_ta) = bond_accounts(
+            &deal.client_bond,
+            pda::client_bond_vault,
+            &deal.client_key,
+            client_deal_ta,
+        );
+        let (executor_bond_mint, executor_bond_ta, deal_state_executor_bond_ta) = bond_accounts(
+            &deal.executor_bond,
+            pda::executor_bond_vault,
+            &deal.executor_key,
+            executor_deal_ta,
+        );
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::WithdrawOffer {
+                client: deal.client_key,
+                executor: deal.executor_key,
+                payer: deal.client_key,
+                deal_state_deal_ta,
+                client_deal_ta,
+                deal_state_client_bond_ta,
+                client_bond_ta,
+                deal_state_executor_bond_ta,
+                executor_bond_ta,
+                deal_mint,
+                client_bond_mint,
+                executor_bond_mint,
+                service_fee: self.config.service_fee_owner,
+                deal_state,
+                holder_stake: deal.holder_mode.map(|_| pda::holder_stake(&deal.client_key).0),
+                config: pda::config().0,
+                associated_token_program: associated_token::ID,
+                token_program: anchor_spl::token::ID,
+                token_2022_program: anchor_spl::token_2022::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::WithdrawOffer {}.data(),
+        }
+    }
+
+    /// The checker account is the client for deals without a checker
+    pub fn slash_bond_ix(&self, deal: &DealState, payer: &Pubkey, side: BondSide) -> Instruction {
+        let deal_state = deal_state_pk(deal);
+        let checker = deal.checker.as_ref().map_or(deal.client_key, |checker| checker.checker_key);
+        let (bond, vault, recipient): (_, fn(&Pubkey, &Pubkey) -> Pubkey, _) = match side {
+            BondSide::Client => (&deal.client_bond, pda::client_bond_vault, deal.executor_key),
+            BondSide::Executor => (&deal.executor_bond, pda::executor_bond_vault, deal.client_key),
+        };
+        let bond_mint = bond.as_ref().map_or(deal.deal_token_mint, |Bond { mint, .. }| *mint);
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::SlashBond {
+                checker,
+                client: deal.client_key,
+                executor: deal.executor_key,
+                payer: *payer,
+                bond_mint,
+                deal_state_bond_ta: vault(&deal_state, &bond_mint),
+                recipient_bond_ta: self.token_account(&bond_mint, &recipient),
+                deal_state,
+                config: pda::config().0,
+                associated_token_program: associated_token::ID,
+                token_program: anchor_spl::token::ID,
+                token_2022_program: anchor_spl::token_2022::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::SlashBond { side }.data(),
+        }
+    }
+
+    pub fn mark_delivered_ix(&self, deal: &DealState) -> Instruction {
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::MarkDelivered {
+                executor: deal.executor_key,
+                deal_state: deal_state_pk(deal),
+                config: pda::config().0,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::MarkDelivered {}.data(),
+        }
+    }
+
+    /// The checker account is the client for deals without a checker
+    pub fn checker_vote_ix(
+        &self,
+        deal: &DealState,
+        checker: &Pubkey,
+        vote: PanelVote,
+    ) -> Instruction {
+        let deal_state = deal_state_pk(deal);
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::CheckerVote {
+                checker: *checker,
+                deal_state,
+                checker_panel: pda::checker_panel(&deal_state).0,
+                config: pda::config().0,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::CheckerVote { vote }.data(),
+        }
+    }
+
+    /// Signed by the config authority, the service party
+    pub fn set_fee_schedule_ix(&self, mint: &Pubkey, args: FeeScheduleArgs) -> Instruction {
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::SetFeeSchedule {
+                authority: self.config.authority,
+                payer: self.ctx.payer.pubkey(),
+                mint: *mint,
+                fee_schedule: pda::fee_schedule(mint).0,
+                config: pda::config().0,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::SetFeeSchedule { args }.data(),
+        }
+    }
+
+    pub fn stake_ix(&self, owner: &Pubkey, amount: u64) -> Instruction {
+        let holder_stake = pda::holder_stake(owner).0;
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::Stake {
+                owner: *owner,
+                payer: self.ctx.payer.pubkey(),
+                holder_mint: self.mints.holder.address,
+                owner_holder_ta: self.mints.holder.token_account(owner),
+                holder_stake_ta: self.mints.holder.token_account(&holder_stake),
+                holder_stake,
+                config: pda::config().0,
+                associated_token_program: associated_token::ID,
+                token_program: anchor_spl::token::ID,
+                token_2022_program: anchor_spl::token_2022::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::Stake { amount }.data(),
+        }
+    }
+
+    pub fn unstake_ix(&self, owner: &Pubkey, amount: u64) -> Instruction {
+        let holder_stake = pda::holder_stake(owner).0;
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::Unstake {
+                owner: *owner,
+                payer: self.ctx.payer.pubkey(),
+                holder_mint: self.mints.holder.address,
+                owner_holder_ta: self.mints.holder.token_account(owner),
+                holder_stake_ta: self.mints.holder.token_account(&holder_stake),
+                holder_stake,
+                config: pda::config().0,
+                associated_token_program: associated_token::ID,
+                token_program: anchor_spl::token::ID,
+                token_2022_program: anchor_spl::token_2022::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::Unstake { amount }.data(),
+        }
+    }
+
+    pub fn recover_holder_tokens_ix(&self, deal: &DealState, payer: &Pubkey) -> Instruction {
+        let deal_state = deal_state_pk(deal);
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::RecoverHolderTokens {
+                client: deal.client_key,
+                executor: deal.executor_key,
+                payer: *payer,
+                holder_mint: self.mints.holder.address,
+                client_holder_ta: self.mints.holder.token_account(&deal.client_key),
+                deal_state_holder_ta: self.mints.holder.token_account(&deal_state),
+                deal_state,
+                service_fee: self.config.service_fee_owner,
+                config: pda::config().0,
+                associated_token_program: associated_token::ID,
+                token_program: anchor_spl::token::ID,
+                token_2022_program: anchor_spl::token_2022::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::RecoverHolderTokens { deal_id: deal.id }.data(),
+        }
+    }
+
+    /// The legacy bond accounts are the deal state token accounts, the deal ones when there is no bond
+    pub fn migrate_deal_ix(&self, deal: &DealState, payer: &Pubkey) -> Instruction {
+        let deal_state = deal_state_pk(deal);
+        let bond_accounts = |bond: &Option<Bond>, vault: fn(&Pubkey, &Pubkey) -> Pubkey| {
+            let mint = bond.as_ref().map_or(deal.deal_token_mint, |Bond { mint, .. }| *mint);
+            let legacy_bond_ta = self.token_account(&mint, &deal_state);
+            match bond {
+                Some(_) => (mint, legacy_bond_ta, vault(&deal_state, &mint)),
+                None => (mint, legacy_bond_ta, legacy_bond_ta),
+            }
+        };
+        let (client_bond_mint, legacy_client_bond_ta, deal_state_client_bond_ta) =
+            bond_accounts(&deal.client_bond, pda::client_bond_vault);
+        let (executor_bond_mint, legacy_executor_bond_ta, deal_state_executor_bond_ta) =
+            bond_accounts(&deal.executor_bond, pda::executor_bond_vault);
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::MigrateDeal {
+                payer: *payer,
+                client_bond_mint,
+                legacy_client_bond_ta,
+                deal_state_client_bond_ta,
+                executor_bond_mint,
+                legacy_executor_bond_ta,
+                deal_state_executor_bond_ta,
+                service_fee: self.config.service_fee_owner,
+                deal_state,
+                config: pda::config().0,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::MigrateDeal {}.data(),
+        }
+    }
+
+    /// The injected config as `update_config` arguments
+    pub fn config_args(&self) -> ConfigArgs {
+        ConfigArgs {
+            authority: self.config.authority,
+            service_account: self.config.service_account,
+            service_fee_owner: self.config.service_fee_owner,
+            holder_mint: self.config.holder_mint,
+            holder_tiers: self
+                .config
+                .holder_tiers
+                .iter()
+                .filter(|holder_tier| holder_tier.amount > 0)
+                .copied()
+                .collect(),
+            holder_stake_cooldown: self.config.holder_stake_cooldown,
+            max_advance_bps: self.config.max_advance_bps,
+            review_window: self.config.review_window,
+        }
+    }
+
+    /// Signed by the config authority, the service party
+    pub fn update_config_ix(&self, args: ConfigArgs) -> Instruction {
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::UpdateConfig {
+                authority: self.config.authority,
+                config: pda::config().0,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::UpdateConfig { args }.data(),
+        }
+    }
+
+    pub fn pause_ix(&self, flags: u32) -> Instruction {
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::SetPaused {
+                authority: self.config.authority,
+                config: pda::config().0,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::Pause { flags }.data(),
+        }
+    }
+
+    pub fn unpause_ix(&self, flags: u32) -> Instruction {
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts: deal_contract::accounts::SetPaused {
+                authority: self.config.authority,
+                config: pda::config().0,
+            }
+            .to_account_metas(None),
+            data: deal_contract::instruction::Unpause { flags }.data(),
+        }
+    }
+
+    pub fn amend_deal_ix(&self, deal: &DealState, args: AmendDealArgs) -> Instruction {
+        let deal_state = deal_state_pk(deal);
+        let deal_mint = deal.deal_token_mint;
+        let checker = deal.checker.as_ref().map_or(deal.client_key, |checker| checker.checker_key);
+        let mut accounts = deal_contract::accounts::AmendDeal {
+            client: deal.client_key,
+            executor: deal.executor_key,
+            checker,
+            deal_mint,
+            client_deal_ta: self.token_account(&deal_mint, &deal.client_key),
+            deal_state_deal_ta: self.token_account(&deal_mint, &deal_state),
+            checker_panel: deal.with_panel.then(|| pda::checker_panel(&deal_state).0),
+            deal_state,
+            config: pda::config().0,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            token_2022_program: anchor_spl::token_2022::ID,
+        }
+        .to_account_metas(None);
+        // the panel cannot sign, its members sign as remaining accounts
+        if let Some(meta) = accounts
+            .iter_mut()
+            .find(|meta| meta.pubkey == checker && !deal.with_panel)
+        {
+            meta.is_signer = true;
+        }
+
+        Instruction {
+            program_id: deal_contract::ID,
+            accounts,
+            data: deal_contract::instruction::AmendDeal { args }.data(),
+        }
+    }
+}
+
+pub fn deal_state_pk(deal: &DealState) -> Pubkey {
+    pda::deal_state(&deal.id, &deal.client_key, &deal.executor_key).0
+}
+
+/// `(amount, discount_bps)` pairs padded to the config array
+pub fn holder_tiers(tiers: &[(u64, u16)]) -> [HolderTier; MAX_HOLDER_TIERS] {
+    let mut holder_tiers = [HolderTier::default(); MAX_HOLDER_TIERS];
+    for (holder_tier, &(amount, discount_bps)) in holder_tiers.iter_mut().zip(tiers) {
+        *holder_tier = HolderTier {
+            amount,
+            discount_bps,
+        };
+    }
+    holder_tiers
+}
+
+pub fn milestone(amount: u64, deadline_ts: Option<i64>) -> MilestoneArgs {
+    MilestoneArgs {
+        amount,
+        deadline_ts,
+        description_hash: [7; 32],
+    }
+}
+
+/// Swaps every occurrence of `from` in the instruction accounts, to build invalid instructions
+pub fn replace_account(instruction: &mut Instruction, from: &Pubkey, to: &Pubkey) {
+    for meta in instruction.accounts.iter_mut() {
+        if meta.pubkey == *from {
+            meta.pubkey = *to;
+        }
+    }
+}
+
+/// Asserts that the transaction failed with the custom program error `code`
+pub fn assert_error(result: Result<(), BanksClientError>, code: impl Into<u32>) {
+    let code = code.into();
+    match result {
+        Err(error) => match error.unwrap() {
+            TransactionError::InstructionError(_, InstructionError::Custom(actual)) => {
+                assert_eq!(actual, code, "expected error {code}, got {actual}")
+            }
+            other => panic!("expected error {code}, got {other:?}"),
+        },
+        Ok(()) => panic!("expected error {code}, the transaction succeeded"),
+    }
+}
@@ -0,0 +1,194 @@
+mod common;
+
+use common::*;
+use deal_contract::{
+    errors::{ErrorCodes, InvalidAccount},
+    state::{DealReceipt, Dispute, MAX_EVIDENCE},
+    ResolveDisputeArgs,
+};
+use deal_contract_client::{pda, CancelBuilder, FinishBuilder};
+use solana_sdk::signer::Signer;
+
+fn split(client_share_bps: u16, bonds_client_share_bps: u16) -> ResolveDisputeArgs {
+    ResolveDisputeArgs {
+        client_share_bps,
+        client_bond_client_share_bps: bonds_client_share_bps,
+        executor_bond_client_share_bps: bonds_client_share_bps,
+    }
+}
+
+#[tokio::test]
+async fn checker_resolves_dispute_with_a_split() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let bond_mint = env.mints.bond;
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .checker(p.checker.pubkey(), 2_000)
+        .client_bond(bond_mint, 3_000)
+        .executor_bond(bond_mint, 4_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let open = env.open_dispute_ix(&deal, &p.executor.pubkey());
+    env.send(&[open], &[&p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+    assert!(deal.is_disputed());
+
+    for (party, hash) in [(&p.client, [2; 32]), (&p.executor, [3; 32])] {
+        let evidence = env.submit_evidence_ix(&deal, &party.pubkey(), hash);
+        env.send(&[evidence], &[party]).await.unwrap();
+    }
+    let dispute: Dispute = env.account(&pda::dispute(&deal_state).0).await;
+    assert_eq!(dispute.initiator, p.executor.pubkey());
+    // the reason given on opening is the first piece of evidence
+    assert_eq!(dispute.evidence.len(), 3);
+
+    // 30% of the payment and all the bonds go to the client
+    let resolve = env.resolve_dispute_ix(
+        &deal,
+        &p.checker.pubkey(),
+        &p.executor.pubkey(),
+        split(3_000, 10_000),
+        true,
+    );
+    env.send(&[resolve], &[&p.checker]).await.unwrap();
+
+    assert_eq!(
+        env.balance(&deal_mint, &p.client.pubkey()).await,
+        BALANCE - SERVICE_FEE - 100_000 - 2_000 + 30_000
+    );
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 70_000);
+    assert_eq!(env.balance(&deal_mint, &p.checker.pubkey()).await, BALANCE + 2_000);
+    assert_eq!(env.balance(&bond_mint, &p.client.pubkey()).await, BALANCE + 4_000);
+    assert_eq!(env.balance(&bond_mint, &p.executor.pubkey()).await, BALANCE - 4_000);
+    assert!(!env.exists(&deal_state).await);
+    assert!(!env.exists(&pda::dispute(&deal_state).0).await);
+
+    let receipt: DealReceipt = env.account(&pda::deal_receipt(&deal_state).0).await;
+    assert_eq!(receipt.executor_received, 70_000);
+    assert_eq!(receipt.client_refunded, 30_000);
+    assert_eq!(receipt.checker_fee, 2_000);
+}
+
+#[tokio::test]
+async fn service_resolution_returns_checker_fee_to_client() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000).checker(p.checker.pubkey(), 2_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let open = env.open_dispute_ix(&deal, &p.client.pubkey());
+    env.send(&[open], &[&p.client]).await.unwrap();
+
+    let resolve = env.resolve_dispute_ix(
+        &deal,
+        &p.service.pubkey(),
+        &p.client.pubkey(),
+        split(10_000, 10_000),
+        false,
+    );
+    env.send(&[resolve], &[&p.service]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.client.pubkey()).await, BALANCE - SERVICE_FEE);
+    assert_eq!(env.balance(&deal_mint, &p.checker.pubkey()).await, BALANCE);
+}
+
+#[tokio::test]
+async fn disputed_deal_is_frozen() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000).checker(p.checker.pubkey(), 2_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let open = env.open_dispute_ix(&deal, &p.outsider.pubkey());
+    assert_error(env.send(&[open], &[&p.outsider]).await, InvalidAccount::DisputeParty);
+
+    let open = env.open_dispute_ix(&deal, &p.client.pubkey());
+    env.send(&[open], &[&p.client]).await.unwrap();
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    assert_error(env.send(&[finish], &[&p.client]).await, ErrorCodes::DealDisputed);
+    let cancel = CancelBuilder::new(&deal, &env.config, p.checker.pubkey()).instruction();
+    assert_error(env.send(&[cancel], &[&p.checker]).await, ErrorCodes::DealDisputed);
+
+    let evidence = env.submit_evidence_ix(&deal, &p.outsider.pubkey(), [4; 32]);
+    assert_error(env.send(&[evidence], &[&p.outsider]).await, InvalidAccount::DisputeParty);
+
+    let resolve = env.resolve_dispute_ix(
+        &deal,
+        &p.outsider.pubkey(),
+        &p.client.pubkey(),
+        split(5_000, 5_000),
+        false,
+    );
+    assert_error(env.send(&[resolve], &[&p.outsider]).await, InvalidAccount::Initializer);
+
+    let resolve = env.resolve_dispute_ix(
+        &deal,
+        &p.checker.pubkey(),
+        &p.client.pubkey(),
+        split(10_001, 5_000),
+        false,
+    );
+    assert_error(env.send(&[resolve], &[&p.checker]).await, ErrorCodes::InvalidBasisPoints);
+}
+
+#[tokio::test]
+async fn evidence_is_limited() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let open = env.open_dispute_ix(&deal, &p.client.pubkey());
+    env.send(&[open], &[&p.client]).await.unwrap();
+
+    for index in 1..MAX_EVIDENCE as u8 {
+        let evidence = env.submit_evidence_ix(&deal, &p.client.pubkey(), [index; 32]);
+        env.send(&[evidence], &[&p.client]).await.unwrap();
+    }
+    let evidence = env.submit_evidence_ix(&deal, &p.executor.pubkey(), [u8::MAX; 32]);
+    assert_error(
+        env.send(&[evidence], &[&p.executor]).await,
+        ErrorCodes::EvidenceLimitReached,
+    );
+}
+
+#[tokio::test]
+async fn evidence_requires_a_disputed_deal() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    env.inject_dispute(&deal, &p.client.pubkey()).await;
+
+    let evidence = env.submit_evidence_ix(&deal, &p.client.pubkey(), [2; 32]);
+    assert_error(env.send(&[evidence], &[&p.client]).await, ErrorCodes::DealNotDisputed);
+    let resolve = env.resolve_dispute_ix(
+        &deal,
+        &p.service.pubkey(),
+        &p.client.pubkey(),
+        split(5_000, 5_000),
+        false,
+    );
+    assert_error(env.send(&[resolve], &[&p.service]).await, ErrorCodes::DealNotDisputed);
+}
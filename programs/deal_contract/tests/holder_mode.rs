@@ -0,0 +1,187 @@
+mod common;
+
+use common::*;
+use deal_contract::{
+    errors::{ErrorCodes, InvalidAccount},
+    state::{ConfigArgs, HolderStake, HolderTier},
+};
+use deal_contract_client::{pda, CancelBuilder, FinishBuilder};
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn holder_stake_backs_the_deal_instead_of_the_fee() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let holder_mint = env.mints.holder;
+    let fee_owner = env.config.service_fee_owner;
+    let holder_stake = pda::holder_stake(&p.client.pubkey()).0;
+
+    let stake = env.stake_ix(&p.client.pubkey(), HOLDER_MODE_AMOUNT);
+    env.send(&[stake], &[&p.client]).await.unwrap();
+
+    let initialize = env.initialize(&p, 100_000).max_service_fee(0).holder_mode();
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    assert_eq!(env.balance(&holder_mint, &holder_stake).await, HOLDER_MODE_AMOUNT);
+    assert_eq!(env.balance(&holder_mint, &deal_state).await, 0);
+    assert_eq!(env.balance(&deal_mint, &fee_owner).await, 0);
+    assert_eq!(env.account::<HolderStake>(&holder_stake).await.active_deals, 1);
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(deal.holder_mode, Some(HOLDER_MODE_AMOUNT));
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[finish], &[&p.client]).await.unwrap();
+
+    assert_eq!(env.account::<HolderStake>(&holder_stake).await.active_deals, 0);
+    assert_eq!(env.balance(&holder_mint, &holder_stake).await, HOLDER_MODE_AMOUNT);
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 100_000);
+}
+
+#[tokio::test]
+async fn holder_tiers_discount_the_fee_in_any_deal_mint() {
+    let (mut env, p) = Env::start().await;
+    let bond_mint = env.mints.bond;
+    let fee_owner = env.config.service_fee_owner;
+
+    let config = ConfigArgs {
+        holder_tiers: vec![
+            HolderTier {
+                amount: 1_000,
+                discount_bps: 2_500,
+            },
+            HolderTier {
+                amount: BALANCE / 2,
+                discount_bps: 5_000,
+            },
+            HolderTier {
+                amount: BALANCE * 2,
+                discount_bps: 10_000,
+            },
+        ],
+        ..env.config_args()
+    };
+    let update = env.update_config_ix(config);
+    env.send(&[update], &[&p.service]).await.unwrap();
+
+    // a stake below the smallest tier does not unlock holder mode
+    let stake = env.stake_ix(&p.client.pubkey(), 500);
+    env.send(&[stake], &[&p.client]).await.unwrap();
+    let initialize = env.initialize_in(&p, bond_mint, 100_000).holder_mode().instruction();
+    assert_error(
+        env.send(&[initialize], &[&p.client, &p.executor]).await,
+        ErrorCodes::HolderModeUnavailable,
+    );
+
+    // the largest tier the stake covers backs every deal, the stake is not consumed
+    let stake = env.stake_ix(&p.client.pubkey(), BALANCE / 2);
+    env.send(&[stake], &[&p.client]).await.unwrap();
+    for deal in 1..=2 {
+        let initialize = env.initialize_in(&p, bond_mint, 100_000).holder_mode();
+        let deal_state = initialize.deal_state();
+        env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+        assert_eq!(env.deal(&deal_state).await.holder_mode, Some(BALANCE / 2));
+        assert_eq!(env.balance(&bond_mint, &fee_owner).await, deal * SERVICE_FEE / 2);
+    }
+    let holder_stake = env.account::<HolderStake>(&pda::holder_stake(&p.client.pubkey()).0).await;
+    assert_eq!(holder_stake.amount, BALANCE / 2 + 500);
+    assert_eq!(holder_stake.active_deals, 2);
+}
+
+#[tokio::test]
+async fn holder_tiers_are_validated() {
+    let (mut env, p) = Env::start().await;
+
+    let tier = |amount, discount_bps| HolderTier {
+        amount,
+        discount_bps,
+    };
+    for (holder_tiers, code) in [
+        (
+            vec![tier(2_000, 5_000), tier(1_000, 7_500)],
+            ErrorCodes::InvalidHolderTiers,
+        ),
+        (
+            vec![tier(1_000, 5_000), tier(2_000, 5_000)],
+            ErrorCodes::InvalidHolderTiers,
+        ),
+        (vec![tier(0, 5_000)], ErrorCodes::InvalidHolderTiers),
+        (vec![tier(1_000, 10_001)], ErrorCodes::InvalidBasisPoints),
+        (
+            (1..=5).map(|i| tier(i * 1_000, i as u16 * 1_000)).collect(),
+            ErrorCodes::InvalidHolderTiers,
+        ),
+    ] {
+        let update = env.update_config_ix(ConfigArgs {
+            holder_tiers,
+            ..env.config_args()
+        });
+        assert_error(env.send(&[update], &[&p.service]).await, code);
+    }
+}
+
+#[tokio::test]
+async fn cancel_releases_the_holder_stake() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let holder_stake = pda::holder_stake(&p.client.pubkey()).0;
+
+    let stake = env.stake_ix(&p.client.pubkey(), HOLDER_MODE_AMOUNT);
+    env.send(&[stake], &[&p.client]).await.unwrap();
+
+    let initialize = env.initialize(&p, 100_000).max_service_fee(0).holder_mode();
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let cancel = CancelBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[cancel], &[&p.client]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.client.pubkey()).await, BALANCE);
+    assert!(!env.exists(&deal_state).await);
+    assert_eq!(env.account::<HolderStake>(&holder_stake).await.active_deals, 0);
+
+    env.warp_by(STAKE_COOLDOWN).await;
+    let unstake = env.unstake_ix(&p.client.pubkey(), HOLDER_MODE_AMOUNT);
+    env.send(&[unstake], &[&p.client]).await.unwrap();
+}
+
+#[tokio::test]
+async fn holder_tokens_of_a_cancelled_legacy_deal_are_recovered() {
+    let (mut env, p) = Env::start().await;
+    let holder_mint = env.mints.holder;
+    let fee_owner = env.config.service_fee_owner;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    // a live deal keeps its accounts
+    env.inject_token_account(&holder_mint, &deal_state, HOLDER_MODE_AMOUNT);
+    let recover = env.recover_holder_tokens_ix(&deal, &p.outsider.pubkey());
+    assert_error(env.send(&[recover], &[&p.outsider]).await, ErrorCodes::DealNotClosed);
+
+    // cancelled before `cancel` returned the holder tokens
+    let cancel = CancelBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[cancel], &[&p.client]).await.unwrap();
+    assert_eq!(env.balance(&holder_mint, &deal_state).await, HOLDER_MODE_AMOUNT);
+
+    let fee_owner_lamports = env.lamports(&fee_owner).await;
+    let recover = env.recover_holder_tokens_ix(&deal, &p.outsider.pubkey());
+    env.send(&[recover], &[&p.outsider]).await.unwrap();
+
+    assert_eq!(
+        env.balance(&holder_mint, &p.client.pubkey()).await,
+        BALANCE + HOLDER_MODE_AMOUNT
+    );
+    assert!(!env.exists(&holder_mint.token_account(&deal_state)).await);
+    assert!(env.lamports(&fee_owner).await > fee_owner_lamports);
+
+    let recover = env.recover_holder_tokens_ix(&deal, &p.outsider.pubkey());
+    assert_error(
+        env.send(&[recover], &[&p.outsider]).await,
+        InvalidAccount::DealStateHolderTokenAccount,
+    );
+}
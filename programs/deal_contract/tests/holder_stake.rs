@@ -0,0 +1,91 @@
+mod common;
+
+use common::*;
+use deal_contract::{
+    errors::{ErrorCodes, InvalidAccount},
+    state::HolderStake,
+};
+use deal_contract_client::{pda, FinishBuilder};
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn stake_backs_several_deals_and_unlocks_after_they_close() {
+    let (mut env, p) = Env::start().await;
+    let holder_mint = env.mints.holder;
+    let holder_stake = pda::holder_stake(&p.client.pubkey()).0;
+
+    let stake = env.stake_ix(&p.client.pubkey(), HOLDER_MODE_AMOUNT);
+    env.send(&[stake], &[&p.client]).await.unwrap();
+    let stake_account = env.account::<HolderStake>(&holder_stake).await;
+    assert_eq!(stake_account.owner, p.client.pubkey());
+    assert_eq!(stake_account.amount, HOLDER_MODE_AMOUNT);
+    assert_eq!(stake_account.unlock_ts, env.now().await + STAKE_COOLDOWN);
+
+    let mut deals = vec![];
+    for _ in 0..2 {
+        let initialize = env.initialize(&p, 100_000).max_service_fee(0).holder_mode();
+        deals.push(initialize.deal_state());
+        env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    }
+    assert_eq!(env.account::<HolderStake>(&holder_stake).await.active_deals, 2);
+
+    env.warp_by(STAKE_COOLDOWN).await;
+    let unstake = env.unstake_ix(&p.client.pubkey(), HOLDER_MODE_AMOUNT);
+    assert_error(env.send(&[unstake], &[&p.client]).await, ErrorCodes::StakeInUse);
+
+    for deal_state in &deals {
+        let deal = env.deal(deal_state).await;
+        let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+        env.send(&[finish], &[&p.client]).await.unwrap();
+    }
+    assert_eq!(env.account::<HolderStake>(&holder_stake).await.active_deals, 0);
+
+    let unstake = env.unstake_ix(&p.client.pubkey(), HOLDER_MODE_AMOUNT);
+    env.send(&[unstake], &[&p.client]).await.unwrap();
+    assert_eq!(env.balance(&holder_mint, &p.client.pubkey()).await, BALANCE);
+    assert_eq!(env.balance(&holder_mint, &holder_stake).await, 0);
+}
+
+#[tokio::test]
+async fn stake_is_locked_for_the_cooldown_after_each_deposit() {
+    let (mut env, p) = Env::start().await;
+
+    let stake = env.stake_ix(&p.client.pubkey(), HOLDER_MODE_AMOUNT);
+    env.send(&[stake], &[&p.client]).await.unwrap();
+
+    env.warp_by(STAKE_COOLDOWN / 2).await;
+    let unstake = env.unstake_ix(&p.client.pubkey(), HOLDER_MODE_AMOUNT);
+    assert_error(env.send(&[unstake], &[&p.client]).await, ErrorCodes::StakeInCooldown);
+
+    // topping up restarts the cooldown
+    let stake = env.stake_ix(&p.client.pubkey(), 1_000);
+    env.send(&[stake], &[&p.client]).await.unwrap();
+    env.warp_by(STAKE_COOLDOWN / 2 + 1).await;
+    let unstake = env.unstake_ix(&p.client.pubkey(), HOLDER_MODE_AMOUNT);
+    assert_error(env.send(&[unstake], &[&p.client]).await, ErrorCodes::StakeInCooldown);
+
+    env.warp_by(STAKE_COOLDOWN / 2).await;
+    let unstake = env.unstake_ix(&p.client.pubkey(), HOLDER_MODE_AMOUNT);
+    env.send(&[unstake], &[&p.client]).await.unwrap();
+    let holder_stake = pda::holder_stake(&p.client.pubkey()).0;
+    assert_eq!(env.account::<HolderStake>(&holder_stake).await.amount, 1_000);
+}
+
+#[tokio::test]
+async fn unstake_is_checked() {
+    let (mut env, p) = Env::start().await;
+
+    let stake = env.stake_ix(&p.client.pubkey(), 0);
+    assert_error(env.send(&[stake], &[&p.client]).await, ErrorCodes::AmountTooLow);
+
+    let stake = env.stake_ix(&p.client.pubkey(), HOLDER_MODE_AMOUNT);
+    env.send(&[stake], &[&p.client]).await.unwrap();
+    env.warp_by(STAKE_COOLDOWN).await;
+
+    let unstake = env.unstake_ix(&p.client.pubkey(), HOLDER_MODE_AMOUNT + 1);
+    assert_error(env.send(&[unstake], &[&p.client]).await, ErrorCodes::InsufficientStake);
+
+    let mut unstake = env.unstake_ix(&p.client.pubkey(), HOLDER_MODE_AMOUNT);
+    replace_account(&mut unstake, &p.client.pubkey(), &p.outsider.pubkey());
+    assert_error(env.send(&[unstake], &[&p.outsider]).await, InvalidAccount::Initializer);
+}
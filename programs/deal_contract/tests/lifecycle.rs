@@ -0,0 +1,298 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use deal_contract::{
+    errors::{ErrorCodes, InvalidAccount},
+    state::{DealReceipt, DealStatus},
+};
+use deal_contract_client::{
+    pda, CancelBuilder, CheckerUpdate, FinishBuilder, UpdateCheckerBuilder,
+};
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn finish_by_client_pays_executor_and_service_fee() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let fee_owner = env.config.service_fee_owner;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &deal_state).await, 100_000);
+    assert_eq!(env.balance(&deal_mint, &fee_owner).await, SERVICE_FEE);
+    assert_eq!(
+        env.balance(&deal_mint, &p.client.pubkey()).await,
+        BALANCE - 100_000 - SERVICE_FEE
+    );
+
+    let deal = env.deal(&deal_state).await;
+    assert!(deal.is_active());
+    assert_eq!(deal.amount, 100_000);
+    assert_eq!(deal.paid_amount, 0);
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[finish], &[&p.client]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 100_000);
+    assert!(!env.exists(&deal_state).await);
+    assert!(!env.exists(&deal_mint.token_account(&deal_state)).await);
+}
+
+#[tokio::test]
+async fn finish_by_checker_pays_checker_fee() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000).checker(p.checker.pubkey(), 2_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+    assert_eq!(env.balance(&deal_mint, &deal_state).await, 102_000);
+
+    let deal = env.deal(&deal_state).await;
+    let finish = FinishBuilder::new(&deal, &env.config, p.checker.pubkey()).instruction();
+    env.send(&[finish], &[&p.checker]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 100_000);
+    assert_eq!(env.balance(&deal_mint, &p.checker.pubkey()).await, BALANCE + 2_000);
+}
+
+#[tokio::test]
+async fn finish_by_anyone_else_fails() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000).checker(p.checker.pubkey(), 2_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.executor.pubkey()).instruction();
+    assert_error(env.send(&[finish], &[&p.executor]).await, ErrorCode::ConstraintRaw);
+}
+
+#[tokio::test]
+async fn finish_without_checker_only_by_client() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    // a deal without a checker must not treat the initializer as one
+    for signer in [&p.executor, &p.outsider] {
+        let finish = FinishBuilder::new(&deal, &env.config, signer.pubkey()).instruction();
+        assert_error(env.send(&[finish], &[signer]).await, ErrorCode::ConstraintRaw);
+    }
+    assert!(env.deal(&deal_state).await.is_active());
+}
+
+#[tokio::test]
+async fn initialize_checks_amount_fee_and_deadline() {
+    let (mut env, p) = Env::start().await;
+    let now = env.now().await;
+
+    let initialize = env.initialize(&p, 0).instruction();
+    assert_error(
+        env.send(&[initialize], &[&p.client, &p.executor]).await,
+        ErrorCodes::AmountTooLow,
+    );
+
+    let initialize = env.initialize(&p, 100_000).max_service_fee(SERVICE_FEE - 1).instruction();
+    assert_error(
+        env.send(&[initialize], &[&p.client, &p.executor]).await,
+        ErrorCodes::ServiceFeeAboveLimit,
+    );
+
+    let initialize = env.initialize(&p, 100_000).deadline(now - 1).instruction();
+    assert_error(
+        env.send(&[initialize], &[&p.client, &p.executor]).await,
+        ErrorCodes::DeadlineExpired,
+    );
+}
+
+#[tokio::test]
+async fn initialize_rejects_overflowing_amounts() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, u64::MAX).checker(p.checker.pubkey(), 1).instruction();
+    assert_error(
+        env.send(&[initialize], &[&p.client, &p.executor, &p.checker]).await,
+        ErrorCodes::MathOverflow,
+    );
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .milestones(vec![milestone(u64::MAX, None), milestone(1, None)])
+        .instruction();
+    assert_error(
+        env.send(&[initialize], &[&p.client, &p.executor]).await,
+        ErrorCodes::MathOverflow,
+    );
+}
+
+#[tokio::test]
+async fn cancel_by_client_after_deadline_refunds_and_keeps_receipt() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let now = env.now().await;
+
+    let initialize = env.initialize(&p, 100_000).deadline(now + 3_600);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let cancel = CancelBuilder::new(&deal, &env.config, p.client.pubkey())
+        .with_receipt()
+        .instruction();
+    assert_error(
+        env.send(std::slice::from_ref(&cancel), &[&p.client]).await,
+        ErrorCodes::DeadlineNotExpired,
+    );
+
+    env.warp_by(3_601).await;
+    env.send(&[cancel], &[&p.client]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.client.pubkey()).await, BALANCE - SERVICE_FEE);
+    assert!(!env.exists(&deal_state).await);
+
+    let receipt: DealReceipt = env.account(&pda::deal_receipt(&deal_state).0).await;
+    assert!(receipt.status == DealStatus::Cancelled);
+    assert_eq!(receipt.client_refunded, 100_000);
+    assert_eq!(receipt.executor_received, 0);
+    assert_eq!(receipt.settled_by, p.client.pubkey());
+}
+
+#[tokio::test]
+async fn cancel_by_checker_pays_checker_fee() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000).checker(p.checker.pubkey(), 2_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let cancel = CancelBuilder::new(&deal, &env.config, p.checker.pubkey()).instruction();
+    env.send(&[cancel], &[&p.checker]).await.unwrap();
+
+    assert_eq!(
+        env.balance(&deal_mint, &p.client.pubkey()).await,
+        BALANCE - SERVICE_FEE - 2_000
+    );
+    assert_eq!(env.balance(&deal_mint, &p.checker.pubkey()).await, BALANCE + 2_000);
+}
+
+#[tokio::test]
+async fn cancel_by_service_account() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let cancel = CancelBuilder::new(&deal, &env.config, p.service.pubkey()).instruction();
+    env.send(&[cancel], &[&p.service]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.client.pubkey()).await, BALANCE - SERVICE_FEE);
+    assert!(!env.exists(&deal_state).await);
+}
+
+#[tokio::test]
+async fn cancel_by_outsider_fails() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    // the builder passes the initializer as the checker of a deal without one
+    let cancel = CancelBuilder::new(&deal, &env.config, p.outsider.pubkey()).instruction();
+    assert_error(env.send(&[cancel], &[&p.outsider]).await, InvalidAccount::Initializer);
+    assert!(env.exists(&deal_state).await);
+}
+
+#[tokio::test]
+async fn update_checker() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    // the parties add a checker together
+    let update = UpdateCheckerBuilder::new(
+        &deal,
+        &env.config,
+        p.client.pubkey(),
+        CheckerUpdate::Add { checker_fee: 0 },
+    )
+    .new_checker(p.checker.pubkey())
+    .instruction();
+    env.send(&[update], &[&p.client, &p.executor, &p.checker]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(deal.with_checker().unwrap().checker_key, p.checker.pubkey());
+
+    // but can't add a second one
+    let update = UpdateCheckerBuilder::new(
+        &deal,
+        &env.config,
+        p.client.pubkey(),
+        CheckerUpdate::Add { checker_fee: 0 },
+    )
+    .new_checker(p.outsider.pubkey())
+    .instruction();
+    assert_error(
+        env.send(&[update], &[&p.client, &p.executor, &p.outsider]).await,
+        ErrorCodes::DealStateWithChecker,
+    );
+
+    // the service account replaces it without the parties
+    let update = UpdateCheckerBuilder::new(
+        &deal,
+        &env.config,
+        p.service.pubkey(),
+        CheckerUpdate::Replace { checker_fee: 0 },
+    )
+    .new_checker(p.outsider.pubkey())
+    .instruction();
+    env.send(&[update], &[&p.service, &p.outsider]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(deal.with_checker().unwrap().checker_key, p.outsider.pubkey());
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.outsider.pubkey()).instruction();
+    env.send(&[finish], &[&p.outsider]).await.unwrap();
+    assert!(!env.exists(&deal_state).await);
+}
+
+#[tokio::test]
+async fn token_2022_deal() {
+    let (mut env, p) = Env::start().await;
+    let mint = env.mints.token_2022;
+
+    let initialize = env.initialize_in(&p, mint, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    assert_eq!(env.balance(&mint, &deal_state).await, 100_000);
+
+    let deal = env.deal(&deal_state).await;
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey())
+        .mint(mint)
+        .instruction();
+    env.send(&[finish], &[&p.client]).await.unwrap();
+
+    assert_eq!(env.balance(&mint, &p.executor.pubkey()).await, BALANCE + 100_000);
+    assert!(!env.exists(&mint.token_account(&deal_state)).await);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::*;
+use deal_contract::{
+    constants::{PAUSE_ALL, PAUSE_EXITS, PAUSE_MIGRATE_DEAL},
+    errors::ErrorCodes,
+    state::{Bond, Checker, DealState, DealStatus, LegacyDealState, DEAL_STATE_VERSION},
+};
+use deal_contract_client::{pda, FinishBuilder};
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn legacy_deal_is_migrated_and_settled() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let bond_mint = env.mints.bond;
+    let holder_mint = env.mints.holder;
+    let now = env.now().await;
+
+    let id = env.new_deal_id();
+    let bump = pda::deal_state(&id, &p.client.pubkey(), &p.executor.pubkey()).1;
+    let deal_state = env.inject_legacy_deal(&LegacyDealState {
+        id,
+        client_key: p.client.pubkey(),
+        executor_key: p.executor.pubkey(),
+        deal_token_mint: deal_mint.address,
+        client_bond: Some(Bond {
+            mint: bond_mint.address,
+            amount: 3_000,
+        }),
+        executor_bond: Some(Bond {
+            mint: bond_mint.address,
+            amount: 4_000,
+        }),
+        checker: Some(Checker {
+            checker_fee: 2_000,
+            checker_key: p.checker.pubkey(),
+        }),
+        holder_mode: Some(HOLDER_MODE_AMOUNT),
+        amount: 100_000,
+        paid_amount: 0,
+        deadline_ts: Some(now + 3_600),
+        bump: [bump],
+    });
+    // the first version kept both bonds in the same deal state token account
+    env.inject_token_account(&deal_mint, &deal_state, 102_000);
+    env.inject_token_account(&bond_mint, &deal_state, 7_000);
+    env.inject_token_account(&holder_mint, &deal_state, HOLDER_MODE_AMOUNT);
+
+    let deal = env.deal(&deal_state).await;
+    assert!(deal.status == DealStatus::Legacy);
+    assert_eq!(deal.version, 0);
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    assert!(env.send(&[finish], &[&p.client]).await.is_err());
+    let mark_delivered = env.mark_delivered_ix(&deal);
+    assert_error(env.send(&[mark_delivered], &[&p.executor]).await, ErrorCodes::DealNotActive);
+
+    let pause = env.pause_ix(PAUSE_MIGRATE_DEAL);
+    env.send(&[pause], &[&p.service]).await.unwrap();
+    let migrate = env.migrate_deal_ix(&deal, &p.outsider.pubkey());
+    assert_error(env.send(&[migrate], &[&p.outsider]).await, ErrorCodes::InstructionPaused);
+    let unpause = env.unpause_ix(PAUSE_MIGRATE_DEAL);
+    env.send(&[unpause], &[&p.service]).await.unwrap();
+
+    // a paused program still lets legacy deals out
+    let pause = env.pause_ix(PAUSE_ALL & !PAUSE_EXITS);
+    env.send(&[pause], &[&p.service]).await.unwrap();
+
+    let migrate = env.migrate_deal_ix(&deal, &p.outsider.pubkey());
+    env.send(&[migrate], &[&p.outsider]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    assert!(deal.is_active());
+    assert_eq!(deal.version, DEAL_STATE_VERSION);
+    assert_eq!(deal.amount, 100_000);
+    assert_eq!(deal.holder_mode, None);
+    assert_eq!(env.account_data(&deal_state).await.unwrap().len(), DealState::SPACE);
+    assert_eq!(
+        env.token_balance(&pda::client_bond_vault(&deal_state, &bond_mint.address)).await,
+        3_000
+    );
+    assert_eq!(
+        env.token_balance(&pda::executor_bond_vault(&deal_state, &bond_mint.address)).await,
+        4_000
+    );
+    assert!(!env.exists(&bond_mint.token_account(&deal_state)).await);
+
+    let migrate = env.migrate_deal_ix(&deal, &p.outsider.pubkey());
+    assert_error(env.send(&[migrate], &[&p.outsider]).await, ErrorCodes::AlreadyMigrated);
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[finish], &[&p.client]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 100_000);
+    assert_eq!(env.balance(&deal_mint, &p.checker.pubkey()).await, BALANCE + 2_000);
+    assert_eq!(env.balance(&bond_mint, &p.client.pubkey()).await, BALANCE + 3_000);
+    assert_eq!(env.balance(&bond_mint, &p.executor.pubkey()).await, BALANCE + 4_000);
+    assert!(!env.exists(&deal_state).await);
+
+    // the holder tokens stay with the closed deal until they are recovered
+    let recover = env.recover_holder_tokens_ix(&deal, &p.client.pubkey());
+    env.send(&[recover], &[&p.client]).await.unwrap();
+    assert_eq!(
+        env.balance(&holder_mint, &p.client.pubkey()).await,
+        BALANCE + HOLDER_MODE_AMOUNT
+    );
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::*;
+use deal_contract::{
+    errors::{ErrorCodes, InvalidAccount},
+    state::{DealMilestones, MilestoneStatus, MAX_MILESTONES},
+};
+use deal_contract_client::{pda, FinishBuilder};
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn approved_milestones_are_paid_out() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .advance_payment(10_000)
+        .milestones(vec![milestone(40_000, None), milestone(50_000, None)]);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    assert!(deal.with_milestones);
+    let approve = env.approve_milestone_ix(&deal, &p.client.pubkey(), 0);
+    env.send(&[approve], &[&p.client]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 50_000);
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(deal.paid_amount, 50_000);
+    let milestones: DealMilestones = env.account(&pda::deal_milestones(&deal_state).0).await;
+    assert!(milestones.milestones[0].status == MilestoneStatus::Approved);
+
+    let approve = env.approve_milestone_ix(&deal, &p.client.pubkey(), 0);
+    assert_error(
+        env.send(&[approve], &[&p.client]).await,
+        ErrorCodes::MilestoneAlreadySettled,
+    );
+    let approve = env.approve_milestone_ix(&deal, &p.client.pubkey(), 2);
+    assert_error(env.send(&[approve], &[&p.client]).await, ErrorCodes::MilestoneNotFound);
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[finish], &[&p.client]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 100_000);
+    assert!(!env.exists(&pda::deal_milestones(&deal_state).0).await);
+}
+
+#[tokio::test]
+async fn rejected_milestone_is_refunded() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let now = env.now().await;
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .checker(p.checker.pubkey(), 0)
+        .milestones(vec![milestone(40_000, Some(now + 3_600)), milestone(60_000, None)]);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let reject = env.reject_milestone_ix(&deal, &p.executor.pubkey(), 0);
+    assert_error(env.send(&[reject], &[&p.executor]).await, InvalidAccount::Initializer);
+
+    // the client may reject only after the milestone deadline, the checker at any time
+    let reject = env.reject_milestone_ix(&deal, &p.client.pubkey(), 0);
+    assert_error(
+        env.send(std::slice::from_ref(&reject), &[&p.client]).await,
+        ErrorCodes::DeadlineNotExpired,
+    );
+    env.warp_by(3_601).await;
+    env.send(&[reject], &[&p.client]).await.unwrap();
+
+    // without a milestone or a deal deadline only the checker may reject
+    let reject = env.reject_milestone_ix(&deal, &p.client.pubkey(), 1);
+    assert_error(env.send(&[reject], &[&p.client]).await, ErrorCodes::DeadlineNotExpired);
+    let reject = env.reject_milestone_ix(&deal, &p.checker.pubkey(), 1);
+    env.send(&[reject], &[&p.checker]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.client.pubkey()).await, BALANCE - SERVICE_FEE);
+    let milestones: DealMilestones = env.account(&pda::deal_milestones(&deal_state).0).await;
+    assert!(milestones
+        .milestones
+        .iter()
+        .all(|milestone| milestone.status == MilestoneStatus::Rejected));
+}
+
+#[tokio::test]
+async fn milestone_without_deadline_falls_back_to_deal_deadline() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let now = env.now().await;
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .deadline(now + 3_600)
+        .milestones(vec![milestone(100_000, None)]);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let reject = env.reject_milestone_ix(&deal, &p.client.pubkey(), 0);
+    assert_error(
+        env.send(std::slice::from_ref(&reject), &[&p.client]).await,
+        ErrorCodes::DeadlineNotExpired,
+    );
+    env.warp_by(3_601).await;
+    env.send(&[reject], &[&p.client]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.client.pubkey()).await, BALANCE - SERVICE_FEE);
+}
+
+#[tokio::test]
+async fn milestones_must_add_up() {
+    let (mut env, p) = Env::start().await;
+
+    let too_many = vec![milestone(1_000, None); MAX_MILESTONES + 1];
+    let amount = 1_000 * too_many.len() as u64;
+    let initialize = env.initialize(&p, amount).milestones(too_many).instruction();
+    assert_error(
+        env.send(&[initialize], &[&p.client, &p.executor]).await,
+        ErrorCodes::TooManyMilestones,
+    );
+
+    let initialize = env
+        .initialize(&p, 100_000)
+        .advance_payment(10_000)
+        .milestones(vec![milestone(40_000, None), milestone(40_000, None)])
+        .instruction();
+    assert_error(
+        env.send(&[initialize], &[&p.client, &p.executor]).await,
+        ErrorCodes::MilestonesAmountMismatch,
+    );
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::*;
+use deal_contract_client::{CancelBuilder, FinishBuilder, PartiallyPayBuilder};
+use solana_sdk::{rent::Rent, signer::Signer};
+
+#[tokio::test]
+async fn native_deal_with_checker_and_partial_payment() {
+    let (mut env, p) = Env::start().await;
+    let native = env.mints.native;
+
+    let initialize = env.initialize_in(&p, native, 1_000_000).checker(p.checker.pubkey(), 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor, &p.checker])
+        .await
+        .unwrap();
+
+    let deal_state_len = env.account_data(&deal_state).await.unwrap().len();
+    let rent = Rent::default().minimum_balance(deal_state_len);
+    assert_eq!(env.lamports(&deal_state).await, rent + 1_100_000);
+
+    let deal = env.deal(&deal_state).await;
+    let executor_before = env.lamports(&p.executor.pubkey()).await;
+    let pay = PartiallyPayBuilder::new(&deal, 200_000).instruction();
+    env.send(&[pay], &[&p.client]).await.unwrap();
+    assert_eq!(env.lamports(&p.executor.pubkey()).await, executor_before + 200_000);
+
+    let deal = env.deal(&deal_state).await;
+    let checker_before = env.lamports(&p.checker.pubkey()).await;
+    let finish = FinishBuilder::new(&deal, &env.config, p.checker.pubkey())
+        .payer(p.checker.pubkey())
+        .instruction();
+    env.send(&[finish], &[&p.checker]).await.unwrap();
+
+    assert_eq!(env.lamports(&p.executor.pubkey()).await, executor_before + 1_000_000);
+    assert_eq!(env.lamports(&p.checker.pubkey()).await, checker_before + 100_000);
+    assert!(!env.exists(&deal_state).await);
+}
+
+#[tokio::test]
+async fn native_deal_cancel_refunds_lamports() {
+    let (mut env, p) = Env::start().await;
+    let native = env.mints.native;
+
+    let client_before = env.lamports(&p.client.pubkey()).await;
+    let initialize = env.initialize_in(&p, native, 1_000_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let cancel = CancelBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[cancel], &[&p.client]).await.unwrap();
+
+    // the deal state rent goes back to the client as well, the transaction fees are paid by the context payer
+    assert_eq!(env.lamports(&p.client.pubkey()).await, client_before - SERVICE_FEE);
+    assert!(!env.exists(&deal_state).await);
+}
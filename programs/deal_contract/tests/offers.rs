@@ -0,0 +1,167 @@
+mod common;
+
+use common::*;
+use deal_contract::{
+    errors::{ErrorCodes, InvalidAccount},
+    state::BondForfeiture,
+    CreateOfferArgs,
+};
+use deal_contract_client::{pda, FinishBuilder};
+use solana_sdk::signer::Signer;
+
+fn offer(env: &mut Env, acceptance_deadline_ts: i64) -> CreateOfferArgs {
+    CreateOfferArgs {
+        id: env.new_deal_id(),
+        deal_amount: 100_000,
+        service_fee: Some(SERVICE_FEE),
+        deadline_ts: None,
+        acceptance_deadline_ts,
+        holder_mode: false,
+        client_bond: None,
+        executor_bond: None,
+        forfeiture: BondForfeiture::default(),
+        checker_fee: None,
+    }
+}
+
+#[tokio::test]
+async fn accepted_offer_becomes_a_deal() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let bond_mint = env.mints.bond;
+    let now = env.now().await;
+
+    let args = CreateOfferArgs {
+        client_bond: Some(3_000),
+        executor_bond: Some(4_000),
+        checker_fee: Some(2_000),
+        ..offer(&mut env, now + 3_600)
+    };
+    let deal_state = pda::deal_state(&args.id, &p.client.pubkey(), &p.executor.pubkey()).0;
+    let create = env.create_offer_ix(&p, args);
+    env.send(&[create], &[&p.client]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    assert!(deal.is_offer());
+    // the escrow holds the service fee until the offer is active
+    assert_eq!(env.balance(&deal_mint, &deal_state).await, 102_000 + SERVICE_FEE);
+    assert_eq!(deal.held_service_fee, SERVICE_FEE);
+    let service_fee_owner = env.config.service_fee_owner;
+    let client_vault = pda::client_bond_vault(&deal_state, &bond_mint.address);
+    assert_eq!(env.token_balance(&client_vault).await, 3_000);
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    assert_error(env.send(&[finish], &[&p.client]).await, ErrorCodes::OfferNotAccepted);
+
+    let accept_role = env.accept_role_ix(&deal, &p.outsider.pubkey());
+    assert_error(env.send(&[accept_role], &[&p.outsider]).await, InvalidAccount::Checker);
+
+    let accept = env.accept_offer_ix(&deal);
+    env.send(&[accept], &[&p.executor]).await.unwrap();
+    let executor_vault = pda::executor_bond_vault(&deal_state, &bond_mint.address);
+    assert_eq!(env.token_balance(&client_vault).await, 3_000);
+    assert_eq!(env.token_balance(&executor_vault).await, 4_000);
+    // still waits for the checker
+    assert!(env.deal(&deal_state).await.is_offer());
+    assert_eq!(env.balance(&deal_mint, &service_fee_owner).await, 0);
+
+    let accept_role = env.accept_role_ix(&deal, &p.checker.pubkey());
+    env.send(&[accept_role], &[&p.checker]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+    assert!(deal.is_active());
+    assert_eq!(deal.held_service_fee, 0);
+    assert_eq!(env.balance(&deal_mint, &deal_state).await, 102_000);
+    assert_eq!(env.balance(&deal_mint, &service_fee_owner).await, SERVICE_FEE);
+
+    let accept = env.accept_offer_ix(&deal);
+    assert_error(env.send(&[accept], &[&p.executor]).await, ErrorCodes::OfferAlreadyAccepted);
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.checker.pubkey()).instruction();
+    env.send(&[finish], &[&p.checker]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 100_000);
+    assert_eq!(env.balance(&bond_mint, &p.client.pubkey()).await, BALANCE);
+    assert_eq!(env.balance(&bond_mint, &p.executor.pubkey()).await, BALANCE);
+}
+
+#[tokio::test]
+async fn offer_expires() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let now = env.now().await;
+
+    // the acceptance deadline must be in the future
+    let args = offer(&mut env, now);
+    let create = env.create_offer_ix(&p, args);
+    assert_error(
+        env.send(&[create], &[&p.client]).await,
+        ErrorCodes::AcceptanceDeadlineExpired,
+    );
+    // and not after the deal deadline
+    let args = CreateOfferArgs {
+        deadline_ts: Some(now + 1_800),
+        ..offer(&mut env, now + 3_600)
+    };
+    let create = env.create_offer_ix(&p, args);
+    assert_error(
+        env.send(&[create], &[&p.client]).await,
+        ErrorCodes::AcceptanceAfterDeadline,
+    );
+
+    let args = offer(&mut env, now + 3_600);
+    let deal_state = pda::deal_state(&args.id, &p.client.pubkey(), &p.executor.pubkey()).0;
+    let create = env.create_offer_ix(&p, args);
+    env.send(&[create], &[&p.client]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let withdraw = env.withdraw_offer_ix(&deal);
+    assert_error(
+        env.send(std::slice::from_ref(&withdraw), &[&p.client]).await,
+        ErrorCodes::AcceptanceDeadlineNotExpired,
+    );
+
+    env.warp_by(3_601).await;
+    let accept = env.accept_offer_ix(&deal);
+    assert_error(
+        env.send(&[accept], &[&p.executor]).await,
+        ErrorCodes::AcceptanceDeadlineExpired,
+    );
+
+    env.send(&[withdraw], &[&p.client]).await.unwrap();
+    // the service fee is refunded with the deposit
+    let service_fee_owner = env.config.service_fee_owner;
+    assert_eq!(env.balance(&deal_mint, &p.client.pubkey()).await, BALANCE);
+    assert_eq!(env.balance(&deal_mint, &service_fee_owner).await, 0);
+    assert!(!env.exists(&deal_state).await);
+}
+
+#[tokio::test]
+async fn offer_sets_the_forfeiture_rules() {
+    let (mut env, p) = Env::start().await;
+    let now = env.now().await;
+
+    let forfeiture = BondForfeiture {
+        executor_on_deadline: true,
+        checker_slash: false,
+    };
+    let args = CreateOfferArgs {
+        forfeiture,
+        ..offer(&mut env, now + 3_600)
+    };
+    let create = env.create_offer_ix(&p, args);
+    assert_error(
+        env.send(&[create], &[&p.client]).await,
+        ErrorCodes::InvalidForfeitureRules,
+    );
+
+    let args = CreateOfferArgs {
+        deadline_ts: Some(now + 7_200),
+        executor_bond: Some(4_000),
+        forfeiture,
+        ..offer(&mut env, now + 3_600)
+    };
+    let deal_state = pda::deal_state(&args.id, &p.client.pubkey(), &p.executor.pubkey()).0;
+    let create = env.create_offer_ix(&p, args);
+    env.send(&[create], &[&p.client]).await.unwrap();
+    assert!(env.deal(&deal_state).await.forfeiture == forfeiture);
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use common::*;
+use deal_contract::{
+    errors::{ErrorCodes, InvalidAccount},
+    state::{CheckerPanel, PanelVote},
+};
+use deal_contract_client::{pda, CancelBuilder, FinishBuilder};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+#[tokio::test]
+async fn panel_approves_and_splits_the_fee() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let third = Keypair::new();
+    let checkers = [&p.checker, &p.outsider, &third];
+
+    let initialize = env.initialize(&p, 100_000).checker_panel(
+        checkers.iter().map(|checker| checker.pubkey()).collect(),
+        2,
+        3_001,
+    );
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+    assert!(deal.with_panel);
+    assert_eq!(
+        deal.checker.as_ref().unwrap().checker_key,
+        pda::checker_panel(&deal_state).0
+    );
+
+    let vote = env.checker_vote_ix(&deal, &p.checker.pubkey(), PanelVote::Approve);
+    env.send(std::slice::from_ref(&vote), &[&p.checker]).await.unwrap();
+    assert_error(env.send(&[vote], &[&p.checker]).await, ErrorCodes::AlreadyVoted);
+    let vote = env.checker_vote_ix(&deal, &p.client.pubkey(), PanelVote::Approve);
+    assert_error(env.send(&[vote], &[&p.client]).await, InvalidAccount::Checker);
+
+    // a single cancellation settles nothing
+    let panel: CheckerPanel = env.account(&pda::checker_panel(&deal_state).0).await;
+    let cancel = CancelBuilder::new(&deal, &env.config, p.outsider.pubkey())
+        .checker_panel(&panel)
+        .instruction();
+    assert_error(env.send(&[cancel], &[&p.outsider]).await, ErrorCodes::PanelNotDecided);
+    let vote = env.checker_vote_ix(&deal, &p.outsider.pubkey(), PanelVote::Cancel);
+    env.send(&[vote], &[&p.outsider]).await.unwrap();
+
+    let panel: CheckerPanel = env.account(&pda::checker_panel(&deal_state).0).await;
+    let finish = FinishBuilder::new(&deal, &env.config, p.checker.pubkey())
+        .checker_panel(&panel)
+        .instruction();
+    assert_error(env.send(&[finish], &[&p.checker]).await, ErrorCodes::AlreadyVoted);
+
+    // the vote reaching the threshold settles the deal
+    let vote = env.checker_vote_ix(&deal, &third.pubkey(), PanelVote::Approve);
+    assert_error(env.send(&[vote], &[&third]).await, ErrorCodes::PanelVoteSettles);
+
+    let finish = FinishBuilder::new(&deal, &env.config, third.pubkey())
+        .checker_panel(&panel)
+        .payer(p.checker.pubkey())
+        .instruction();
+    env.send(&[finish], &[&third, &p.checker]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 100_000);
+    // every voter takes part in the split, the remainder goes to the first ones
+    assert_eq!(env.balance(&deal_mint, &p.checker.pubkey()).await, BALANCE + 1_001);
+    assert_eq!(env.balance(&deal_mint, &p.outsider.pubkey()).await, BALANCE + 1_000);
+    assert_eq!(env.balance(&deal_mint, &third.pubkey()).await, 1_000);
+    assert!(!env.exists(&deal_state).await);
+    assert!(!env.exists(&pda::checker_panel(&deal_state).0).await);
+}
+
+#[tokio::test]
+async fn panel_cancels_the_deal() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let third = Keypair::new();
+
+    let now = env.now().await;
+
+    let initialize = env.initialize(&p, 100_000).deadline(now + 3_600).checker_panel(
+        vec![p.checker.pubkey(), p.outsider.pubkey(), third.pubkey()],
+        2,
+        2_000,
+    );
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let vote = env.checker_vote_ix(&deal, &p.checker.pubkey(), PanelVote::Cancel);
+    env.send(&[vote], &[&p.checker]).await.unwrap();
+    let vote = env.checker_vote_ix(&deal, &p.outsider.pubkey(), PanelVote::Cancel);
+    assert_error(env.send(&[vote], &[&p.outsider]).await, ErrorCodes::PanelVoteSettles);
+
+    // the panel decision does not wait for the deadline
+    let panel: CheckerPanel = env.account(&pda::checker_panel(&deal_state).0).await;
+    let cancel = CancelBuilder::new(&deal, &env.config, p.outsider.pubkey())
+        .checker_panel(&panel)
+        .instruction();
+    env.send(&[cancel], &[&p.outsider]).await.unwrap();
+
+    assert_eq!(
+        env.balance(&deal_mint, &p.client.pubkey()).await,
+        BALANCE - SERVICE_FEE - 2_000
+    );
+    assert_eq!(env.balance(&deal_mint, &p.checker.pubkey()).await, BALANCE + 1_000);
+    assert_eq!(env.balance(&deal_mint, &p.outsider.pubkey()).await, BALANCE + 1_000);
+    assert_eq!(env.balance(&deal_mint, &third.pubkey()).await, 0);
+    assert!(!env.exists(&pda::checker_panel(&deal_state).0).await);
+}
+
+#[tokio::test]
+async fn checker_panel_is_validated() {
+    let (mut env, p) = Env::start().await;
+    let third = Keypair::new().pubkey();
+    let deal_mint = env.mints.deal;
+    let (checker, outsider) = (p.checker.pubkey(), p.outsider.pubkey());
+
+    for (checkers, threshold) in [
+        (vec![checker, outsider, third], 1),
+        (vec![checker, outsider, third], 4),
+        (vec![checker], 1),
+        (vec![checker, checker, third], 2),
+        (vec![checker, p.executor.pubkey(), third], 2),
+    ] {
+        let initialize = env.initialize(&p, 100_000).checker_panel(checkers, threshold, 1_000);
+        assert_error(
+            env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await,
+            ErrorCodes::InvalidCheckerPanel,
+        );
+    }
+
+    // the client may still finish on its own, nobody voted so the fee goes back to the client
+    let initialize = env.initialize(&p, 100_000).checker_panel(vec![checker, outsider], 2, 3_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    assert_error(
+        env.send(&[finish], &[&p.client]).await,
+        InvalidAccount::CheckerDealTokenAccount,
+    );
+
+    let panel: CheckerPanel = env.account(&pda::checker_panel(&deal_state).0).await;
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey())
+        .checker_panel(&panel)
+        .instruction();
+    env.send(&[finish], &[&p.client]).await.unwrap();
+    assert_eq!(
+        env.balance(&deal_mint, &p.client.pubkey()).await,
+        BALANCE - SERVICE_FEE - 100_000
+    );
+    assert_eq!(env.balance(&deal_mint, &checker).await, BALANCE);
+    assert_eq!(env.balance(&deal_mint, &outsider).await, BALANCE);
+}
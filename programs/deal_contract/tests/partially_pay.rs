@@ -0,0 +1,179 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use deal_contract::errors::ErrorCodes;
+use deal_contract_client::{CancelBuilder, FinishBuilder, PartiallyPayBuilder};
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn partial_release_comes_out_of_the_escrow() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let pay = PartiallyPayBuilder::new(&deal, 30_000).instruction();
+    env.send(&[pay], &[&p.client]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(deal.paid_amount, 30_000);
+    assert_eq!(env.balance(&deal_mint, &deal_state).await, 70_000);
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 30_000);
+
+    let pay = PartiallyPayBuilder::new(&deal, 70_001).instruction();
+    assert_error(env.send(&[pay], &[&p.client]).await, ErrorCodes::ReleaseExceedsEscrow);
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[finish], &[&p.client]).await.unwrap();
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 100_000);
+    assert_eq!(
+        env.balance(&deal_mint, &p.client.pubkey()).await,
+        BALANCE - SERVICE_FEE - 100_000
+    );
+}
+
+#[tokio::test]
+async fn release_is_capped_by_the_advance_payment() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000).advance_payment(40_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let pay = PartiallyPayBuilder::new(&deal, 60_001).instruction();
+    assert_error(env.send(&[pay], &[&p.client]).await, ErrorCodes::ReleaseExceedsEscrow);
+    let pay = PartiallyPayBuilder::new(&deal, 60_000).instruction();
+    env.send(&[pay], &[&p.client]).await.unwrap();
+    assert_eq!(env.deal(&deal_state).await.paid_amount, 100_000);
+}
+
+#[tokio::test]
+async fn advance_payment_is_validated() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000).advance_payment(100_001).instruction();
+    assert_error(
+        env.send(&[initialize], &[&p.client, &p.executor]).await,
+        ErrorCodes::AdvanceExceedsDealAmount,
+    );
+
+    // MAX_ADVANCE_BPS is a half of the deal amount
+    let initialize = env.initialize(&p, 100_000).advance_payment(50_001).instruction();
+    assert_error(
+        env.send(&[initialize], &[&p.client, &p.executor]).await,
+        ErrorCodes::AdvanceExceedsMaxAdvance,
+    );
+
+    let initialize = env.initialize(&p, 100_000).advance_payment(50_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 50_000);
+    assert_eq!(env.deal(&deal_state).await.paid_amount, 50_000);
+}
+
+#[tokio::test]
+async fn milestone_deals_release_through_milestones() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000).milestones(vec![milestone(100_000, None)]);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let pay = PartiallyPayBuilder::new(&deal, 10_000).instruction();
+    assert_error(env.send(&[pay], &[&p.client]).await, ErrorCodes::DealWithMilestones);
+    let top_up = PartiallyPayBuilder::new(&deal, 10_000).top_up().instruction();
+    env.send(&[top_up], &[&p.client]).await.unwrap();
+}
+
+#[tokio::test]
+async fn top_up_goes_from_client_to_executor() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let pay = PartiallyPayBuilder::new(&deal, 30_000).top_up().instruction();
+    env.send(&[pay], &[&p.client]).await.unwrap();
+
+    // top-ups do not touch the escrow, so the deal can still be finished in full
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(deal.paid_amount, 0);
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 30_000);
+    assert_eq!(
+        env.balance(&deal_mint, &p.client.pubkey()).await,
+        BALANCE - SERVICE_FEE - 100_000 - 30_000
+    );
+    assert_eq!(env.balance(&deal_mint, &deal_state).await, 100_000);
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[finish], &[&p.client]).await.unwrap();
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 130_000);
+}
+
+#[tokio::test]
+async fn advance_payment_is_not_refunded_on_cancel() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000).advance_payment(25_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 25_000);
+    assert_eq!(env.balance(&deal_mint, &deal_state).await, 75_000);
+
+    let deal = env.deal(&deal_state).await;
+    assert_eq!(deal.paid_amount, 25_000);
+    let cancel = CancelBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[cancel], &[&p.client]).await.unwrap();
+
+    assert_eq!(
+        env.balance(&deal_mint, &p.client.pubkey()).await,
+        BALANCE - SERVICE_FEE - 25_000
+    );
+}
+
+#[tokio::test]
+async fn only_the_client_pays() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let mut pay = PartiallyPayBuilder::new(&deal, 30_000).instruction();
+    replace_account(&mut pay, &p.client.pubkey(), &p.outsider.pubkey());
+    assert_error(env.send(&[pay], &[&p.outsider]).await, ErrorCode::ConstraintRaw);
+}
+
+#[tokio::test]
+async fn client_token_account_is_checked() {
+    let (mut env, p) = Env::start().await;
+    let bond_mint = env.mints.bond;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let client_deal_ta = deal_mint.token_account(&p.client.pubkey());
+    let mut pay = PartiallyPayBuilder::new(&deal, 30_000).top_up().instruction();
+    replace_account(&mut pay, &client_deal_ta, &bond_mint.token_account(&p.client.pubkey()));
+    assert_error(env.send(&[pay], &[&p.client]).await, ErrorCodes::InvalidMint);
+
+    let mut pay = PartiallyPayBuilder::new(&deal, 30_000).top_up().instruction();
+    replace_account(&mut pay, &client_deal_ta, &deal_mint.token_account(&p.outsider.pubkey()));
+    assert_error(env.send(&[pay], &[&p.client]).await, ErrorCodes::InvalidOwner);
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::*;
+use deal_contract::{
+    constants::*,
+    errors::{ErrorCodes, InvalidAccount},
+    state::ProgramConfig,
+};
+use deal_contract_client::{pda, CancelBuilder, FinishBuilder, PartiallyPayBuilder};
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn paused_entry_points_keep_the_exits_open() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let mut deals = vec![];
+    for _ in 0..2 {
+        let initialize = env.initialize(&p, 100_000);
+        let deal_state = initialize.deal_state();
+        env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+        deals.push(env.deal(&deal_state).await);
+    }
+
+    let pause = env.pause_ix(PAUSE_INITIALIZE | PAUSE_PARTIALLY_PAY);
+    env.send(&[pause], &[&p.service]).await.unwrap();
+    let config = env.account::<ProgramConfig>(&pda::config().0).await;
+    assert_eq!(config.paused, PAUSE_INITIALIZE | PAUSE_PARTIALLY_PAY);
+
+    let initialize = env.initialize(&p, 100_000).instruction();
+    assert_error(
+        env.send(&[initialize], &[&p.client, &p.executor]).await,
+        ErrorCodes::InstructionPaused,
+    );
+    let pay = PartiallyPayBuilder::new(&deals[0], 30_000).instruction();
+    assert_error(env.send(&[pay], &[&p.client]).await, ErrorCodes::InstructionPaused);
+
+    let finish = FinishBuilder::new(&deals[0], &env.config, p.client.pubkey()).instruction();
+    env.send(&[finish], &[&p.client]).await.unwrap();
+    let cancel = CancelBuilder::new(&deals[1], &env.config, p.client.pubkey()).instruction();
+    env.send(&[cancel], &[&p.client]).await.unwrap();
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 100_000);
+
+    let unpause = env.unpause_ix(PAUSE_INITIALIZE);
+    env.send(&[unpause], &[&p.service]).await.unwrap();
+    let initialize = env.initialize(&p, 100_000).instruction();
+    env.send(&[initialize], &[&p.client, &p.executor]).await.unwrap();
+    let config = env.account::<ProgramConfig>(&pda::config().0).await;
+    assert_eq!(config.paused, PAUSE_PARTIALLY_PAY);
+}
+
+#[tokio::test]
+async fn pausing_some_instructions_leaves_the_others() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+
+    let pause = env.pause_ix(PAUSE_FINISH | PAUSE_CANCEL | PAUSE_STAKE);
+    env.send(&[pause], &[&p.service]).await.unwrap();
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    assert_error(env.send(&[finish], &[&p.client]).await, ErrorCodes::InstructionPaused);
+    let cancel = CancelBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    assert_error(env.send(&[cancel], &[&p.client]).await, ErrorCodes::InstructionPaused);
+    let stake = env.stake_ix(&p.client.pubkey(), HOLDER_MODE_AMOUNT);
+    assert_error(env.send(&[stake], &[&p.client]).await, ErrorCodes::InstructionPaused);
+
+    // the other instructions are not affected
+    let pay = PartiallyPayBuilder::new(&deal, 30_000).instruction();
+    env.send(&[pay], &[&p.client]).await.unwrap();
+
+    let unpause = env.unpause_ix(PAUSE_ALL);
+    env.send(&[unpause], &[&p.service]).await.unwrap();
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[finish], &[&p.client]).await.unwrap();
+}
+
+#[tokio::test]
+async fn pause_is_for_the_config_authority_only() {
+    let (mut env, p) = Env::start().await;
+
+    let mut pause = env.pause_ix(PAUSE_INITIALIZE);
+    replace_account(&mut pause, &p.service.pubkey(), &p.outsider.pubkey());
+    assert_error(env.send(&[pause], &[&p.outsider]).await, InvalidAccount::Authority);
+
+    for flags in [0, PAUSE_ALL + 1] {
+        let pause = env.pause_ix(flags);
+        assert_error(env.send(&[pause], &[&p.service]).await, ErrorCodes::InvalidPauseFlags);
+        let unpause = env.unpause_ix(flags);
+        assert_error(env.send(&[unpause], &[&p.service]).await, ErrorCodes::InvalidPauseFlags);
+    }
+}
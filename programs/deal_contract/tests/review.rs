@@ -0,0 +1,106 @@
+mod common;
+
+use common::*;
+use deal_contract::errors::{ErrorCodes, InvalidAccount};
+use deal_contract_client::{CancelBuilder, FinishBuilder};
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn executor_claims_after_the_review_window() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let mark = env.mark_delivered_ix(&deal);
+    env.send(&[mark], &[&p.executor]).await.unwrap();
+    let deal = env.deal(&deal_state).await;
+    assert!(deal.review_deadline_ts.is_some());
+
+    let mark = env.mark_delivered_ix(&deal);
+    assert_error(env.send(&[mark], &[&p.executor]).await, ErrorCodes::AlreadyDelivered);
+
+    let claim = FinishBuilder::new(&deal, &env.config, p.executor.pubkey())
+        .claim_after_review()
+        .instruction();
+    assert_error(
+        env.send(std::slice::from_ref(&claim), &[&p.executor]).await,
+        ErrorCodes::ReviewWindowNotExpired,
+    );
+    let finish = FinishBuilder::new(&deal, &env.config, p.executor.pubkey()).instruction();
+    assert_error(env.send(&[finish], &[&p.executor]).await, InvalidAccount::Initializer);
+
+    env.warp_by(REVIEW_WINDOW + 1).await;
+    let open = env.open_dispute_ix(&deal, &p.client.pubkey());
+    assert_error(env.send(&[open], &[&p.client]).await, ErrorCodes::ReviewWindowExpired);
+
+    env.send(&[claim], &[&p.executor]).await.unwrap();
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 100_000);
+    assert!(!env.exists(&deal_state).await);
+}
+
+#[tokio::test]
+async fn client_settles_within_the_review_window() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let mark = env.mark_delivered_ix(&deal);
+    env.send(&[mark], &[&p.executor]).await.unwrap();
+
+    let finish = FinishBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    env.send(&[finish], &[&p.client]).await.unwrap();
+    assert_eq!(env.balance(&deal_mint, &p.executor.pubkey()).await, BALANCE + 100_000);
+}
+
+#[tokio::test]
+async fn dispute_within_the_review_window_blocks_the_claim() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let mark = env.mark_delivered_ix(&deal);
+    env.send(&[mark], &[&p.executor]).await.unwrap();
+    let open = env.open_dispute_ix(&deal, &p.client.pubkey());
+    env.send(&[open], &[&p.client]).await.unwrap();
+
+    env.warp_by(REVIEW_WINDOW + 1).await;
+    let claim = FinishBuilder::new(&deal, &env.config, p.executor.pubkey())
+        .claim_after_review()
+        .instruction();
+    assert_error(env.send(&[claim], &[&p.executor]).await, ErrorCodes::DealDisputed);
+}
+
+#[tokio::test]
+async fn delivered_deal_cannot_be_cancelled_by_a_party() {
+    let (mut env, p) = Env::start().await;
+
+    let initialize = env.initialize(&p, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    let deal = env.deal(&deal_state).await;
+    let mark = env.mark_delivered_ix(&deal);
+    env.send(&[mark], &[&p.executor]).await.unwrap();
+
+    for party in [&p.client, &p.executor] {
+        let cancel = CancelBuilder::new(&deal, &env.config, party.pubkey()).instruction();
+        assert_error(env.send(&[cancel], &[party]).await, ErrorCodes::DealDelivered);
+    }
+
+    // nor after the review window, the executor claims then
+    env.warp_by(REVIEW_WINDOW + 1).await;
+    let cancel = CancelBuilder::new(&deal, &env.config, p.client.pubkey()).instruction();
+    assert_error(env.send(&[cancel], &[&p.client]).await, ErrorCodes::DealDelivered);
+    assert!(env.exists(&deal_state).await);
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use deal_contract::{
+    errors::{ErrorCodes, InvalidAccount},
+    state::{FeeSchedule, FeeScheduleArgs},
+};
+use deal_contract_client::pda;
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn service_fee_follows_the_schedule() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal;
+    let fee_owner = env.config.service_fee_owner;
+
+    let schedule = env.set_fee_schedule_ix(
+        &deal_mint.address,
+        FeeScheduleArgs {
+            fee_bps: 100,
+            min_fee: 500,
+            max_fee: 5_000,
+        },
+    );
+    env.send(&[schedule], &[&p.service]).await.unwrap();
+    let fee_schedule: FeeSchedule = env.account(&pda::fee_schedule(&deal_mint.address).0).await;
+    assert_eq!(fee_schedule.mint, deal_mint.address);
+
+    // 1% of the deal amount, within the bounds of the schedule
+    let mut collected = 0;
+    for (amount, service_fee) in [(10_000, 500), (200_000, 2_000), (1_000_000, 5_000)] {
+        let client_balance = env.balance(&deal_mint, &p.client.pubkey()).await;
+        let initialize = env.initialize(&p, amount);
+        env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+        collected += service_fee;
+        assert_eq!(env.balance(&deal_mint, &fee_owner).await, collected);
+        assert_eq!(
+            env.balance(&deal_mint, &p.client.pubkey()).await,
+            client_balance - amount - service_fee
+        );
+    }
+}
+
+#[tokio::test]
+async fn service_fee_limit_and_schedule_validation() {
+    let (mut env, p) = Env::start().await;
+    let deal_mint = env.mints.deal.address;
+
+    let args = |fee_bps, min_fee, max_fee| FeeScheduleArgs {
+        fee_bps,
+        min_fee,
+        max_fee,
+    };
+    let schedule = env.set_fee_schedule_ix(&deal_mint, args(100, 0, u64::MAX));
+    env.send(&[schedule], &[&p.service]).await.unwrap();
+
+    // the client bounds the fee it accepts
+    let initialize = env.initialize(&p, 100_000).max_service_fee(999).instruction();
+    assert_error(
+        env.send(&[initialize], &[&p.client, &p.executor]).await,
+        ErrorCodes::ServiceFeeAboveLimit,
+    );
+    let initialize = env.initialize(&p, 100_000).max_service_fee(1_000).instruction();
+    env.send(&[initialize], &[&p.client, &p.executor]).await.unwrap();
+
+    let mut schedule = env.set_fee_schedule_ix(&deal_mint, args(100, 0, 0));
+    replace_account(&mut schedule, &p.service.pubkey(), &p.outsider.pubkey());
+    assert_error(env.send(&[schedule], &[&p.outsider]).await, InvalidAccount::Authority);
+
+    let schedule = env.set_fee_schedule_ix(&deal_mint, args(100, 2_000, 1_000));
+    assert_error(env.send(&[schedule], &[&p.service]).await, ErrorCodes::InvalidFeeSchedule);
+    let schedule = env.set_fee_schedule_ix(&deal_mint, args(10_001, 0, 1_000));
+    assert_error(env.send(&[schedule], &[&p.service]).await, ErrorCodes::InvalidBasisPoints);
+}
+
+#[tokio::test]
+async fn deal_mint_without_a_schedule_is_rejected() {
+    let (mut env, p) = Env::start().await;
+    let holder_mint = env.mints.holder;
+    let fee_owner = env.config.service_fee_owner;
+
+    let initialize = env.initialize_in(&p, holder_mint, 100_000).instruction();
+    assert_error(
+        env.send(&[initialize], &[&p.client, &p.executor]).await,
+        ErrorCode::AccountNotInitialized,
+    );
+
+    // a schedule may waive the fee
+    let schedule = env.set_fee_schedule_ix(
+        &holder_mint.address,
+        FeeScheduleArgs {
+            fee_bps: 0,
+            min_fee: 0,
+            max_fee: 0,
+        },
+    );
+    env.send(&[schedule], &[&p.service]).await.unwrap();
+
+    let initialize = env.initialize_in(&p, holder_mint, 100_000).max_service_fee(0);
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+    assert_eq!(env.balance(&holder_mint, &p.client.pubkey()).await, BALANCE - 100_000);
+    assert_eq!(env.balance(&holder_mint, &fee_owner).await, 0);
+}
+
+#[tokio::test]
+async fn service_fee_is_grossed_up_for_transfer_fees() {
+    let (mut env, p) = Env::start().await;
+    let mint = env.mints.token_2022;
+    let fee_owner = env.config.service_fee_owner;
+    env.inject_transfer_fee(&mint, 100);
+
+    let initialize = env.initialize_in(&p, mint, 100_000);
+    let deal_state = initialize.deal_state();
+    env.send(&[initialize.instruction()], &[&p.client, &p.executor]).await.unwrap();
+
+    // the deal and the service both receive their full amount, the client pays the 1% on top
+    assert_eq!(env.balance(&mint, &deal_state).await, 100_000);
+    assert_eq!(env.balance(&mint, &fee_owner).await, SERVICE_FEE);
+    assert_eq!(env.balance(&mint, &p.client.pubkey()).await, BALANCE - 101_011 - 1_011);
+}
@@ -1,11 +1,20 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program, BN, IdlTypes } from "@coral-xyz/anchor";
-import { PublicKey, Keypair, TransactionInstruction, VersionedTransaction, Signer, AddressLookupTableAccount, TransactionMessage, Connection, ComputeBudgetProgram } from '@solana/web3.js';
-import { TOKEN_PROGRAM_ID, createAccount, getAssociatedTokenAddressSync } from "@solana/spl-token";
+import { PublicKey, Keypair, TransactionInstruction, VersionedTransaction, Signer, AddressLookupTableAccount, TransactionMessage, Connection, ComputeBudgetProgram, BPF_LOADER_UPGRADEABLE_PROGRAM_ID } from '@solana/web3.js';
+import { TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, NATIVE_MINT, createAccount, getAssociatedTokenAddressSync } from "@solana/spl-token";
 import { DealContract } from "../target/types/deal_contract";
 import { ASSOCIATED_PROGRAM_ID } from "@coral-xyz/anchor/dist/cjs/utils/token";
 
 export const DEAL_STATE_SEED: string = "deal_state";
+export const CONFIG_SEED: string = "config";
+export const DISPUTE_SEED: string = "dispute";
+export const MILESTONES_SEED: string = "milestones";
+export const RECEIPT_SEED: string = "receipt";
+export const CLIENT_BOND_SEED: string = "client_bond";
+export const EXECUTOR_BOND_SEED: string = "executor_bond";
+export const CHECKER_PANEL_SEED: string = "checker_panel";
+export const FEE_SCHEDULE_SEED: string = "fee_schedule";
+export const HOLDER_STAKE_SEED: string = "holder_stake";
 
 export const ENCODER = anchor.utils.bytes.utf8;
 
@@ -16,6 +25,11 @@ export const SERVICE_FEE_OWNER: PublicKey = new PublicKey("C8pHACh7SAZWVZvgppM6V
 export const SERVICE_FEE_MINT: PublicKey = new PublicKey("62PtWFh2dQ69LKbHumBpMa7wG71r7i7Damwo2wMYfcR1");
 export const SERVICE_FEE_TA: PublicKey = getAssociatedTokenAddressSync(SERVICE_FEE_MINT, SERVICE_FEE_OWNER);
 
+// Native SOL deals are settled in lamports: the wallets and the deal state itself stand for the token accounts
+export const getTokenAccount = (mint: PublicKey, owner: PublicKey, allowOwnerOffCurve = false, programId = TOKEN_PROGRAM_ID): PublicKey => {
+  return mint.equals(NATIVE_MINT) ? owner : getAssociatedTokenAddressSync(mint, owner, allowOwnerOffCurve, programId)
+}
+
 export const HOLDER_MINT: PublicKey = new PublicKey("64esx9p99rgwzmBCFCaUDCKJL2b2WrgdFe7chyaDyrKD"); // FIXME
 
 export const DEAL_CONTRACT_PROGRAM_ID: PublicKey = new PublicKey("GKNkN4uDJWmidEC9h5Q9GQXNg48Go6q5bdnkDj6bSopz");
@@ -36,6 +50,76 @@ export function getDealStatePk(dealId: Buffer, clientPk: PublicKey, executorPk:
   ], programId ? programId : DEAL_CONTRACT_PROGRAM_ID)
 }
 
+export function getConfigPk(programId?: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([
+    ENCODER.encode(CONFIG_SEED),
+  ], programId ? programId : DEAL_CONTRACT_PROGRAM_ID)
+}
+
+export function getDisputePk(dealState: PublicKey, programId?: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([
+    ENCODER.encode(DISPUTE_SEED),
+    dealState.toBuffer(),
+  ], programId ? programId : DEAL_CONTRACT_PROGRAM_ID)
+}
+
+export function getDealMilestonesPk(dealState: PublicKey, programId?: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([
+    ENCODER.encode(MILESTONES_SEED),
+    dealState.toBuffer(),
+  ], programId ? programId : DEAL_CONTRACT_PROGRAM_ID)
+}
+
+export function getDealReceiptPk(dealState: PublicKey, programId?: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([
+    ENCODER.encode(RECEIPT_SEED),
+    dealState.toBuffer(),
+  ], programId ? programId : DEAL_CONTRACT_PROGRAM_ID)
+}
+
+export function getCheckerPanelPk(dealState: PublicKey, programId?: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([
+    ENCODER.encode(CHECKER_PANEL_SEED),
+    dealState.toBuffer(),
+  ], programId ? programId : DEAL_CONTRACT_PROGRAM_ID)
+}
+
+export function getFeeSchedulePk(mint: PublicKey, programId?: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([
+    ENCODER.encode(FEE_SCHEDULE_SEED),
+    mint.toBuffer(),
+  ], programId ? programId : DEAL_CONTRACT_PROGRAM_ID)
+}
+
+export function getHolderStakePk(owner: PublicKey, programId?: PublicKey): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync([
+    ENCODER.encode(HOLDER_STAKE_SEED),
+    owner.toBuffer(),
+  ], programId ? programId : DEAL_CONTRACT_PROGRAM_ID)
+}
+
+// `[checker, checkerDealTa]` of every panel member paid on settlement
+export function getPanelFeeAccounts(dealMint: PublicKey, checkers: PublicKey[], dealTokenProgram = TOKEN_PROGRAM_ID) {
+  return checkers.flatMap((checker) => [
+    { pubkey: checker, isSigner: false, isWritable: true },
+    { pubkey: getTokenAccount(dealMint, checker, false, dealTokenProgram), isSigner: false, isWritable: true },
+  ]);
+}
+
+// Each bond is kept in its own vault, native SOL bonds in the deal state itself
+export function getBondVaultPk(seed: string, mint: PublicKey, dealState: PublicKey, programId?: PublicKey): PublicKey {
+  return mint.equals(NATIVE_MINT) ? dealState : PublicKey.findProgramAddressSync([
+    ENCODER.encode(seed),
+    dealState.toBuffer(),
+  ], programId ? programId : DEAL_CONTRACT_PROGRAM_ID)[0]
+}
+
+export function getProgramDataPk(programId?: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync([
+    (programId ? programId : DEAL_CONTRACT_PROGRAM_ID).toBuffer(),
+  ], BPF_LOADER_UPGRADEABLE_PROGRAM_ID)[0]
+}
+
 export async function signAndSendIxs(
   connection: Connection,
   instructions: TransactionInstruction[], 
@@ -81,11 +165,147 @@ export const getTotalComputeIxs = (
   return [modifyComputeUnits, addPriorityFee];
 };
 
+export type ConfigArgs = IdlTypes<DealContract>["ConfigArgs"];
+
+export async function getInitConfigIx ({
+  dealContractProgram, authorityPk, payerPk, args
+}: {
+  dealContractProgram: Program<DealContract>,
+  authorityPk: PublicKey,
+  payerPk: PublicKey,
+  args: ConfigArgs,
+}) {
+  return dealContractProgram.methods.initConfig(args)
+  .accountsStrict({
+    authority: authorityPk,
+    payer: payerPk,
+    program: dealContractProgram.programId,
+    programData: getProgramDataPk(dealContractProgram.programId),
+    config: getConfigPk(dealContractProgram.programId)[0],
+    systemProgram: anchor.web3.SystemProgram.programId,
+  })
+}
+
+export async function getUpdateConfigIx ({
+  dealContractProgram, authorityPk, args
+}: {
+  dealContractProgram: Program<DealContract>,
+  authorityPk: PublicKey,
+  args: ConfigArgs,
+}) {
+  return dealContractProgram.methods.updateConfig(args)
+  .accountsStrict({
+    authority: authorityPk,
+    config: getConfigPk(dealContractProgram.programId)[0],
+  })
+}
+
+export type FeeScheduleArgs = IdlTypes<DealContract>["FeeScheduleArgs"];
+
+// `flags` are `PAUSE_*` bits of the program constants, e.g. `1 << 0` for `initialize`
+export async function getPauseIx ({
+  dealContractProgram, authorityPk, flags
+}: {
+  dealContractProgram: Program<DealContract>,
+  authorityPk: PublicKey,
+  flags: number,
+}) {
+  return dealContractProgram.methods.pause(flags)
+  .accountsStrict({
+    authority: authorityPk,
+    config: getConfigPk(dealContractProgram.programId)[0],
+  })
+}
+
+export async function getUnpauseIx ({
+  dealContractProgram, authorityPk, flags
+}: {
+  dealContractProgram: Program<DealContract>,
+  authorityPk: PublicKey,
+  flags: number,
+}) {
+  return dealContractProgram.methods.unpause(flags)
+  .accountsStrict({
+    authority: authorityPk,
+    config: getConfigPk(dealContractProgram.programId)[0],
+  })
+}
+
+export async function getSetFeeScheduleIx ({
+  dealContractProgram, authorityPk, payerPk, mint, args
+}: {
+  dealContractProgram: Program<DealContract>,
+  authorityPk: PublicKey,
+  payerPk: PublicKey,
+  mint: PublicKey,
+  args: FeeScheduleArgs,
+}) {
+  return dealContractProgram.methods.setFeeSchedule(args)
+  .accountsStrict({
+    authority: authorityPk,
+    payer: payerPk,
+    mint,
+    feeSchedule: getFeeSchedulePk(mint, dealContractProgram.programId)[0],
+    config: getConfigPk(dealContractProgram.programId)[0],
+    systemProgram: anchor.web3.SystemProgram.programId,
+  })
+}
+
+export async function getStakeIx ({
+  dealContractProgram, ownerPk, payerPk, amount
+}: {
+  dealContractProgram: Program<DealContract>,
+  ownerPk: PublicKey,
+  payerPk: PublicKey,
+  amount: number | BN,
+}) {
+  const holderStake = getHolderStakePk(ownerPk, dealContractProgram.programId)[0];
+  return dealContractProgram.methods.stake(new BN(amount))
+  .accountsStrict({
+    owner: ownerPk,
+    payer: payerPk,
+    holderMint: HOLDER_MINT,
+    ownerHolderTa: getTokenAccount(HOLDER_MINT, ownerPk),
+    holderStakeTa: getTokenAccount(HOLDER_MINT, holderStake, true),
+    holderStake,
+    config: getConfigPk(dealContractProgram.programId)[0],
+    associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    token2022Program: TOKEN_2022_PROGRAM_ID,
+    systemProgram: anchor.web3.SystemProgram.programId,
+  })
+}
+
+export async function getUnstakeIx ({
+  dealContractProgram, ownerPk, payerPk, amount
+}: {
+  dealContractProgram: Program<DealContract>,
+  ownerPk: PublicKey,
+  payerPk: PublicKey,
+  amount: number | BN,
+}) {
+  const holderStake = getHolderStakePk(ownerPk, dealContractProgram.programId)[0];
+  return dealContractProgram.methods.unstake(new BN(amount))
+  .accountsStrict({
+    owner: ownerPk,
+    payer: payerPk,
+    holderMint: HOLDER_MINT,
+    ownerHolderTa: getTokenAccount(HOLDER_MINT, ownerPk),
+    holderStakeTa: getTokenAccount(HOLDER_MINT, holderStake, true),
+    holderStake,
+    config: getConfigPk(dealContractProgram.programId)[0],
+    associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    token2022Program: TOKEN_2022_PROGRAM_ID,
+    systemProgram: anchor.web3.SystemProgram.programId,
+  })
+}
+
 export async function getInitializeIx ({
   dealContractProgram,
   dealId,
   amount,
-  serviceFee,
+  maxServiceFee,
   clientPk,
   executorPk,
   payerPk,
@@ -93,16 +313,18 @@ export async function getInitializeIx ({
   holderMode,
   deadline,
   withChecker = null,
+  withCheckerPanel = null,
   clientBond = null,
   executorBond = null,
+  advancePaymentAmount = 0,
+  forfeiture = { executorOnDeadline: false, checkerSlash: false },
+  milestones = [],
+  dealTokenProgram = TOKEN_PROGRAM_ID,
 }: {
   dealContractProgram: Program<DealContract>,
   dealId: string | Buffer,
   amount: number,
-  serviceFee: {
-      amount: number,
-      mint?: PublicKey
-  },
+  maxServiceFee?: number,
   clientPk: PublicKey,
   executorPk: PublicKey,
   payerPk: PublicKey,
@@ -113,28 +335,40 @@ export async function getInitializeIx ({
     checkerFee: BN,
     checkerKey: PublicKey
   },
+  withCheckerPanel?: {
+    checkerFee: BN,
+    checkers: PublicKey[],
+    threshold: number
+  },
   clientBond?: IdlTypes<DealContract>["Bond"],
   executorBond?: IdlTypes<DealContract>["Bond"],
+  advancePaymentAmount?: number,
+  forfeiture?: IdlTypes<DealContract>["BondForfeiture"],
+  milestones?: IdlTypes<DealContract>["MilestoneArgs"][],
+  dealTokenProgram?: PublicKey,
 }) {
   if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
   dealId = dealId as Buffer;
   const dealState = getDealStatePk(dealId, clientPk, executorPk)[0];
 
-  const dealStateDealTa = getAssociatedTokenAddressSync(dealMint, dealState, true);
-  const clientDealTa = getAssociatedTokenAddressSync(dealMint, clientPk);
-  const executorDealTa = getAssociatedTokenAddressSync(dealMint, executorPk);
+  const dealStateDealTa = getTokenAccount(dealMint, dealState, true, dealTokenProgram);
+  const clientDealTa = getTokenAccount(dealMint, clientPk, false, dealTokenProgram);
+  const executorDealTa = getTokenAccount(dealMint, executorPk, false, dealTokenProgram);
+
 
-  const serviceFeeTa = serviceFee.mint ? getAssociatedTokenAddressSync(serviceFee.mint, SERVICE_FEE_OWNER) : SERVICE_FEE_TA;
-  
   return dealContractProgram.methods.initialize({
     id: Array.from(dealId),
     dealAmount: new anchor.BN(amount),
-    serviceFee: new anchor.BN(serviceFee.amount),
+    serviceFee: maxServiceFee !== undefined ? new BN(maxServiceFee) : null,
     deadlineTs: deadline !== undefined ? new BN(deadline) : null,
     holderMode: !!holderMode,
-    checkerFee: !!withChecker ? withChecker.checkerFee : null,
+    checkerFee: !!withChecker ? withChecker.checkerFee : !!withCheckerPanel ? withCheckerPanel.checkerFee : null,
+    checkerPanel: !!withCheckerPanel ? { checkers: withCheckerPanel.checkers, threshold: withCheckerPanel.threshold } : null,
     clientBond: clientBond ? clientBond.amount : null,
     executorBond: executorBond ? executorBond.amount : null,
+    forfeiture,
+    advancePaymentAmount: new anchor.BN(advancePaymentAmount),
+    milestones,
   })
   .accountsStrict({
     client: clientPk,
@@ -145,35 +379,36 @@ export async function getInitializeIx ({
     dealMint,
     clientBondMint: clientBond ? clientBond.mint : dealMint,
     executorBondMint: executorBond ? executorBond.mint : dealMint,
-    serviceMint: !!serviceFee.mint ? serviceFee.mint : SERVICE_FEE_MINT,
 
-    clientHolderTa: holderMode ? getAssociatedTokenAddressSync(HOLDER_MINT, clientPk) : clientDealTa,
-    clientBondTa: clientBond ? getAssociatedTokenAddressSync(clientBond.mint, clientPk) : clientDealTa,
+    clientBondTa: clientBond ? getTokenAccount(clientBond.mint, clientPk) : clientDealTa,
     clientDealTa,
-    clientServiceTa: getAssociatedTokenAddressSync(!!serviceFee.mint ? serviceFee.mint : SERVICE_FEE_MINT, clientPk),
   
-    dealStateClientBondTa: clientBond ? getAssociatedTokenAddressSync(clientBond.mint, dealState, true) : dealStateDealTa,
+    dealStateClientBondTa: clientBond ? getBondVaultPk(CLIENT_BOND_SEED, clientBond.mint, dealState) : dealStateDealTa,
     dealStateDealTa,
-    dealStateExecutorBondTa: executorBond ? getAssociatedTokenAddressSync(executorBond.mint, dealState, true) : dealStateDealTa,
-    dealStateHolderTa: holderMode ? getAssociatedTokenAddressSync(HOLDER_MINT, dealState, true) : dealStateDealTa,
+    dealStateExecutorBondTa: executorBond ? getBondVaultPk(EXECUTOR_BOND_SEED, executorBond.mint, dealState) : dealStateDealTa,
   
-    executorBondTa: executorBond ? getAssociatedTokenAddressSync(executorBond.mint, executorPk) : executorDealTa,
+    executorBondTa: executorBond ? getTokenAccount(executorBond.mint, executorPk) : executorDealTa,
     executorDealTa,
   
     serviceFeeOwner: SERVICE_FEE_OWNER,
-    serviceFeeTa,
+    serviceFeeTa: getTokenAccount(dealMint, SERVICE_FEE_OWNER, false, dealTokenProgram),
   
     dealState,
-    holderMint: HOLDER_MINT,
+    holderStake: holderMode ? getHolderStakePk(clientPk)[0] : null,
+    dealMilestones: milestones.length > 0 ? getDealMilestonesPk(dealState)[0] : null,
+    checkerPanel: withCheckerPanel ? getCheckerPanelPk(dealState)[0] : null,
+    config: getConfigPk()[0],
+    feeSchedule: getFeeSchedulePk(dealMint)[0],
     associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
     tokenProgram: TOKEN_PROGRAM_ID,
+    token2022Program: TOKEN_2022_PROGRAM_ID,
     systemProgram: anchor.web3.SystemProgram.programId,
   }).preInstructions([getTotalComputeIxs(400000)[0]]);
 }
 
 
 export async function getCancelIx ({
-  dealContractProgram, initializer, dealId, clientPk, executorPk, payerPk, dealMint, checkerKey = null, clientBondMint, executorBondMint
+  dealContractProgram, initializer, dealId, clientPk, executorPk, payerPk, dealMint, checkerKey = null, clientBondMint, executorBondMint, withMilestones = false, withReceipt = false, panelCheckers = null, holderMode = false, dealTokenProgram = TOKEN_PROGRAM_ID
 }: {
   dealContractProgram: Program<DealContract>,
 
@@ -187,16 +422,21 @@ export async function getCancelIx ({
   checkerKey?: PublicKey,
   clientBondMint?: PublicKey,
   executorBondMint?: PublicKey,
+  withMilestones?: boolean,
+  withReceipt?: boolean,
+  panelCheckers?: PublicKey[],
+  holderMode?: boolean,
+  dealTokenProgram?: PublicKey,
 }) {
   if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
   dealId = dealId as Buffer;
   const dealState = getDealStatePk(dealId, clientPk, executorPk)[0];
 
-  const dealStateDealTa = getAssociatedTokenAddressSync(dealMint, dealState, true);
-  const clientDealTa = getAssociatedTokenAddressSync(dealMint, clientPk);
-  const executorDealTa = getAssociatedTokenAddressSync(dealMint, executorPk);
+  const dealStateDealTa = getTokenAccount(dealMint, dealState, true, dealTokenProgram);
+  const clientDealTa = getTokenAccount(dealMint, clientPk, false, dealTokenProgram);
+  const executorDealTa = getTokenAccount(dealMint, executorPk, false, dealTokenProgram);
 
-  const checkerDealTa = getAssociatedTokenAddressSync(dealMint, checkerKey ? checkerKey: initializer);
+  const checkerDealTa = getTokenAccount(dealMint, checkerKey ? checkerKey: initializer, false, dealTokenProgram);
 
   return dealContractProgram.methods.cancel()
   .accountsStrict({
@@ -211,20 +451,97 @@ export async function getCancelIx ({
     clientBondMint: clientBondMint ? clientBondMint : dealMint,
     executorBondMint: executorBondMint ? executorBondMint : dealMint,
 
-    clientBondTa: clientBondMint ? getAssociatedTokenAddressSync(clientBondMint, clientPk) : clientDealTa,
+    clientBondTa: clientBondMint ? getTokenAccount(clientBondMint, clientPk) : clientDealTa,
     clientDealTa,
   
-    dealStateClientBondTa: clientBondMint ? getAssociatedTokenAddressSync(clientBondMint, dealState, true) : dealStateDealTa,
+    dealStateClientBondTa: clientBondMint ? getBondVaultPk(CLIENT_BOND_SEED, clientBondMint, dealState) : dealStateDealTa,
     dealStateDealTa,
-    dealStateExecutorBondTa: executorBondMint ? getAssociatedTokenAddressSync(executorBondMint, dealState, true) : dealStateDealTa,
+    dealStateExecutorBondTa: executorBondMint ? getBondVaultPk(EXECUTOR_BOND_SEED, executorBondMint, dealState) : dealStateDealTa,
   
-    executorBondTa: executorBondMint ? getAssociatedTokenAddressSync(executorBondMint, executorPk) : executorDealTa,
+    executorBondTa: executorBondMint ? getTokenAccount(executorBondMint, executorPk) : executorDealTa,
+    clientExecutorBondTa: executorBondMint ? getTokenAccount(executorBondMint, clientPk) : clientDealTa,
   
+    serviceFee: SERVICE_FEE_OWNER,
     dealState,
+    dealMilestones: withMilestones ? getDealMilestonesPk(dealState)[0] : null,
+    dealReceipt: withReceipt ? getDealReceiptPk(dealState)[0] : null,
+    checkerPanel: panelCheckers ? getCheckerPanelPk(dealState)[0] : null,
+    holderStake: holderMode ? getHolderStakePk(clientPk)[0] : null,
+    config: getConfigPk()[0],
     associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
     tokenProgram: TOKEN_PROGRAM_ID,
+    token2022Program: TOKEN_2022_PROGRAM_ID,
     systemProgram: anchor.web3.SystemProgram.programId,
-  }).preInstructions([getTotalComputeIxs(400000)[0]])
+  })
+  .remainingAccounts(panelCheckers ? getPanelFeeAccounts(dealMint, panelCheckers, dealTokenProgram) : [])
+  .preInstructions([getTotalComputeIxs(400000)[0]])
+}
+
+// Returns the holder tokens a deal cancelled before `cancel` handled holder mode left behind
+export async function getRecoverHolderTokensIx ({
+  dealContractProgram, dealId, clientPk, executorPk, payerPk
+}: {
+  dealContractProgram: Program<DealContract>,
+  dealId: string | Buffer,
+  clientPk: PublicKey,
+  executorPk: PublicKey,
+  payerPk: PublicKey,
+}) {
+  if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
+  dealId = dealId as Buffer;
+  const dealState = getDealStatePk(dealId, clientPk, executorPk)[0];
+
+  return dealContractProgram.methods.recoverHolderTokens(Array.from(dealId))
+  .accountsStrict({
+    client: clientPk,
+    executor: executorPk,
+    payer: payerPk,
+    holderMint: HOLDER_MINT,
+    clientHolderTa: getTokenAccount(HOLDER_MINT, clientPk),
+    dealStateHolderTa: getTokenAccount(HOLDER_MINT, dealState, true),
+    dealState,
+    serviceFee: SERVICE_FEE_OWNER,
+    config: getConfigPk()[0],
+    associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    token2022Program: TOKEN_2022_PROGRAM_ID,
+    systemProgram: anchor.web3.SystemProgram.programId,
+  })
+}
+
+// Upgrades a deal created by the first version of the program, which kept the bonds in deal state token accounts
+export async function getMigrateDealIx ({
+  dealContractProgram, dealId, clientPk, executorPk, payerPk, dealMint, clientBondMint, executorBondMint
+}: {
+  dealContractProgram: Program<DealContract>,
+  dealId: string | Buffer,
+  clientPk: PublicKey,
+  executorPk: PublicKey,
+  payerPk: PublicKey,
+  dealMint: PublicKey,
+  clientBondMint?: PublicKey,
+  executorBondMint?: PublicKey,
+}) {
+  if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
+  dealId = dealId as Buffer;
+  const dealState = getDealStatePk(dealId, clientPk, executorPk)[0];
+  const dealStateDealTa = getTokenAccount(dealMint, dealState, true);
+
+  return dealContractProgram.methods.migrateDeal()
+  .accountsStrict({
+    payer: payerPk,
+    clientBondMint: clientBondMint ? clientBondMint : dealMint,
+    legacyClientBondTa: clientBondMint ? getTokenAccount(clientBondMint, dealState, true) : dealStateDealTa,
+    dealStateClientBondTa: clientBondMint ? getBondVaultPk(CLIENT_BOND_SEED, clientBondMint, dealState) : dealStateDealTa,
+    executorBondMint: executorBondMint ? executorBondMint : dealMint,
+    legacyExecutorBondTa: executorBondMint ? getTokenAccount(executorBondMint, dealState, true) : dealStateDealTa,
+    dealStateExecutorBondTa: executorBondMint ? getBondVaultPk(EXECUTOR_BOND_SEED, executorBondMint, dealState) : dealStateDealTa,
+    serviceFee: SERVICE_FEE_OWNER,
+    dealState,
+    config: getConfigPk()[0],
+    tokenProgram: TOKEN_PROGRAM_ID,
+    systemProgram: anchor.web3.SystemProgram.programId,
+  })
 }
 
 export async function getFinishIx ({
@@ -239,6 +556,10 @@ export async function getFinishIx ({
   checkerKey = null,
   clientBond = null,
   executorBond = null,
+  withMilestones = false,
+  withReceipt = false,
+  panelCheckers = null,
+  dealTokenProgram = TOKEN_PROGRAM_ID,
 }: {
   dealContractProgram: Program<DealContract>,
   dealId: string | Buffer,
@@ -251,15 +572,19 @@ export async function getFinishIx ({
   checkerKey?: PublicKey,
   clientBond?: IdlTypes<DealContract>["Bond"],
   executorBond?: IdlTypes<DealContract>["Bond"],
+  withMilestones?: boolean,
+  withReceipt?: boolean,
+  panelCheckers?: PublicKey[],
+  dealTokenProgram?: PublicKey,
 }) {
   if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
   dealId = dealId as Buffer;
   const dealState = getDealStatePk(dealId, clientPk, executorPk)[0];
 
-  const dealStateDealTa = getAssociatedTokenAddressSync(dealMint, dealState, true);
-  const clientDealTa = getAssociatedTokenAddressSync(dealMint, clientPk);
-  const executorDealTa = getAssociatedTokenAddressSync(dealMint, executorPk);
-  const checkerDealTa = checkerKey ? getAssociatedTokenAddressSync(dealMint, checkerKey) : executorDealTa;
+  const dealStateDealTa = getTokenAccount(dealMint, dealState, true, dealTokenProgram);
+  const clientDealTa = getTokenAccount(dealMint, clientPk, false, dealTokenProgram);
+  const executorDealTa = getTokenAccount(dealMint, executorPk, false, dealTokenProgram);
+  const checkerDealTa = checkerKey ? getTokenAccount(dealMint, checkerKey, false, dealTokenProgram) : executorDealTa;
 
   return dealContractProgram.methods.finish()
   .accountsStrict({
@@ -270,28 +595,450 @@ export async function getFinishIx ({
     checker: checkerKey ? checkerKey : initializer,
     payer: payerPk,
 
-    clientHolderTa: getAssociatedTokenAddressSync(HOLDER_MINT, clientPk),
 
     dealMint,
     clientBondMint: clientBond ? clientBond.mint : dealMint,
     executorBondMint: executorBond ? executorBond.mint : dealMint,
 
-    clientBondTa: clientBond ? getAssociatedTokenAddressSync(clientBond.mint, clientPk) : clientDealTa,
+    clientBondTa: clientBond ? getTokenAccount(clientBond.mint, clientPk) : clientDealTa,
   
-    dealStateClientBondTa: clientBond ? getAssociatedTokenAddressSync(clientBond.mint, dealState, true) : dealStateDealTa,
+    dealStateClientBondTa: clientBond ? getBondVaultPk(CLIENT_BOND_SEED, clientBond.mint, dealState) : dealStateDealTa,
     dealStateDealTa,
-    dealStateExecutorBondTa: executorBond ? getAssociatedTokenAddressSync(clientBond.mint, dealState, true) : dealStateDealTa,
-    dealStateHolderTa: holderMode ? getAssociatedTokenAddressSync(HOLDER_MINT, dealState, true) : dealStateDealTa,
+    dealStateExecutorBondTa: executorBond ? getBondVaultPk(EXECUTOR_BOND_SEED, executorBond.mint, dealState) : dealStateDealTa,
   
-    executorBondTa: executorBond ? getAssociatedTokenAddressSync(executorBond.mint, executorPk) : executorDealTa,
+    executorBondTa: executorBond ? getTokenAccount(executorBond.mint, executorPk) : executorDealTa,
     executorDealTa,
   
     dealState,
+    holderStake: holderMode ? getHolderStakePk(clientPk)[0] : null,
+    dealMilestones: withMilestones ? getDealMilestonesPk(dealState)[0] : null,
+    dealReceipt: withReceipt ? getDealReceiptPk(dealState)[0] : null,
+    checkerPanel: panelCheckers ? getCheckerPanelPk(dealState)[0] : null,
+    config: getConfigPk()[0],
 
-    holderMint: HOLDER_MINT,
     serviceFee: SERVICE_FEE_OWNER,
     associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
     tokenProgram: TOKEN_PROGRAM_ID,
+    token2022Program: TOKEN_2022_PROGRAM_ID,
+    systemProgram: anchor.web3.SystemProgram.programId,
+  })
+  .remainingAccounts(panelCheckers ? getPanelFeeAccounts(dealMint, panelCheckers, dealTokenProgram) : [])
+  .preInstructions([getTotalComputeIxs(400000)[0]])
+}
+
+export async function getOpenDisputeIx ({
+  dealContractProgram, initializer, dealId, clientPk, executorPk, payerPk, reasonHash
+}: {
+  dealContractProgram: Program<DealContract>,
+  initializer: PublicKey,
+  dealId: string | Buffer,
+  clientPk: PublicKey,
+  executorPk: PublicKey,
+  payerPk: PublicKey,
+  reasonHash: Buffer,
+}) {
+  if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
+  dealId = dealId as Buffer;
+  const dealState = getDealStatePk(dealId, clientPk, executorPk)[0];
+
+  return dealContractProgram.methods.openDispute({ reasonHash: Array.from(reasonHash) })
+  .accountsStrict({
+    initializer,
+    payer: payerPk,
+    dealState,
+    dispute: getDisputePk(dealState)[0],
+    config: getConfigPk()[0],
+    systemProgram: anchor.web3.SystemProgram.programId,
+  })
+}
+
+export async function getSubmitEvidenceIx ({
+  dealContractProgram, initializer, dealId, clientPk, executorPk, hash
+}: {
+  dealContractProgram: Program<DealContract>,
+  initializer: PublicKey,
+  dealId: string | Buffer,
+  clientPk: PublicKey,
+  executorPk: PublicKey,
+  hash: Buffer,
+}) {
+  if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
+  dealId = dealId as Buffer;
+  const dealState = getDealStatePk(dealId, clientPk, executorPk)[0];
+
+  return dealContractProgram.methods.submitEvidence({ hash: Array.from(hash) })
+  .accountsStrict({
+    initializer,
+    dealState,
+    dispute: getDisputePk(dealState)[0],
+    config: getConfigPk()[0],
+  })
+}
+
+export async function getResolveDisputeIx ({
+  dealContractProgram,
+  initializer,
+  disputeInitiator,
+  dealId,
+  clientPk,
+  executorPk,
+  payerPk,
+  dealMint,
+  holderMode,
+  checkerKey = null,
+  clientBondMint = null,
+  executorBondMint = null,
+  clientShareBps,
+  clientBondClientShareBps = 0,
+  executorBondClientShareBps = 0,
+  withMilestones = false,
+  withReceipt = false,
+  withCheckerPanel = false,
+}: {
+  dealContractProgram: Program<DealContract>,
+  initializer: PublicKey,
+  disputeInitiator: PublicKey,
+  dealId: string | Buffer,
+  clientPk: PublicKey,
+  executorPk: PublicKey,
+  payerPk: PublicKey,
+  dealMint: PublicKey,
+  holderMode?: boolean,
+  checkerKey?: PublicKey,
+  clientBondMint?: PublicKey,
+  executorBondMint?: PublicKey,
+  clientShareBps: number,
+  clientBondClientShareBps?: number,
+  executorBondClientShareBps?: number,
+  withMilestones?: boolean,
+  withReceipt?: boolean,
+  withCheckerPanel?: boolean,
+}) {
+  if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
+  dealId = dealId as Buffer;
+  const dealState = getDealStatePk(dealId, clientPk, executorPk)[0];
+
+  const dealStateDealTa = getTokenAccount(dealMint, dealState, true);
+  const clientDealTa = getTokenAccount(dealMint, clientPk);
+  const executorDealTa = getTokenAccount(dealMint, executorPk);
+
+  return dealContractProgram.methods.resolveDispute({
+    clientShareBps,
+    clientBondClientShareBps,
+    executorBondClientShareBps,
+  })
+  .accountsStrict({
+    initializer,
+    checker: checkerKey ? checkerKey : initializer,
+    client: clientPk,
+    executor: executorPk,
+    payer: payerPk,
+
+    dealStateDealTa,
+    clientDealTa,
+    executorDealTa,
+    checkerDealTa: checkerKey ? getTokenAccount(dealMint, checkerKey) : executorDealTa,
+
+    dealStateClientBondTa: clientBondMint ? getBondVaultPk(CLIENT_BOND_SEED, clientBondMint, dealState) : dealStateDealTa,
+    dealStateExecutorBondTa: executorBondMint ? getBondVaultPk(EXECUTOR_BOND_SEED, executorBondMint, dealState) : dealStateDealTa,
+    clientBondTa: clientBondMint ? getTokenAccount(clientBondMint, clientPk) : clientDealTa,
+    executorClientBondTa: clientBondMint ? getTokenAccount(clientBondMint, executorPk) : executorDealTa,
+    executorBondTa: executorBondMint ? getTokenAccount(executorBondMint, executorPk) : executorDealTa,
+    clientExecutorBondTa: executorBondMint ? getTokenAccount(executorBondMint, clientPk) : clientDealTa,
+
+
+    dealMint,
+    clientBondMint: clientBondMint ? clientBondMint : dealMint,
+    executorBondMint: executorBondMint ? executorBondMint : dealMint,
+
+    serviceFee: SERVICE_FEE_OWNER,
+    disputeInitiator,
+    dealState,
+    holderStake: holderMode ? getHolderStakePk(clientPk)[0] : null,
+    dispute: getDisputePk(dealState)[0],
+    dealMilestones: withMilestones ? getDealMilestonesPk(dealState)[0] : null,
+    dealReceipt: withReceipt ? getDealReceiptPk(dealState)[0] : null,
+    checkerPanel: withCheckerPanel ? getCheckerPanelPk(dealState)[0] : null,
+    config: getConfigPk()[0],
+    associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    token2022Program: TOKEN_2022_PROGRAM_ID,
     systemProgram: anchor.web3.SystemProgram.programId,
   }).preInstructions([getTotalComputeIxs(400000)[0]])
-}
\ No newline at end of file
+}
+
+export async function getCheckerVoteIx ({
+  dealContractProgram, checkerPk, dealId, clientPk, executorPk, vote
+}: {
+  dealContractProgram: Program<DealContract>,
+  checkerPk: PublicKey,
+  dealId: string | Buffer,
+  clientPk: PublicKey,
+  executorPk: PublicKey,
+  vote: IdlTypes<DealContract>["PanelVote"],
+}) {
+  if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
+  dealId = dealId as Buffer;
+  const dealState = getDealStatePk(dealId, clientPk, executorPk)[0];
+
+  return dealContractProgram.methods.checkerVote(vote)
+  .accountsStrict({
+    checker: checkerPk,
+    dealState,
+    checkerPanel: getCheckerPanelPk(dealState)[0],
+    config: getConfigPk()[0],
+  })
+}
+
+export async function getApproveMilestoneIx ({
+  dealContractProgram, initializer, dealId, clientPk, executorPk, payerPk, dealMint, index
+}: {
+  dealContractProgram: Program<DealContract>,
+  initializer: PublicKey,
+  dealId: string | Buffer,
+  clientPk: PublicKey,
+  executorPk: PublicKey,
+  payerPk: PublicKey,
+  dealMint: PublicKey,
+  index: number,
+}) {
+  if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
+  dealId = dealId as Buffer;
+  const dealState = getDealStatePk(dealId, clientPk, executorPk)[0];
+
+  return dealContractProgram.methods.approveMilestone({ index })
+  .accountsStrict({
+    initializer,
+    executor: executorPk,
+    payer: payerPk,
+    dealMint,
+    dealStateDealTa: getTokenAccount(dealMint, dealState, true),
+    executorDealTa: getTokenAccount(dealMint, executorPk),
+    dealState,
+    dealMilestones: getDealMilestonesPk(dealState)[0],
+    config: getConfigPk()[0],
+    systemProgram: anchor.web3.SystemProgram.programId,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    token2022Program: TOKEN_2022_PROGRAM_ID,
+    associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+  })
+}
+
+export async function getRejectMilestoneIx ({
+  dealContractProgram, initializer, dealId, clientPk, executorPk, payerPk, dealMint, index
+}: {
+  dealContractProgram: Program<DealContract>,
+  initializer: PublicKey,
+  dealId: string | Buffer,
+  clientPk: PublicKey,
+  executorPk: PublicKey,
+  payerPk: PublicKey,
+  dealMint: PublicKey,
+  index: number,
+}) {
+  if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
+  dealId = dealId as Buffer;
+  const dealState = getDealStatePk(dealId, clientPk, executorPk)[0];
+
+  return dealContractProgram.methods.rejectMilestone({ index })
+  .accountsStrict({
+    initializer,
+    client: clientPk,
+    payer: payerPk,
+    dealMint,
+    dealStateDealTa: getTokenAccount(dealMint, dealState, true),
+    clientDealTa: getTokenAccount(dealMint, clientPk),
+    dealState,
+    dealMilestones: getDealMilestonesPk(dealState)[0],
+    config: getConfigPk()[0],
+    systemProgram: anchor.web3.SystemProgram.programId,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    token2022Program: TOKEN_2022_PROGRAM_ID,
+    associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+  })
+}
+
+export async function getCreateOfferIx ({
+  dealContractProgram,
+  dealId,
+  amount,
+  maxServiceFee,
+  clientPk,
+  executorPk,
+  payerPk,
+  dealMint,
+  acceptanceDeadline,
+  deadline,
+  holderMode,
+  withChecker = null,
+  clientBond = null,
+  executorBond = null,
+  forfeiture = { executorOnDeadline: false, checkerSlash: false },
+}: {
+  dealContractProgram: Program<DealContract>,
+  dealId: string | Buffer,
+  amount: number,
+  maxServiceFee?: number,
+  clientPk: PublicKey,
+  executorPk: PublicKey,
+  payerPk: PublicKey,
+  dealMint: PublicKey,
+  acceptanceDeadline: number,
+  deadline?: number,
+  holderMode?: boolean,
+  withChecker?: {
+    checkerFee: BN,
+    checkerKey: PublicKey
+  },
+  clientBond?: IdlTypes<DealContract>["Bond"],
+  executorBond?: IdlTypes<DealContract>["Bond"],
+  forfeiture?: IdlTypes<DealContract>["BondForfeiture"],
+}) {
+  if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
+  dealId = dealId as Buffer;
+  const dealState = getDealStatePk(dealId, clientPk, executorPk)[0];
+
+  const dealStateDealTa = getTokenAccount(dealMint, dealState, true);
+  const clientDealTa = getTokenAccount(dealMint, clientPk);
+
+  return dealContractProgram.methods.createOffer({
+    id: Array.from(dealId),
+    dealAmount: new anchor.BN(amount),
+    serviceFee: maxServiceFee !== undefined ? new BN(maxServiceFee) : null,
+    deadlineTs: deadline !== undefined ? new BN(deadline) : null,
+    acceptanceDeadlineTs: new BN(acceptanceDeadline),
+    holderMode: !!holderMode,
+    checkerFee: !!withChecker ? withChecker.checkerFee : null,
+    clientBond: clientBond ? clientBond.amount : null,
+    executorBond: executorBond ? executorBond.amount : null,
+    forfeiture,
+  })
+  .accountsStrict({
+    client: clientPk,
+    executor: executorPk,
+    checker: withChecker ? withChecker.checkerKey : payerPk,
+    payer: payerPk,
+
+    dealMint,
+    clientBondMint: clientBond ? clientBond.mint : dealMint,
+    executorBondMint: executorBond ? executorBond.mint : dealMint,
+
+    serviceFeeOwner: SERVICE_FEE_OWNER,
+    serviceFeeTa: getTokenAccount(dealMint, SERVICE_FEE_OWNER),
+
+    clientDealTa,
+    dealStateDealTa,
+    clientBondTa: clientBond ? getTokenAccount(clientBond.mint, clientPk) : clientDealTa,
+    dealStateClientBondTa: clientBond ? getBondVaultPk(CLIENT_BOND_SEED, clientBond.mint, dealState) : dealStateDealTa,
+
+    dealState,
+    holderStake: holderMode ? getHolderStakePk(clientPk)[0] : null,
+    config: getConfigPk()[0],
+    feeSchedule: getFeeSchedulePk(dealMint)[0],
+    systemProgram: anchor.web3.SystemProgram.programId,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    token2022Program: TOKEN_2022_PROGRAM_ID,
+    associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+  }).preInstructions([getTotalComputeIxs(400000)[0]]);
+}
+
+export async function getAcceptOfferIx ({
+  dealContractProgram, dealId, clientPk, executorPk, payerPk, dealMint, executorBondMint
+}: {
+  dealContractProgram: Program<DealContract>,
+  dealId: string | Buffer,
+  clientPk: PublicKey,
+  executorPk: PublicKey,
+  payerPk: PublicKey,
+  dealMint: PublicKey,
+  executorBondMint?: PublicKey,
+}) {
+  if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
+  dealId = dealId as Buffer;
+  const dealState = getDealStatePk(dealId, clientPk, executorPk)[0];
+  const bondMint = executorBondMint ? executorBondMint : dealMint;
+
+  return dealContractProgram.methods.acceptOffer()
+  .accountsStrict({
+    executor: executorPk,
+    payer: payerPk,
+    executorBondMint: bondMint,
+    executorBondTa: getTokenAccount(bondMint, executorPk),
+    dealStateExecutorBondTa: getBondVaultPk(EXECUTOR_BOND_SEED, bondMint, dealState),
+    dealState,
+    config: getConfigPk()[0],
+    systemProgram: anchor.web3.SystemProgram.programId,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    token2022Program: TOKEN_2022_PROGRAM_ID,
+    associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+  })
+}
+
+export async function getAcceptRoleIx ({
+  dealContractProgram, dealId, clientPk, executorPk, checkerPk
+}: {
+  dealContractProgram: Program<DealContract>,
+  dealId: string | Buffer,
+  clientPk: PublicKey,
+  executorPk: PublicKey,
+  checkerPk: PublicKey,
+}) {
+  if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
+  dealId = dealId as Buffer;
+
+  return dealContractProgram.methods.acceptRole()
+  .accountsStrict({
+    checker: checkerPk,
+    dealState: getDealStatePk(dealId, clientPk, executorPk)[0],
+    config: getConfigPk()[0],
+  })
+}
+
+export async function getWithdrawOfferIx ({
+  dealContractProgram, dealId, clientPk, executorPk, payerPk, dealMint, holderMode, clientBondMint, executorBondMint
+}: {
+  dealContractProgram: Program<DealContract>,
+  dealId: string | Buffer,
+  clientPk: PublicKey,
+  executorPk: PublicKey,
+  payerPk: PublicKey,
+  dealMint: PublicKey,
+  holderMode?: boolean,
+  clientBondMint?: PublicKey,
+  executorBondMint?: PublicKey,
+}) {
+  if (!(dealId instanceof Buffer)) {dealId = uuidTodealIdBuf(dealId)}
+  dealId = dealId as Buffer;
+  const dealState = getDealStatePk(dealId, clientPk, executorPk)[0];
+
+  const dealStateDealTa = getTokenAccount(dealMint, dealState, true);
+  const clientDealTa = getTokenAccount(dealMint, clientPk);
+  const executorDealTa = getTokenAccount(dealMint, executorPk);
+
+  return dealContractProgram.methods.withdrawOffer()
+  .accountsStrict({
+    client: clientPk,
+    executor: executorPk,
+    payer: payerPk,
+
+    dealStateDealTa,
+    clientDealTa,
+    dealStateClientBondTa: clientBondMint ? getBondVaultPk(CLIENT_BOND_SEED, clientBondMint, dealState) : dealStateDealTa,
+    clientBondTa: clientBondMint ? getTokenAccount(clientBondMint, clientPk) : clientDealTa,
+    dealStateExecutorBondTa: executorBondMint ? getBondVaultPk(EXECUTOR_BOND_SEED, executorBondMint, dealState) : dealStateDealTa,
+    executorBondTa: executorBondMint ? getTokenAccount(executorBondMint, executorPk) : executorDealTa,
+
+    dealMint,
+    clientBondMint: clientBondMint ? clientBondMint : dealMint,
+    executorBondMint: executorBondMint ? executorBondMint : dealMint,
+
+    serviceFee: SERVICE_FEE_OWNER,
+    dealState,
+    holderStake: holderMode ? getHolderStakePk(clientPk)[0] : null,
+    config: getConfigPk()[0],
+    associatedTokenProgram: ASSOCIATED_PROGRAM_ID,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    token2022Program: TOKEN_2022_PROGRAM_ID,
+    systemProgram: anchor.web3.SystemProgram.programId,
+  }).preInstructions([getTotalComputeIxs(400000)[0]])
+}
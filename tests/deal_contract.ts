@@ -1,11 +1,11 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program, AnchorProvider, IdlTypes, BN } from "@coral-xyz/anchor";
 import { PublicKey, Keypair, Signer, SystemProgram, Transaction, Commitment, AddressLookupTableAccount, AddressLookupTableProgram, VersionedTransaction, VersionedMessage } from '@solana/web3.js';
-import { TOKEN_PROGRAM_ID, createMint, createAccount, mintTo, getAccount, DEFAULT_ACCOUNT_STATE_SIZE, getAssociatedTokenAddressSync } from "@solana/spl-token";
+import { TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, NATIVE_MINT, ExtensionType, createInitializeMintInstruction, createInitializeTransferFeeConfigInstruction, getMintLen, createMint, createAccount, mintTo, getAccount, DEFAULT_ACCOUNT_STATE_SIZE, getAssociatedTokenAddressSync } from "@solana/spl-token";
 import { DealContract, IDL as DC_IDL } from "../target/types/deal_contract";
 import { assert } from "chai";
 import { v4 as uuid } from 'uuid'
-import { DEAL_CONTRACT_PROGRAM_ID, getCancelIx, getDealStatePk, getFinishIx, getInitializeIx, getTotalComputeIxs, HOLDER_MINT, SERVICE_FEE_MINT, SERVICE_FEE_MINT_KP, SERVICE_FEE_OWNER, SERVICE_FEE_TA, signAndSendIxs as signAndSendIxs, uuidTodealIdBuf } from "./client";
+import { DEAL_CONTRACT_PROGRAM_ID, getApproveMilestoneIx, getCancelIx, getCreateOfferIx, getAcceptOfferIx, getAcceptRoleIx, getWithdrawOfferIx, getDealMilestonesPk, getDealReceiptPk, getRejectMilestoneIx, getConfigPk, getDealStatePk, getDisputePk, getFeeSchedulePk, getFinishIx, getInitConfigIx, getInitializeIx, getOpenDisputeIx, getResolveDisputeIx, getSetFeeScheduleIx, getSubmitEvidenceIx, getTotalComputeIxs, getUpdateConfigIx, HOLDER_MINT, SERVICE_FEE_MINT, SERVICE_FEE_MINT_KP, SERVICE_FEE_OWNER, SERVICE_FEE_TA, signAndSendIxs as signAndSendIxs, uuidTodealIdBuf } from "./client";
 import NodeWallet from "@coral-xyz/anchor/dist/cjs/nodewallet";
 import { ASSOCIATED_PROGRAM_ID } from "@coral-xyz/anchor/dist/cjs/utils/token";
 import './keys';
@@ -53,6 +53,18 @@ const program = new Program( DC_IDL as anchor.Idl, PROGRAM_ID, provider) as Prog
 
 describe("🤖 Tests Contractus smart-contract", () => {
   let addressLookupTablePk: PublicKey;
+
+  // Every deal mint needs a fee schedule, a flat one keeps the fee predictable
+  const setFlatFeeSchedule = async (mint: PublicKey, fee: number) => {
+    const instruction = (await getSetFeeScheduleIx({
+      dealContractProgram: program,
+      authorityPk: payerKp.publicKey,
+      payerPk: payerKp.publicKey,
+      mint,
+      args: { feeBps: 0, minFee: new BN(fee), maxFee: new BN(fee) },
+    })).instruction();
+    await signAndSendIxs(conn, [await instruction], [payerKp], payerKp)
+  };
   it("create addressLookupTable", async()=>{
     const alt = await conn.getAccountInfo(new PublicKey(ADDRESS_LOOKUP_TABLE_ADDRESS));
     if (alt.data.length > 0) { 
@@ -147,6 +159,104 @@ describe("🤖 Tests Contractus smart-contract", () => {
     );
   })
   
+  describe("⚙️ Program config", ()=> {
+    const configArgs = {
+      authority: payerKp.publicKey,
+      serviceAccount: serviceKp.publicKey,
+      serviceFeeOwner: SERVICE_FEE_OWNER,
+      holderMint: HOLDER_MINT,
+      holderTiers: [{ amount: new BN("10000000000000"), discountBps: 10_000 }],
+      maxAdvanceBps: 5_000,
+      reviewWindow: new BN(3 * 24 * 60 * 60),
+      holderStakeCooldown: new BN(7 * 24 * 60 * 60),
+    };
+
+    it("Try init config not by the upgrade authority", async () => {
+      try {
+        const instruction = (await getInitConfigIx({
+          dealContractProgram: program,
+          authorityPk: clientKp.publicKey,
+          payerPk: payerKp.publicKey,
+          args: configArgs,
+        })).instruction();
+        await signAndSendIxs(conn, [await instruction], [payerKp, clientKp], payerKp)
+        assert.ok(false)
+      } catch (error) {
+        assert.ok(true)
+      }
+    });
+
+    it("Init config", async () => {
+      const configPk = getConfigPk()[0];
+      if (await conn.getAccountInfo(configPk)) { return }
+
+      const instruction = (await getInitConfigIx({
+        dealContractProgram: program,
+        authorityPk: payerKp.publicKey,
+        payerPk: payerKp.publicKey,
+        args: configArgs,
+      })).instruction();
+      await signAndSendIxs(conn, [await instruction], [payerKp], payerKp)
+
+      const config = await program.account.programConfig.fetch(configPk, "processed");
+      assert.ok(config.authority.toBase58() == payerKp.publicKey.toBase58(), `invalid config.authority`)
+      assert.ok(config.serviceAccount.toBase58() == serviceKp.publicKey.toBase58(), `invalid config.serviceAccount`)
+      assert.ok(config.holderMint.toBase58() == HOLDER_MINT.toBase58(), `invalid config.holderMint`)
+      assert.ok(config.holderTiers[0].amount.toString() == configArgs.holderTiers[0].amount.toString(), `invalid config.holderTiers`)
+      assert.ok(config.maxAdvanceBps == configArgs.maxAdvanceBps, `invalid config.maxAdvanceBps`)
+      assert.ok(config.reviewWindow.toString() == configArgs.reviewWindow.toString(), `invalid config.reviewWindow`)
+    });
+
+    it("Try update config not by the config authority", async () => {
+      try {
+        const instruction = (await getUpdateConfigIx({
+          dealContractProgram: program,
+          authorityPk: clientKp.publicKey,
+          args: {...configArgs, authority: clientKp.publicKey},
+        })).instruction();
+        await signAndSendIxs(conn, [await instruction], [payerKp, clientKp], payerKp)
+        assert.ok(false)
+      } catch (error) {
+        assert.ok(true)
+      }
+    });
+
+    it("Update config", async () => {
+      const instruction = (await getUpdateConfigIx({
+        dealContractProgram: program,
+        authorityPk: payerKp.publicKey,
+        args: configArgs,
+      })).instruction();
+      await signAndSendIxs(conn, [await instruction], [payerKp], payerKp)
+
+      const config = await program.account.programConfig.fetch(getConfigPk()[0], "processed");
+      assert.ok(config.serviceFeeOwner.toBase58() == SERVICE_FEE_OWNER.toBase58(), `invalid config.serviceFeeOwner`)
+    });
+
+    it("Try set fee schedule not by the config authority", async () => {
+      try {
+        const instruction = (await getSetFeeScheduleIx({
+          dealContractProgram: program,
+          authorityPk: clientKp.publicKey,
+          payerPk: payerKp.publicKey,
+          mint: NATIVE_MINT,
+          args: { feeBps: 0, minFee: new BN(0), maxFee: new BN(0) },
+        })).instruction();
+        await signAndSendIxs(conn, [await instruction], [payerKp, clientKp], payerKp)
+        assert.ok(false)
+      } catch (error) {
+        assert.ok(true)
+      }
+    });
+
+    it("Set fee schedule", async () => {
+      await setFlatFeeSchedule(NATIVE_MINT, 5000);
+
+      const feeSchedule = await program.account.feeSchedule.fetch(getFeeSchedulePk(NATIVE_MINT)[0], "processed");
+      assert.ok(feeSchedule.minFee.toString() == "5000" && feeSchedule.maxFee.toString() == "5000", `invalid feeSchedule`)
+    });
+  })
+
   describe("👽️ Deals with third party checker (no performance bond)", ()=> {
     const clientDealTokenBalance = 10000;
     const otherTokenBalance = 500;
@@ -157,14 +267,11 @@ describe("🤖 Tests Contractus smart-contract", () => {
     let clientHolderTa: PublicKey;
     let executorDealTa: PublicKey;
 
-    const createDeal = async ({dealId, amount, serviceFee, withChecker, clientBond, executorBond, 
-      holderMode, signers, executor, client}: {
+    const createDeal = async ({dealId, amount, maxServiceFee, withChecker, clientBond, executorBond, 
+      holderMode, signers, executor, client, milestones}: {
       dealId: string | Buffer,
       amount: number,
-      serviceFee: {
-          amount: number,
-          mint?: PublicKey
-      },
+      maxServiceFee?: number,
       signers: Signer[],
       deadline?: number,
       withChecker?: {
@@ -176,12 +283,13 @@ describe("🤖 Tests Contractus smart-contract", () => {
       holderMode?: boolean,
       executor?: PublicKey,
       client?: PublicKey,
+      milestones?: IdlTypes<DealContract>["MilestoneArgs"][],
     }) => {
        const instruction = (await getInitializeIx({
         dealContractProgram: program,
         dealId,
         amount,
-        serviceFee,
+        maxServiceFee,
         clientPk: client ? client : clientKp.publicKey,
         executorPk: executor ? executor : executorKp.publicKey,
         payerPk: payerKp.publicKey,
@@ -192,7 +300,8 @@ describe("🤖 Tests Contractus smart-contract", () => {
           checkerFee: new BN(withChecker.checkerFee)
         } : undefined,
         clientBond,
-        executorBond      
+        executorBond,
+        milestones,
       })).instruction();
       return await signAndSendIxs(conn, [getTotalComputeIxs(400000)[0], await instruction], signers, payerKp, [await getAddressLookupTable()])
     };
@@ -211,6 +320,7 @@ describe("🤖 Tests Contractus smart-contract", () => {
         {commitment: COMMITMENT}
       );
       console.log(`dealMint: ${dealMint.toString()}`);
+      await setFlatFeeSchedule(dealMint, 50);
 
       clientDealTa = getAssociatedTokenAddressSync(dealMint, clientKp.publicKey);
       executorDealTa = getAssociatedTokenAddressSync(dealMint, executorKp.publicKey);
@@ -248,10 +358,7 @@ describe("🤖 Tests Contractus smart-contract", () => {
       await createDeal({
         dealId, 
         amount, 
-        serviceFee: {
-          amount: serviceFee,
-          mint: dealMint
-        }, 
+        maxServiceFee: serviceFee, 
         holderMode: false,
         withChecker: {
           checkerFee: new BN(checkerFee),
@@ -294,10 +401,7 @@ describe("🤖 Tests Contractus smart-contract", () => {
       const sig = await createDeal({
         dealId, 
         amount, 
-        serviceFee: {
-          amount: serviceFee,
-          mint: dealMint
-        }, 
+        maxServiceFee: serviceFee, 
         signers: [clientKp, executorKp, payerKp]
       });
      
@@ -330,10 +434,7 @@ describe("🤖 Tests Contractus smart-contract", () => {
         await createDeal({
           dealId, 
           amount, 
-          serviceFee: {
-            amount: serviceFee,
-            mint: dealMint
-          }, 
+          maxServiceFee: serviceFee, 
           signers: [clientKp, executorKp, payerKp]
         });
         assert.ok(false)
@@ -355,10 +456,7 @@ describe("🤖 Tests Contractus smart-contract", () => {
       await createDeal({
         dealId, 
         amount, 
-        serviceFee: {
-          amount: serviceFee,
-          mint: dealMint
-        }, 
+        maxServiceFee: serviceFee, 
         signers: [clientKp, executorKp, checkerKp, payerKp],
         withChecker: {
           checkerKey: checkerKp.publicKey,
@@ -372,8 +470,6 @@ describe("🤖 Tests Contractus smart-contract", () => {
       assert.ok(Number(dealStateDealTaInfoBefore.amount) == amount + checkerFee, 
         `invalid dealStateDealTaInfoBefore.amount. expected ${amount + checkerFee} got ${dealStateDealTaInfoBefore.amount}`)
 
-      const dealStateHolderTa = getAssociatedTokenAddressSync(HOLDER_MINT, dealStatePk, true);
-
       const checkerDealTa = getAssociatedTokenAddressSync(dealMint, checkerKp.publicKey);
       
       const instruction = (await getFinishIx({          
@@ -413,10 +509,7 @@ describe("🤖 Tests Contractus smart-contract", () => {
       await createDeal({
         dealId, 
         amount, 
-        serviceFee: {
-          amount: serviceFee,
-          mint: dealMint
-        }, 
+        maxServiceFee: serviceFee, 
         signers: [clientKp, executorKp, checkerKp, payerKp],
         withChecker: {
           checkerKey: checkerKp.publicKey,
@@ -468,10 +561,7 @@ describe("🤖 Tests Contractus smart-contract", () => {
       {
         dealId: uuid(),
         amount: 1000,
-        serviceFee: {
-          amount: 100,
-          mint: dealMint
-        }, 
+        maxServiceFee: 100, 
         client: clientKp.publicKey,
         executor: clientKp.publicKey,
         holderMode: false,
@@ -487,10 +577,7 @@ describe("🤖 Tests Contractus smart-contract", () => {
       createDeal({
         dealId: uuid(), 
         amount: 1000, 
-        serviceFee: {
-          amount: 0,
-          mint: dealMint
-        }, 
+        maxServiceFee: 0, 
         signers: [clientKp, executorKp]
       }).then(() => {
         assert.ok(false)
@@ -505,10 +592,7 @@ describe("🤖 Tests Contractus smart-contract", () => {
         await createDeal({
           dealId: uuid(),
           amount: 1000,
-        serviceFee: {
-          amount: 0,
-          mint: dealMint
-        }, 
+        maxServiceFee: 0, 
           withChecker: {
             checkerFee: 0,
             checkerKey: checkerKp.publicKey
@@ -528,10 +612,7 @@ describe("🤖 Tests Contractus smart-contract", () => {
         await createDeal({
           dealId: uuid(),
           amount: 0,
-        serviceFee: {
-          amount: 0,
-          mint: dealMint
-        }, 
+        maxServiceFee: 0, 
           signers: [clientKp, executorKp, checkerKp, payerKp],
           holderMode: false
         })
@@ -547,10 +628,7 @@ describe("🤖 Tests Contractus smart-contract", () => {
         await createDeal({
           dealId: uuid(),
           amount: 1000,
-        serviceFee: {
-          amount: 0,
-          mint: dealMint
-        }, 
+        maxServiceFee: 0, 
           signers: [clientKp, executorKp, checkerKp, payerKp],
           holderMode: false
         })
@@ -560,6 +638,601 @@ describe("🤖 Tests Contractus smart-contract", () => {
         assert.ok(true)
       }
     })
+
+    it("Open dispute, submit evidence and try finish", async () => {
+      const dealId = uuidTodealIdBuf(uuid())
+      const checkerFee = 100
+      const amount = 1000
+
+      await createDeal({
+        dealId,
+        amount,
+        maxServiceFee: 50,
+        signers: [clientKp, executorKp, checkerKp, payerKp],
+        withChecker: {
+          checkerKey: checkerKp.publicKey,
+          checkerFee: new BN(checkerFee)
+        }
+      });
+      const dealStatePk = getDealStatePk(dealId, clientKp.publicKey, executorKp.publicKey)[0];
+
+      const openIx = (await getOpenDisputeIx({
+        dealContractProgram: program,
+        initializer: executorKp.publicKey,
+        dealId,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+        reasonHash: Buffer.alloc(32, 1),
+      })).instruction();
+      await signAndSendIxs(conn, [await openIx], [payerKp, executorKp], payerKp)
+
+      const evidenceIx = (await getSubmitEvidenceIx({
+        dealContractProgram: program,
+        initializer: clientKp.publicKey,
+        dealId,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        hash: Buffer.alloc(32, 2),
+      })).instruction();
+      await signAndSendIxs(conn, [await evidenceIx], [payerKp, clientKp], payerKp)
+
+      const dealStateData = await program.account.dealState.fetch(dealStatePk, "processed");
+      assert.ok(dealStateData.status.disputed !== undefined, `invalid dealStateData.status`)
+      const disputeData = await program.account.dispute.fetch(getDisputePk(dealStatePk)[0], "processed");
+      assert.ok(disputeData.initiator.toBase58() == executorKp.publicKey.toBase58(), `invalid disputeData.initiator`)
+      assert.ok(disputeData.evidence.length == 2, `invalid disputeData.evidence.length. expected 2 got ${disputeData.evidence.length}`)
+
+      try {
+        const finishIx = (await getFinishIx({
+          initializer: clientKp.publicKey,
+          dealMint,
+          clientPk: clientKp.publicKey,
+          dealContractProgram: program,
+          dealId,
+          executorPk: executorKp.publicKey,
+          checkerKey: checkerKp.publicKey,
+          payerPk: payerKp.publicKey,
+        })).instruction()
+        await signAndSendIxs(conn, [await finishIx], [clientKp, payerKp], payerKp, [await getAddressLookupTable()])
+        assert.ok(false)
+      } catch (error) {
+        assert.ok(true)
+      }
+    });
+
+    it("Resolve dispute as checker with proportional split", async () => {
+      const dealId = uuidTodealIdBuf(uuid())
+      const checkerFee = 100
+      const amount = 1000
+      const clientShareBps = 3000
+
+      await createDeal({
+        dealId,
+        amount,
+        maxServiceFee: 50,
+        signers: [clientKp, executorKp, checkerKp, payerKp],
+        withChecker: {
+          checkerKey: checkerKp.publicKey,
+          checkerFee: new BN(checkerFee)
+        }
+      });
+      const dealStatePk = getDealStatePk(dealId, clientKp.publicKey, executorKp.publicKey)[0];
+
+      const openIx = (await getOpenDisputeIx({
+        dealContractProgram: program,
+        initializer: clientKp.publicKey,
+        dealId,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+        reasonHash: Buffer.alloc(32, 3),
+      })).instruction();
+      await signAndSendIxs(conn, [await openIx], [payerKp, clientKp], payerKp)
+
+      const clientDealTaInfoBefore = await getAccount(provider.connection, clientDealTa, "processed");
+      const executorDealTaInfoBefore = await getAccount(provider.connection, executorDealTa, "processed");
+
+      const resolveIx = (await getResolveDisputeIx({
+        dealContractProgram: program,
+        initializer: checkerKp.publicKey,
+        disputeInitiator: clientKp.publicKey,
+        dealId,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+        dealMint,
+        checkerKey: checkerKp.publicKey,
+        clientShareBps,
+      })).instruction();
+      await signAndSendIxs(conn, [await resolveIx], [payerKp, checkerKp], payerKp, [await getAddressLookupTable()])
+
+      const toClient = amount * clientShareBps / 10000;
+      const clientDealTaInfo = await getAccount(provider.connection, clientDealTa, "processed");
+      assert.ok((Number(clientDealTaInfoBefore.amount) + toClient).toString() == clientDealTaInfo.amount.toString(),
+        `invalid clientDealTaInfo.amount. expected ${Number(clientDealTaInfoBefore.amount) + toClient} got ${clientDealTaInfo.amount}`)
+      const executorDealTaInfo = await getAccount(provider.connection, executorDealTa, "processed");
+      assert.ok((Number(executorDealTaInfoBefore.amount) + amount - toClient).toString() == executorDealTaInfo.amount.toString(),
+        `invalid executorDealTaInfo.amount. expected ${Number(executorDealTaInfoBefore.amount) + amount - toClient} got ${executorDealTaInfo.amount}`)
+
+      assert.ok(await conn.getAccountInfo(dealStatePk, "processed") == null, `dealState hadn't been closed`)
+      assert.ok(await conn.getAccountInfo(getDisputePk(dealStatePk)[0], "processed") == null, `dispute hadn't been closed`)
+    });
+
+    it("Create deal with milestones, approve, reject and finish", async () => {
+      const dealId = uuidTodealIdBuf(uuid())
+      const checkerFee = 100
+      const milestones = [
+        { amount: new BN(300), deadlineTs: null, descriptionHash: Array.from(Buffer.alloc(32, 1)) },
+        { amount: new BN(300), deadlineTs: null, descriptionHash: Array.from(Buffer.alloc(32, 2)) },
+        { amount: new BN(400), deadlineTs: null, descriptionHash: Array.from(Buffer.alloc(32, 3)) },
+      ]
+      const amount = 1000
+
+      await createDeal({
+        dealId,
+        amount,
+        maxServiceFee: 50,
+        signers: [clientKp, executorKp, checkerKp, payerKp],
+        withChecker: {
+          checkerKey: checkerKp.publicKey,
+          checkerFee: new BN(checkerFee)
+        },
+        milestones,
+      });
+      const dealStatePk = getDealStatePk(dealId, clientKp.publicKey, executorKp.publicKey)[0];
+      const dealMilestonesPk = getDealMilestonesPk(dealStatePk)[0];
+
+      const executorDealTaInfoBefore = await getAccount(provider.connection, executorDealTa, "processed");
+      const clientDealTaInfoBefore = await getAccount(provider.connection, clientDealTa, "processed");
+
+      const approveIx = (await getApproveMilestoneIx({
+        dealContractProgram: program,
+        initializer: clientKp.publicKey,
+        dealId,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+        dealMint,
+        index: 0,
+      })).instruction();
+      await signAndSendIxs(conn, [await approveIx], [payerKp, clientKp], payerKp)
+
+      try {
+        await signAndSendIxs(conn, [await approveIx], [payerKp, clientKp], payerKp)
+        assert.ok(false)
+      } catch (error) {
+        assert.ok(true)
+      }
+
+      const rejectIx = (await getRejectMilestoneIx({
+        dealContractProgram: program,
+        initializer: checkerKp.publicKey,
+        dealId,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+        dealMint,
+        index: 1,
+      })).instruction();
+      await signAndSendIxs(conn, [await rejectIx], [payerKp, checkerKp], payerKp)
+
+      const dealStateData = await program.account.dealState.fetch(dealStatePk, "processed");
+      assert.ok(dealStateData.paidAmount.toString() == "300", `invalid dealStateData.paidAmount. expected 300 got ${dealStateData.paidAmount}`)
+      assert.ok(dealStateData.amount.toString() == "700", `invalid dealStateData.amount. expected 700 got ${dealStateData.amount}`)
+      const dealMilestonesData = await program.account.dealMilestones.fetch(dealMilestonesPk, "processed");
+      assert.ok(dealMilestonesData.milestones[0].status.approved !== undefined, `invalid milestones[0].status`)
+      assert.ok(dealMilestonesData.milestones[1].status.rejected !== undefined, `invalid milestones[1].status`)
+
+      const finishIx = (await getFinishIx({
+        initializer: clientKp.publicKey,
+        dealMint,
+        clientPk: clientKp.publicKey,
+        dealContractProgram: program,
+        dealId,
+        executorPk: executorKp.publicKey,
+        checkerKey: checkerKp.publicKey,
+        payerPk: payerKp.publicKey,
+        withMilestones: true,
+      })).instruction()
+      await signAndSendIxs(conn, [await finishIx], [clientKp, payerKp], payerKp, [await getAddressLookupTable()])
+
+      const executorDealTaInfo = await getAccount(provider.connection, executorDealTa, "processed");
+      assert.ok((Number(executorDealTaInfoBefore.amount) + 700).toString() == executorDealTaInfo.amount.toString(),
+        `invalid executorDealTaInfo.amount. expected ${Number(executorDealTaInfoBefore.amount) + 700} got ${executorDealTaInfo.amount}`)
+      const clientDealTaInfo = await getAccount(provider.connection, clientDealTa, "processed");
+      assert.ok((Number(clientDealTaInfoBefore.amount) + 300).toString() == clientDealTaInfo.amount.toString(),
+        `invalid clientDealTaInfo.amount. expected ${Number(clientDealTaInfoBefore.amount) + 300} got ${clientDealTaInfo.amount}`)
+      assert.ok(await conn.getAccountInfo(dealMilestonesPk, "processed") == null, `dealMilestones hadn't been closed`)
+    });
+
+    it("Try create deal with milestones not matching the deal amount", async () => {
+      try {
+        await createDeal({
+          dealId: uuid(),
+          amount: 1000,
+          maxServiceFee: 50,
+          signers: [clientKp, executorKp, payerKp],
+          milestones: [
+            { amount: new BN(300), deadlineTs: null, descriptionHash: Array.from(Buffer.alloc(32, 1)) },
+          ],
+        })
+        assert.ok(false)
+      } catch (error) {
+        assert.ok(true)
+      }
+    });
+  })
+
+  describe("🪙 Deals with Token-2022 mint (transfer fee)", ()=> {
+    const clientDealTokenBalance = 10000;
+    const transferFeeBps = 100;
+
+    const dealMintKp = Keypair.generate();
+    const dealMint = dealMintKp.publicKey;
+    let clientDealTa: PublicKey;
+
+    before( async()=>{
+      const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+      const tx = new Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: payerKp.publicKey,
+          newAccountPubkey: dealMint,
+          space: mintLen,
+          lamports: await conn.getMinimumBalanceForRentExemption(mintLen),
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        createInitializeTransferFeeConfigInstruction(dealMint, mintAuthorityKp.publicKey, mintAuthorityKp.publicKey, transferFeeBps, BigInt(1000000), TOKEN_2022_PROGRAM_ID),
+        createInitializeMintInstruction(dealMint, 0, mintAuthorityKp.publicKey, null, TOKEN_2022_PROGRAM_ID),
+      );
+      await provider.sendAndConfirm(tx, [payerKp, dealMintKp]);
+      await setFlatFeeSchedule(dealMint, 50);
+
+      clientDealTa = await createAccount(provider.connection, payerKp, dealMint, clientKp.publicKey, undefined, confirmOptions, TOKEN_2022_PROGRAM_ID);
+      await mintTo(provider.connection, mintAuthorityKp, dealMint, clientDealTa, mintAuthorityKp.publicKey, clientDealTokenBalance, undefined, confirmOptions, TOKEN_2022_PROGRAM_ID);
+    })
+
+    it("Create deal and finish with checker", async () => {
+      const dealId = uuidTodealIdBuf(uuid())
+      const checkerFee = 100
+      const amount = 1000
+      const serviceFee = 50
+
+      const instruction = (await getInitializeIx({
+        dealContractProgram: program,
+        dealId,
+        amount,
+        maxServiceFee: serviceFee,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+        dealMint,
+        holderMode: false,
+        withChecker: {
+          checkerKey: checkerKp.publicKey,
+          checkerFee: new BN(checkerFee)
+        },
+        dealTokenProgram: TOKEN_2022_PROGRAM_ID,
+      })).instruction();
+      await signAndSendIxs(conn, [getTotalComputeIxs(400000)[0], await instruction], [clientKp, executorKp, checkerKp, payerKp], payerKp, [await getAddressLookupTable()])
+
+      const dealStatePk = getDealStatePk(dealId, clientKp.publicKey, executorKp.publicKey)[0];
+      const dealStateDealTa = getAssociatedTokenAddressSync(dealMint, dealStatePk, true, TOKEN_2022_PROGRAM_ID);
+      const dealStateDealTaInfo = await getAccount(provider.connection, dealStateDealTa, "processed", TOKEN_2022_PROGRAM_ID);
+      assert.ok(Number(dealStateDealTaInfo.amount) == amount + checkerFee,
+        `the transfer fee must be covered by the client. expected ${amount + checkerFee} got ${dealStateDealTaInfo.amount}`)
+
+      const finishIx = (await getFinishIx({
+        initializer: checkerKp.publicKey,
+        dealMint,
+        clientPk: clientKp.publicKey,
+        dealContractProgram: program,
+        dealId,
+        executorPk: executorKp.publicKey,
+        checkerKey: checkerKp.publicKey,
+        payerPk: payerKp.publicKey,
+        dealTokenProgram: TOKEN_2022_PROGRAM_ID,
+      })).instruction()
+      await signAndSendIxs(conn, [await finishIx], [checkerKp, payerKp], payerKp, [await getAddressLookupTable()])
+
+      assert.ok(await conn.getAccountInfo(dealStateDealTa, "processed") == null, `dealStateDealTa hadn't been closed`)
+
+      const executorDealTa = getAssociatedTokenAddressSync(dealMint, executorKp.publicKey, false, TOKEN_2022_PROGRAM_ID);
+      const executorDealTaInfo = await getAccount(provider.connection, executorDealTa, "processed", TOKEN_2022_PROGRAM_ID);
+      const expected = amount - amount * transferFeeBps / 10000;
+      assert.ok(Number(executorDealTaInfo.amount) == expected,
+        `invalid executorDealTaInfo.amount. expected ${expected} got ${executorDealTaInfo.amount}`)
+    });
+  })
+
+  describe("◎ Deals in native SOL", ()=> {
+    it("Create deal and finish with checker", async () => {
+      const dealId = uuidTodealIdBuf(uuid())
+      const checkerFee = 100000
+      const amount = 1000000
+      const serviceFee = 5000
+
+      const dealStatePk = getDealStatePk(dealId, clientKp.publicKey, executorKp.publicKey)[0];
+
+      const instruction = (await getInitializeIx({
+        dealContractProgram: program,
+        dealId,
+        amount,
+        maxServiceFee: serviceFee,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+        dealMint: NATIVE_MINT,
+        holderMode: false,
+        withChecker: {
+          checkerKey: checkerKp.publicKey,
+          checkerFee: new BN(checkerFee)
+        },
+      })).instruction();
+      await signAndSendIxs(conn, [getTotalComputeIxs(400000)[0], await instruction], [clientKp, executorKp, checkerKp, payerKp], payerKp, [await getAddressLookupTable()])
+
+      const dealStateRent = await conn.getMinimumBalanceForRentExemption((await conn.getAccountInfo(dealStatePk, "processed")).data.length);
+      const dealStateLamports = await conn.getBalance(dealStatePk, "processed");
+      assert.ok(dealStateLamports == dealStateRent + amount + checkerFee,
+        `invalid deal state lamports. expected ${dealStateRent + amount + checkerFee} got ${dealStateLamports}`)
+
+      const executorLamportsBefore = await conn.getBalance(executorKp.publicKey, "processed");
+      const checkerLamportsBefore = await conn.getBalance(checkerKp.publicKey, "processed");
+
+      const finishIx = (await getFinishIx({
+        initializer: payerKp.publicKey,
+        dealMint: NATIVE_MINT,
+        clientPk: clientKp.publicKey,
+        dealContractProgram: program,
+        dealId,
+        executorPk: executorKp.publicKey,
+        checkerKey: checkerKp.publicKey,
+        payerPk: payerKp.publicKey,
+      })).instruction()
+      try {
+        await signAndSendIxs(conn, [await finishIx], [payerKp], payerKp, [await getAddressLookupTable()])
+        assert.ok(false)
+      } catch (error) {
+        assert.ok(true)
+      }
+
+      const checkerFinishIx = (await getFinishIx({
+        initializer: clientKp.publicKey,
+        dealMint: NATIVE_MINT,
+        clientPk: clientKp.publicKey,
+        dealContractProgram: program,
+        dealId,
+        executorPk: executorKp.publicKey,
+        checkerKey: checkerKp.publicKey,
+        payerPk: payerKp.publicKey,
+      })).instruction()
+      await signAndSendIxs(conn, [await checkerFinishIx], [clientKp, payerKp], payerKp, [await getAddressLookupTable()])
+
+      assert.ok(await conn.getAccountInfo(dealStatePk, "processed") == null, `dealState hadn't been closed`)
+      const executorLamportsAfter = await conn.getBalance(executorKp.publicKey, "processed");
+      assert.ok(executorLamportsAfter == executorLamportsBefore + amount,
+        `invalid executor lamports. expected ${executorLamportsBefore + amount} got ${executorLamportsAfter}`)
+      const checkerLamportsAfter = await conn.getBalance(checkerKp.publicKey, "processed");
+      assert.ok(checkerLamportsAfter == checkerLamportsBefore + checkerFee,
+        `invalid checker lamports. expected ${checkerLamportsBefore + checkerFee} got ${checkerLamportsAfter}`)
+    });
+    it("Emit DealCreated and DealFinished events", async () => {
+      const dealId = uuidTodealIdBuf(uuid())
+      const amount = 1000000
+      const serviceFee = 5000
+
+      const getEvents = async (signature: string) => {
+        const tx = await conn.getTransaction(signature, {commitment: "confirmed", maxSupportedTransactionVersion: 0});
+        const eventParser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+        return Array.from(eventParser.parseLogs(tx.meta.logMessages));
+      }
+
+      const instruction = (await getInitializeIx({
+        dealContractProgram: program,
+        dealId,
+        amount,
+        maxServiceFee: serviceFee,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+        dealMint: NATIVE_MINT,
+        holderMode: false,
+      })).instruction();
+      const initializeSignature = await signAndSendIxs(conn, [getTotalComputeIxs(400000)[0], await instruction], [clientKp, executorKp, payerKp], payerKp, [await getAddressLookupTable()]) as string
+
+      const [dealCreated] = (await getEvents(initializeSignature)).filter((event) => event.name == "DealCreated");
+      assert.ok(dealCreated !== undefined, `DealCreated event hadn't been emitted`)
+      assert.ok(Buffer.from(dealCreated.data.dealId as number[]).equals(dealId), `invalid DealCreated.dealId`)
+      assert.ok((dealCreated.data.client as PublicKey).equals(clientKp.publicKey), `invalid DealCreated.client`)
+      assert.ok((dealCreated.data.amount as BN).toNumber() == amount, `invalid DealCreated.amount`)
+
+      const finishIx = (await getFinishIx({
+        initializer: clientKp.publicKey,
+        dealMint: NATIVE_MINT,
+        clientPk: clientKp.publicKey,
+        dealContractProgram: program,
+        dealId,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+      })).instruction()
+      const finishSignature = await signAndSendIxs(conn, [await finishIx], [clientKp, payerKp], payerKp, [await getAddressLookupTable()]) as string
+
+      const [dealFinished] = (await getEvents(finishSignature)).filter((event) => event.name == "DealFinished");
+      assert.ok(dealFinished !== undefined, `DealFinished event hadn't been emitted`)
+      assert.ok((dealFinished.data.executor as PublicKey).equals(executorKp.publicKey), `invalid DealFinished.executor`)
+      assert.ok((dealFinished.data.payment as BN).toNumber() == amount, `invalid DealFinished.payment`)
+    });
+    it("Cancel deal keeping a receipt", async () => {
+      const dealId = uuidTodealIdBuf(uuid())
+      const amount = 1000000
+      const serviceFee = 5000
+
+      const dealStatePk = getDealStatePk(dealId, clientKp.publicKey, executorKp.publicKey)[0];
+
+      const instruction = (await getInitializeIx({
+        dealContractProgram: program,
+        dealId,
+        amount,
+        maxServiceFee: serviceFee,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+        dealMint: NATIVE_MINT,
+        holderMode: false,
+      })).instruction();
+      await signAndSendIxs(conn, [getTotalComputeIxs(400000)[0], await instruction], [clientKp, executorKp, payerKp], payerKp, [await getAddressLookupTable()])
+
+      const cancelIx = (await getCancelIx({
+        dealContractProgram: program,
+        initializer: executorKp.publicKey,
+        dealId,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+        dealMint: NATIVE_MINT,
+        withReceipt: true,
+      })).instruction()
+      await signAndSendIxs(conn, [await cancelIx], [executorKp, payerKp], payerKp, [await getAddressLookupTable()])
+
+      assert.ok(await conn.getAccountInfo(dealStatePk, "processed") == null, `dealState hadn't been closed`)
+      const dealReceipt = await program.account.dealReceipt.fetch(getDealReceiptPk(dealStatePk)[0], "processed");
+      assert.ok(dealReceipt.status.cancelled !== undefined, `receipt should record the cancellation`)
+      assert.ok(dealReceipt.settledBy.equals(executorKp.publicKey), `invalid dealReceipt.settledBy`)
+      assert.ok(dealReceipt.clientRefunded.toNumber() == amount,
+        `invalid dealReceipt.clientRefunded. expected ${amount} got ${dealReceipt.clientRefunded}`)
+      assert.ok(dealReceipt.executorReceived.toNumber() == 0, `invalid dealReceipt.executorReceived`)
+    });
+  })
+
+  describe("🤝 Offers with later acceptance", ()=> {
+    const amount = 1000000
+    const checkerFee = 100000
+    const serviceFee = 5000
+
+    const createOffer = async (dealId: Buffer, acceptanceDeadline: number) => {
+      const instruction = (await getCreateOfferIx({
+        dealContractProgram: program,
+        dealId,
+        amount,
+        maxServiceFee: serviceFee,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+        dealMint: NATIVE_MINT,
+        acceptanceDeadline,
+        holderMode: false,
+        withChecker: {
+          checkerKey: checkerKp.publicKey,
+          checkerFee: new BN(checkerFee)
+        },
+      })).instruction();
+      await signAndSendIxs(conn, [await instruction], [clientKp, payerKp], payerKp, [await getAddressLookupTable()])
+    }
+
+    it("Create offer, accept by executor and checker, finish", async () => {
+      const dealId = uuidTodealIdBuf(uuid())
+      const dealStatePk = getDealStatePk(dealId, clientKp.publicKey, executorKp.publicKey)[0];
+      await createOffer(dealId, Math.floor(Date.now() / 1000) + 60)
+
+      let dealState = await program.account.dealState.fetch(dealStatePk, "processed");
+      assert.ok(dealState.status.created !== undefined, `deal should be offered`)
+
+      const finishIx = async () => (await getFinishIx({
+        initializer: clientKp.publicKey,
+        dealMint: NATIVE_MINT,
+        clientPk: clientKp.publicKey,
+        dealContractProgram: program,
+        dealId,
+        executorPk: executorKp.publicKey,
+        checkerKey: checkerKp.publicKey,
+        payerPk: payerKp.publicKey,
+      })).instruction()
+      try {
+        await signAndSendIxs(conn, [await finishIx()], [clientKp, payerKp], payerKp, [await getAddressLookupTable()])
+        assert.ok(false)
+      } catch (error) {
+        assert.ok(true)
+      }
+
+      const acceptOfferIx = (await getAcceptOfferIx({
+        dealContractProgram: program,
+        dealId,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+        dealMint: NATIVE_MINT,
+      })).instruction()
+      await signAndSendIxs(conn, [await acceptOfferIx], [executorKp, payerKp], payerKp, [await getAddressLookupTable()])
+
+      dealState = await program.account.dealState.fetch(dealStatePk, "processed");
+      assert.ok(dealState.status.created !== undefined, `deal should wait for the checker`)
+
+      const acceptRoleIx = (await getAcceptRoleIx({
+        dealContractProgram: program,
+        dealId,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        checkerPk: checkerKp.publicKey,
+      })).instruction()
+      await signAndSendIxs(conn, [await acceptRoleIx], [checkerKp, payerKp], payerKp, [await getAddressLookupTable()])
+
+      dealState = await program.account.dealState.fetch(dealStatePk, "processed");
+      assert.ok(dealState.status.active !== undefined, `deal should be active`)
+
+      const executorLamportsBefore = await conn.getBalance(executorKp.publicKey, "processed");
+      await signAndSendIxs(conn, [await finishIx()], [clientKp, payerKp], payerKp, [await getAddressLookupTable()])
+
+      assert.ok(await conn.getAccountInfo(dealStatePk, "processed") == null, `dealState hadn't been closed`)
+      const executorLamportsAfter = await conn.getBalance(executorKp.publicKey, "processed");
+      assert.ok(executorLamportsAfter == executorLamportsBefore + amount,
+        `invalid executor lamports. expected ${executorLamportsBefore + amount} got ${executorLamportsAfter}`)
+    });
+
+    it("Withdraw offer after acceptance deadline", async () => {
+      const dealId = uuidTodealIdBuf(uuid())
+      const dealStatePk = getDealStatePk(dealId, clientKp.publicKey, executorKp.publicKey)[0];
+      await createOffer(dealId, Math.floor(Date.now() / 1000) + 2)
+
+      const withdrawOfferIx = async () => (await getWithdrawOfferIx({
+        dealContractProgram: program,
+        dealId,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+        dealMint: NATIVE_MINT,
+      })).instruction()
+      try {
+        await signAndSendIxs(conn, [await withdrawOfferIx()], [clientKp, payerKp], payerKp, [await getAddressLookupTable()])
+        assert.ok(false)
+      } catch (error) {
+        assert.ok(true)
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+
+      const acceptOfferIx = (await getAcceptOfferIx({
+        dealContractProgram: program,
+        dealId,
+        clientPk: clientKp.publicKey,
+        executorPk: executorKp.publicKey,
+        payerPk: payerKp.publicKey,
+        dealMint: NATIVE_MINT,
+      })).instruction()
+      try {
+        await signAndSendIxs(conn, [await acceptOfferIx], [executorKp, payerKp], payerKp, [await getAddressLookupTable()])
+        assert.ok(false)
+      } catch (error) {
+        assert.ok(true)
+      }
+
+      const clientLamportsBefore = await conn.getBalance(clientKp.publicKey, "processed");
+      await signAndSendIxs(conn, [await withdrawOfferIx()], [clientKp, payerKp], payerKp, [await getAddressLookupTable()])
+
+      assert.ok(await conn.getAccountInfo(dealStatePk, "processed") == null, `dealState hadn't been closed`)
+      const clientLamportsAfter = await conn.getBalance(clientKp.publicKey, "processed");
+      assert.ok(clientLamportsAfter > clientLamportsBefore + amount + checkerFee,
+        `client should get the deposit and the deal state rent back`)
+    });
   })
 
   // describe("👻 Deals with performance bond (no checker)", ()=> {